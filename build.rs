@@ -0,0 +1,4 @@
+// Re-embed migrations when they change (see `common::migrations`)
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
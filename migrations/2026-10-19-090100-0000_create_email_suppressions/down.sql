@@ -0,0 +1,2 @@
+ALTER TABLE users DROP COLUMN email_undeliverable;
+DROP TABLE email_suppressions;
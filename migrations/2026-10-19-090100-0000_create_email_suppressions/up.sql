@@ -0,0 +1,13 @@
+CREATE TABLE email_suppressions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    email VARCHAR NOT NULL UNIQUE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason VARCHAR NOT NULL,
+    description TEXT,
+    provider_event_id VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_suppressions_user_id ON email_suppressions(user_id);
+
+ALTER TABLE users ADD COLUMN email_undeliverable BOOLEAN NOT NULL DEFAULT FALSE;
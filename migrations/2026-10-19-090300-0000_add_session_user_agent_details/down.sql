@@ -0,0 +1,7 @@
+ALTER TABLE user_sessions
+    DROP COLUMN browser,
+    DROP COLUMN browser_version,
+    DROP COLUMN os,
+    DROP COLUMN os_version,
+    DROP COLUMN device_class,
+    DROP COLUMN engine;
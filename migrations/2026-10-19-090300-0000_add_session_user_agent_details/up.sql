@@ -0,0 +1,7 @@
+ALTER TABLE user_sessions
+    ADD COLUMN browser VARCHAR,
+    ADD COLUMN browser_version VARCHAR,
+    ADD COLUMN os VARCHAR,
+    ADD COLUMN os_version VARCHAR,
+    ADD COLUMN device_class VARCHAR,
+    ADD COLUMN engine VARCHAR;
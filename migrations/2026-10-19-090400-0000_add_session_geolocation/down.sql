@@ -0,0 +1,9 @@
+ALTER TABLE user_sessions
+    DROP COLUMN country_code,
+    DROP COLUMN country,
+    DROP COLUMN city,
+    DROP COLUMN asn,
+    DROP COLUMN as_org,
+    DROP COLUMN latitude,
+    DROP COLUMN longitude,
+    DROP COLUMN risk_score;
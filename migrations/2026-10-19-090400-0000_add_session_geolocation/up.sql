@@ -0,0 +1,9 @@
+ALTER TABLE user_sessions
+    ADD COLUMN country_code VARCHAR,
+    ADD COLUMN country VARCHAR,
+    ADD COLUMN city VARCHAR,
+    ADD COLUMN asn BIGINT,
+    ADD COLUMN as_org VARCHAR,
+    ADD COLUMN latitude DOUBLE PRECISION,
+    ADD COLUMN longitude DOUBLE PRECISION,
+    ADD COLUMN risk_score INTEGER NOT NULL DEFAULT 0;
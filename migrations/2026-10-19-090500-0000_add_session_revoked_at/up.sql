@@ -0,0 +1,3 @@
+ALTER TABLE user_sessions ADD COLUMN revoked_at TIMESTAMP;
+
+UPDATE user_sessions SET revoked_at = last_used_at WHERE is_revoked;
@@ -0,0 +1,9 @@
+-- Each running job worker records when it last polled, so readiness checks
+-- can tell whether anything is processing the queue
+CREATE TABLE job_workers (
+    id VARCHAR PRIMARY KEY,
+    started_at TIMESTAMP NOT NULL,
+    last_seen_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_job_workers_last_seen_at ON job_workers(last_seen_at);
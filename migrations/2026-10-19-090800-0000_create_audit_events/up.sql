@@ -0,0 +1,37 @@
+-- Security-relevant actions, kept as a hash chain: each row's hash covers its
+-- own fields and the previous row's hash, so editing or removing a row breaks
+-- every hash after it. The triggers below make the table append-only for
+-- everyone but its owner disabling them on purpose.
+CREATE TABLE audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    occurred_at TIMESTAMP NOT NULL,
+    action VARCHAR NOT NULL,
+    -- No foreign keys: the record outlives the users it mentions
+    actor_id UUID,
+    target_id UUID,
+    ip_address VARCHAR,
+    user_agent TEXT,
+    request_id VARCHAR,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    prev_hash VARCHAR(64) NOT NULL,
+    hash VARCHAR(64) NOT NULL UNIQUE
+);
+
+CREATE INDEX idx_audit_events_occurred_at ON audit_events(occurred_at);
+CREATE INDEX idx_audit_events_actor_id ON audit_events(actor_id);
+CREATE INDEX idx_audit_events_target_id ON audit_events(target_id);
+CREATE INDEX idx_audit_events_action ON audit_events(action);
+
+CREATE FUNCTION audit_events_reject_change() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_no_update_or_delete
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_reject_change();
+
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_reject_change();
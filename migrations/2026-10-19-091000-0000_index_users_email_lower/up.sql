@@ -0,0 +1,2 @@
+-- Backs the case-insensitive lookup by email
+CREATE INDEX idx_users_email_lower ON users (lower(email));
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+use actix_cors::Cors;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::header,
+    web, App, Error, HttpMessage,
+};
+use crate::common::{
+    clock::{Clock, SystemClock},
+    config::AppConfig,
+    id_generator::{IdGenerator, RandomIdGenerator},
+    live_config::LiveConfig,
+    database::{DbPool, DieselUnitOfWork},
+    health::{self, HealthRegistry},
+    middleware::{request_id, RequestId, RequestSpan, REQUEST_ID_HEADER},
+    unit_of_work::DynUnitOfWork,
+};
+use crate::modules;
+use crate::modules::audit::{
+    application::service::AuditService,
+    domain::repository::AuditRepository,
+    infrastructure::diesel_repository::DieselAuditRepository,
+    interfaces::http::handlers::AuditServiceImpl,
+};
+use crate::modules::auth::{
+    application::{service::AuthService, token_service::TokenService},
+    domain::repository::{verification::VerificationTokenRepository, AuthRepositories, SessionRepository},
+    infrastructure::{diesel_repository::DieselSessionRepository, diesel_token_repository::DieselVerificationTokenRepository},
+    interfaces::http::handlers::{AuthRepositoriesImpl, AuthServiceImpl},
+};
+use crate::modules::email::{
+    application::delivery_service::DeliveryEventService,
+    domain::{repository::SuppressionRepository, service::EmailService},
+    infrastructure::{diesel_repository::DieselSuppressionRepository, resend::ResendEmailService},
+    interfaces::http::handlers::DeliveryEventServiceImpl,
+};
+use crate::modules::posts::{
+    application::service::PostService,
+    domain::repository::PostRepository,
+    infrastructure::diesel_repository::DieselPostRepository,
+    interfaces::http::handlers::PostServiceImpl,
+};
+use crate::modules::users::{
+    application::{notification_service::NotificationPreferenceService, service::UserService},
+    domain::repository::{notification::NotificationPreferenceRepository, UserRepository},
+    infrastructure::{
+        diesel_notification_repository::DieselNotificationPreferenceRepository,
+        diesel_repository::DieselUserRepository,
+    },
+    interfaces::http::handlers::{NotificationPreferenceServiceImpl, UserServiceImpl},
+};
+
+/// The adapter behind every port the services use. The server runs with
+/// [`Adapters::diesel`]; tests replace single fields before building an
+/// [`AppState`] from them.
+#[derive(Clone)]
+pub struct Adapters {
+    pub users: Arc<dyn UserRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub verification_tokens: Arc<dyn VerificationTokenRepository>,
+    pub notification_preferences: Arc<dyn NotificationPreferenceRepository>,
+    pub suppressions: Arc<dyn SuppressionRepository>,
+    pub posts: Arc<dyn PostRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+    /// Must hand out repositories over the same store as the fields above.
+    pub auth_unit_of_work: Arc<dyn DynUnitOfWork<AuthRepositoriesImpl>>,
+    pub email: Arc<dyn EmailService>,
+    pub tokens: Arc<TokenService>,
+    pub clock: Arc<dyn Clock>,
+    pub ids: Arc<dyn IdGenerator>,
+    /// Probes the adapters above for `/health/ready`.
+    pub health: Arc<HealthRegistry>,
+}
+
+impl Adapters {
+    /// Postgres repositories, Resend for email, JWTs signed with the configured
+    /// keys, the system clock and random ids.
+    pub fn diesel(pool: &DbPool, live_config: &LiveConfig) -> Self {
+        let config = live_config.get();
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let mut health = HealthRegistry::new();
+        health::register(&mut health, pool);
+        modules::email::infrastructure::health::register(&mut health, live_config);
+        modules::jobs::infrastructure::health::register(&mut health, pool, &config, clock.clone());
+
+        let suppressions: Arc<dyn SuppressionRepository> = Arc::new(DieselSuppressionRepository::new(pool.clone()));
+        let notification_preferences: Arc<dyn NotificationPreferenceRepository> =
+            Arc::new(DieselNotificationPreferenceRepository::new(pool.clone()));
+        let auth_unit_of_work = DieselUnitOfWork::new(pool.clone(), |db| AuthRepositories {
+            users: Arc::new(DieselUserRepository::new(db.clone())) as Arc<dyn UserRepository>,
+            sessions: Arc::new(DieselSessionRepository::new(db.clone())) as Arc<dyn SessionRepository>,
+            tokens: Arc::new(DieselVerificationTokenRepository::new(db)) as Arc<dyn VerificationTokenRepository>,
+        });
+
+        Self {
+            users: Arc::new(DieselUserRepository::new(pool.clone())),
+            sessions: Arc::new(DieselSessionRepository::new(pool.clone())),
+            verification_tokens: Arc::new(DieselVerificationTokenRepository::new(pool.clone())),
+            email: Arc::new(ResendEmailService::new(live_config.clone(), suppressions.clone(), notification_preferences.clone(), clock.clone())),
+            notification_preferences,
+            suppressions,
+            posts: Arc::new(DieselPostRepository::new(pool.clone())),
+            audit: Arc::new(DieselAuditRepository::new(pool.clone())),
+            auth_unit_of_work: Arc::new(auth_unit_of_work),
+            tokens: Arc::new(TokenService::new(AppConfig::clone(&config))),
+            clock,
+            ids: Arc::new(RandomIdGenerator),
+            health: Arc::new(health),
+        }
+    }
+}
+
+/// The services the HTTP handlers share, built once at startup.
+#[derive(Clone)]
+pub struct AppState {
+    /// The configuration as loaded at startup.
+    pub config: AppConfig,
+    /// Follows reloads; see [`LiveConfig`] for which settings change.
+    pub live_config: LiveConfig,
+    pub auth: Arc<AuthServiceImpl>,
+    pub users: Arc<UserServiceImpl>,
+    pub notification_preferences: Arc<NotificationPreferenceServiceImpl>,
+    pub posts: Arc<PostServiceImpl>,
+    pub delivery_events: Arc<DeliveryEventServiceImpl>,
+    pub audit: Arc<AuditServiceImpl>,
+    pub email: Arc<dyn EmailService>,
+    // `tokens`, `sessions` and `clock` are for the `AuthenticatedUser`
+    // extractor, which runs outside `AuthService`
+    pub tokens: Arc<TokenService>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub clock: Arc<dyn Clock>,
+    pub health: Arc<HealthRegistry>,
+}
+
+impl AppState {
+    /// Wires the production adapters.
+    pub fn new(pool: DbPool, live_config: LiveConfig) -> Self {
+        let adapters = Adapters::diesel(&pool, &live_config);
+        Self::from_adapters(live_config, adapters)
+    }
+
+    pub fn from_adapters(live_config: LiveConfig, adapters: Adapters) -> Self {
+        let config = AppConfig::clone(&live_config.get());
+        let audit = Arc::new(AuditService::new(adapters.audit, adapters.clock.clone()));
+        let auth = AuthService::new(
+            adapters.users.clone(),
+            adapters.sessions.clone(),
+            adapters.verification_tokens.clone(),
+            adapters.email.clone(),
+            adapters.auth_unit_of_work,
+            adapters.tokens.clone(),
+            adapters.clock.clone(),
+            adapters.ids,
+            audit.clone(),
+            config.clone(),
+        );
+
+        Self {
+            auth: Arc::new(auth),
+            users: Arc::new(UserService::new(adapters.users.clone(), audit.clone())),
+            notification_preferences: Arc::new(NotificationPreferenceService::new(adapters.notification_preferences, adapters.clock.clone())),
+            posts: Arc::new(PostService::new(adapters.posts)),
+            delivery_events: Arc::new(DeliveryEventService::new(adapters.suppressions, adapters.users)),
+            audit,
+            email: adapters.email,
+            tokens: adapters.tokens,
+            sessions: adapters.sessions,
+            clock: adapters.clock,
+            health: adapters.health,
+            config,
+            live_config,
+        }
+    }
+}
+
+/// The application with every module's routes, as served by `main.rs`.
+pub fn build_app(
+    state: AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    let live_config = state.live_config.clone();
+    let cors = Cors::default()
+        .allowed_origin_fn(move |origin, _| origin.to_str().is_ok_and(|origin| live_config.get().allows_origin(origin)))
+        .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
+        .allowed_headers(vec![
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            // W3C trace context, so browser traces continue into the API
+            header::HeaderName::from_static("traceparent"),
+            header::HeaderName::from_static("tracestate"),
+            REQUEST_ID_HEADER,
+        ])
+        .expose_headers(vec![REQUEST_ID_HEADER])
+        .supports_credentials()
+        .max_age(state.config.cors.max_age_secs);
+
+    App::new()
+        .app_data(web::Data::new(state.config.clone()))
+        .app_data(web::Data::new(state))
+        .wrap(cors)
+        // Standard access log, plus the request id since it's written after the request's span closed
+        .wrap(
+            actix_web::middleware::Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{request_id}xi"#)
+                .custom_request_replace("request_id", |req| {
+                    req.extensions().get::<RequestId>().map(|id| id.as_str().to_string()).unwrap_or_default()
+                }),
+        )
+        .wrap(actix_web::middleware::from_fn(crate::common::metrics::record_http))
+        // Everything above runs inside the request's span, which carries the request id
+        .wrap(tracing_actix_web::TracingLogger::<RequestSpan>::new())
+        .wrap(actix_web::middleware::from_fn(request_id))
+        .configure(modules::auth::interfaces::http::routes::config)
+        // Ahead of users, whose scope would otherwise take /users/me/security-activity
+        .configure(modules::audit::interfaces::http::routes::config)
+        .configure(modules::users::interfaces::http::routes::config)
+        .configure(modules::posts::interfaces::http::routes::config)
+        .configure(modules::email::interfaces::http::routes::config)
+        .configure(crate::common::health::config)
+        .configure(crate::common::metrics::config)
+        .route("/", web::get().to(|| async { "Hello from Rust Hexagonal API!" }))
+}
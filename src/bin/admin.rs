@@ -0,0 +1,313 @@
+use std::io::BufRead;
+use std::process::ExitCode;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDateTime;
+use clap::{Parser, Subcommand};
+use rand::RngCore;
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+use validator::Validate;
+
+use rust_modular_hexagonal_api_template::{app::AppState, common, modules};
+use common::{config::{AppConfig, ConfigArgs}, live_config::LiveConfig, database::DbPool, errors::AppError, logging, migrations};
+use modules::auth::{
+    application::cleanup::AuthCleanupService,
+    infrastructure::{diesel_repository::DieselSessionRepository, diesel_token_repository::DieselVerificationTokenRepository},
+    interfaces::http::dto::RegisterUserDto,
+};
+use modules::users::domain::entity::User;
+
+/// Operational tasks against the application database.
+#[derive(Parser)]
+#[command(name = "admin")]
+struct Cli {
+    /// Print machine-readable JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a user without sending a verification email.
+    CreateUser {
+        #[arg(long)]
+        email: String,
+        /// Read from stdin when omitted, to keep it out of the shell history.
+        #[arg(long)]
+        password: Option<String>,
+        #[arg(long)]
+        locale: Option<String>,
+        /// Grant a role to the new user. Repeatable.
+        #[arg(long = "role")]
+        roles: Vec<String>,
+        /// Mark the email as verified.
+        #[arg(long)]
+        verified: bool,
+    },
+    /// Grant a role to a user.
+    GrantRole { email: String, role: String },
+    /// Revoke a role from a user.
+    RevokeRole { email: String, role: String },
+    /// Mark a user's email as verified.
+    VerifyUser { email: String },
+    /// Block a user from signing in and end their sessions.
+    Deactivate { email: String },
+    /// Sign a user out everywhere.
+    RevokeSessions { email: String },
+    /// List users, oldest first.
+    ListUsers {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+    /// Delete expired email verification and password reset tokens.
+    PurgeExpiredTokens,
+    /// Send a new email verification link.
+    ResendVerification { email: String },
+    /// Generate a new JWT_SECRET, retiring the current one to JWT_PREVIOUS_SECRETS.
+    RotateKeys {
+        /// How many retired secrets to keep accepting, newest first.
+        #[arg(long, default_value_t = 1)]
+        keep: usize,
+    },
+    /// Manage the database schema with the migrations embedded in this build.
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateAction {
+    /// Apply all pending migrations.
+    Up,
+    /// Revert the most recently applied migration.
+    Down,
+    /// Show which migrations are applied.
+    Status,
+}
+
+#[derive(Serialize)]
+struct UserRow {
+    id: Uuid,
+    email: String,
+    is_active: bool,
+    is_verified: bool,
+    roles: Vec<String>,
+    created_at: NaiveDateTime,
+    last_login_at: Option<NaiveDateTime>,
+}
+
+impl UserRow {
+    fn new(user: User, roles: Vec<String>) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            is_active: user.is_active,
+            is_verified: user.is_verified,
+            roles,
+            created_at: user.created_at,
+            last_login_at: user.last_login_at,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = AppConfig::init(&cli.config);
+    logging::init_cli();
+
+    let pool = common::database::init(&config);
+    let result = match cli.command {
+        Command::Migrate { action } => migrate(action, &pool, cli.json).map_err(|e| e.to_string()),
+        command => run(command, &pool, &config, cli.json).await.map_err(|e| e.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if cli.json {
+                eprintln!("{}", json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("error: {}", e);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, pool: &DbPool, config: &AppConfig, json: bool) -> Result<(), AppError> {
+    let state = AppState::new(pool.clone(), LiveConfig::new(config.clone()));
+    let (users, auth) = (&state.users, &state.auth);
+
+    match command {
+        Command::CreateUser { email, password, locale, roles, verified } => {
+            let password = match password {
+                Some(password) => password,
+                None => read_password()?,
+            };
+            let dto = RegisterUserDto { email, password, locale };
+            dto.validate()?;
+
+            let user = auth.create_user(dto.email, dto.password, dto.locale, verified).await?;
+            for role in &roles {
+                users.assign_role(user.id, role, None).await?;
+            }
+            let row = UserRow::new(user, roles);
+            let human = format!("Created user {} ({})", row.email, row.id);
+            print(json, &row, human);
+        }
+        Command::GrantRole { email, role } => {
+            let user = users.find_user_by_email(&email).await?;
+            users.assign_role(user.id, &role, None).await?;
+            print(json, &json!({ "user_id": user.id, "role": role }), format!("Granted {} to {}", role, email));
+        }
+        Command::RevokeRole { email, role } => {
+            let user = users.find_user_by_email(&email).await?;
+            users.remove_role(user.id, &role, None).await?;
+            print(json, &json!({ "user_id": user.id, "role": role }), format!("Revoked {} from {}", role, email));
+        }
+        Command::VerifyUser { email } => {
+            let user = users.find_user_by_email(&email).await?;
+            users.verify_user(user.id, None).await?;
+            print(json, &json!({ "user_id": user.id, "verified": true }), format!("Verified {}", email));
+        }
+        Command::Deactivate { email } => {
+            let user = users.find_user_by_email(&email).await?;
+            auth.deactivate_user(user.id).await?;
+            print(json, &json!({ "user_id": user.id, "active": false }), format!("Deactivated {} and revoked their sessions", email));
+        }
+        Command::RevokeSessions { email } => {
+            let user = users.find_user_by_email(&email).await?;
+            auth.revoke_all_sessions(user.id, None).await?;
+            print(json, &json!({ "user_id": user.id, "sessions_revoked": true }), format!("Revoked all sessions of {}", email));
+        }
+        Command::ListUsers { limit, offset } => {
+            let mut rows = Vec::new();
+            for user in users.list_users(limit, offset).await? {
+                let roles = users.get_roles(user.id).await?;
+                rows.push(UserRow::new(user, roles));
+            }
+            let human = user_table(&rows);
+            print(json, &rows, human);
+        }
+        Command::PurgeExpiredTokens => {
+            let cleanup = AuthCleanupService::new(
+                DieselSessionRepository::new(pool.clone()),
+                DieselVerificationTokenRepository::new(pool.clone()),
+                state.clock.clone(),
+                config.clone(),
+            );
+            let deleted = cleanup.purge_expired_tokens().await?;
+            print(json, &json!({ "deleted": deleted }), format!("Deleted {} expired tokens", deleted));
+        }
+        Command::ResendVerification { email } => {
+            auth.request_email_verification(&email).await?;
+            print(json, &json!({ "email": email, "sent": true }), format!("Sent a new verification link to {}", email));
+        }
+        Command::RotateKeys { keep } => {
+            let previous: Vec<&str> = std::iter::once(&config.auth.jwt_secret)
+                .chain(&config.auth.jwt_previous_secrets)
+                .map(|secret| secret.expose())
+                .take(keep)
+                .collect();
+            let rotation = json!({
+                "JWT_SECRET": generate_secret(),
+                "JWT_PREVIOUS_SECRETS": previous.join(","),
+            });
+            let human = format!(
+                "Set these in the environment of every instance and restart:\n\n\
+                 JWT_SECRET={}\nJWT_PREVIOUS_SECRETS={}\n\n\
+                 Retired secrets keep verifying existing tokens. Drop them once access tokens \
+                 ({} min) and emailed links signed with them no longer matter.",
+                rotation["JWT_SECRET"].as_str().unwrap_or_default(),
+                rotation["JWT_PREVIOUS_SECRETS"].as_str().unwrap_or_default(),
+                config.auth.access_token_minutes,
+            );
+            print(json, &rotation, human);
+        }
+        Command::Migrate { .. } => unreachable!("handled in main"),
+    }
+
+    Ok(())
+}
+
+fn migrate(action: MigrateAction, pool: &DbPool, json: bool) -> Result<(), migrations::MigrationError> {
+    match action {
+        MigrateAction::Up => {
+            let applied = migrations::run_pending(pool)?;
+            let human = match applied.is_empty() {
+                true => "Database is up to date".to_string(),
+                false => applied.iter().map(|name| format!("Applied {}", name)).collect::<Vec<_>>().join("\n"),
+            };
+            print(json, &json!({ "applied": applied }), human);
+        }
+        MigrateAction::Down => {
+            let reverted = migrations::revert_last(pool)?;
+            print(json, &json!({ "reverted": reverted }), format!("Reverted {}", reverted));
+        }
+        MigrateAction::Status => {
+            let status = migrations::status(pool)?;
+            let human = status.iter()
+                .map(|m| format!("[{}] {}", if m.applied { "x" } else { " " }, m.name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            print(json, &status, human);
+        }
+    }
+    Ok(())
+}
+
+fn print<T: Serialize>(json: bool, value: &T, human: String) {
+    if json {
+        match serde_json::to_string_pretty(value) {
+            Ok(output) => println!("{}", output),
+            Err(e) => eprintln!("error: failed to serialize output: {}", e),
+        }
+    } else {
+        println!("{}", human);
+    }
+}
+
+fn user_table(rows: &[UserRow]) -> String {
+    let mut table = format!("{:<36}  {:<32}  {:<6}  {:<8}  {:<19}  {}", "ID", "EMAIL", "ACTIVE", "VERIFIED", "CREATED", "ROLES");
+    for row in rows {
+        table.push_str(&format!(
+            "\n{:<36}  {:<32}  {:<6}  {:<8}  {:<19}  {}",
+            row.id,
+            row.email,
+            if row.is_active { "yes" } else { "no" },
+            if row.is_verified { "yes" } else { "no" },
+            row.created_at.format("%Y-%m-%d %H:%M:%S"),
+            row.roles.join(","),
+        ));
+    }
+    table
+}
+
+fn read_password() -> Result<String, AppError> {
+    eprintln!("Password (read from stdin):");
+    let mut password = String::new();
+    std::io::stdin().lock().read_line(&mut password).map_err(|e| {
+        tracing::error!("Failed to read password: {}", e);
+        AppError::InternalError
+    })?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// 256 random bits, base64url encoded.
+fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use clap::Parser;
+use rust_modular_hexagonal_api_template::{common, modules};
+use common::{clock::SystemClock, config::{AppConfig, ConfigArgs}, live_config::{self, LiveConfig}, logging};
+
+/// Runs background jobs without the HTTP server.
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+/// Runs the background job worker without the HTTP server, so jobs can be
+/// scaled separately. Set `JOBS_IN_PROCESS=false` on the API when using it.
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let config = AppConfig::init(&cli.config);
+    let _logging = logging::init(&config);
+
+    common::user_agent_parser::init(config.auth.user_agent_rules_path.as_deref())
+        .expect("Failed to load user agent rules");
+    common::geoip::init(&config).expect("Failed to open GeoIP databases");
+
+    let pool = common::database::init(&config);
+    if let Err(e) = common::migrations::prepare_on_startup(&pool, &config) {
+        tracing::error!("{}. Run `admin migrate up` or set RUN_MIGRATIONS_ON_BOOT=true.", e);
+        std::process::exit(1);
+    }
+
+    // SIGHUP reloads the log filter and feature flags, as in the API
+    let live_config = LiveConfig::new(config);
+    live_config::reload_on_sighup(live_config.clone(), cli.config);
+    let worker = modules::jobs::infrastructure::worker::worker(&pool, &live_config, Arc::new(SystemClock));
+
+    tokio::select! {
+        _ = worker.run() => {}
+        _ = tokio::signal::ctrl_c() => tracing::info!("Shutting down job worker"),
+    }
+}
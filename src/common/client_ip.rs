@@ -0,0 +1,175 @@
+use std::fmt;
+use std::future::{ready, Ready};
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::{dev::Payload, http::header::HeaderMap, web, FromRequest, HttpRequest};
+use ipnet::IpNet;
+use crate::common::{config::{AppConfig, ForwardedHeader}, errors::AppError};
+
+/// The real address of the client, resolved through trusted reverse proxies.
+///
+/// Only the `FORWARDED_HEADER` the proxies set is read, and only when the TCP
+/// peer is in `TRUSTED_PROXIES`; the chain is then walked from the nearest
+/// hop back and the first untrusted address wins. Clients can prepend to that
+/// header or send the others, but neither changes the result.
+///
+/// Extraction fails when the connection has no peer address, e.g. on a Unix
+/// socket or in a `TestRequest`; take `Option<ClientIp>` where that's fine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl fmt::Display for ClientIp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for ClientIp {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let config = req.app_data::<web::Data<AppConfig>>();
+        let trusted = config.map(|config| config.server.trusted_proxies.as_slice()).unwrap_or_default();
+        let header = config.map(|config| config.server.forwarded_header).unwrap_or_default();
+
+        ready(match req.peer_addr() {
+            Some(peer) => Ok(ClientIp(resolve(peer.ip(), req.headers(), trusted, header))),
+            None => {
+                tracing::warn!("Request has no peer address to resolve the client IP from");
+                Err(AppError::InternalError)
+            }
+        })
+    }
+}
+
+/// Resolves the client address from the peer and the forwarding header it set.
+pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted: &[IpNet], header: ForwardedHeader) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+
+    let hops = match header {
+        ForwardedHeader::XForwardedFor => header_list(headers, "X-Forwarded-For"),
+        ForwardedHeader::XRealIp => header_list(headers, "X-Real-IP"),
+        ForwardedHeader::Forwarded => forwarded_chain(headers),
+    };
+
+    let mut client = peer;
+    for hop in hops.unwrap_or_default().iter().rev() {
+        // An unknown or obfuscated hop ends what we can vouch for
+        let Some(ip) = parse_node(hop) else { break };
+        client = ip;
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// The `for=` nodes of every `Forwarded` element, in order.
+fn forwarded_chain(headers: &HeaderMap) -> Option<Vec<String>> {
+    let nodes: Vec<String> = header_values(headers, "Forwarded")
+        .flat_map(|value| value.split(',').map(str::to_string).collect::<Vec<_>>())
+        .filter_map(|element| {
+            element.split(';')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                .map(|(_, node)| node.trim().trim_matches('"').to_string())
+        })
+        .collect();
+
+    Some(nodes).filter(|n| !n.is_empty())
+}
+
+fn header_list(headers: &HeaderMap, name: &str) -> Option<Vec<String>> {
+    let hops: Vec<String> = header_values(headers, name)
+        .flat_map(|value| value.split(',').map(|hop| hop.trim().to_string()).collect::<Vec<_>>())
+        .filter(|hop| !hop.is_empty())
+        .collect();
+
+    Some(hops).filter(|h| !h.is_empty())
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).filter_map(|value| value.to_str().ok())
+}
+
+/// Parses `1.2.3.4`, `1.2.3.4:5678`, `2001:db8::1` or `[2001:db8::1]:5678`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    node.parse::<IpAddr>().ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| node.strip_prefix('[')?.strip_suffix(']')?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(HeaderName::from_static(name), HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    fn trusted() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap(), "2001:db8::/32".parse().unwrap()]
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ignores_headers_from_untrusted_peer() {
+        let spoofed = headers(&[("x-forwarded-for", "1.1.1.1")]);
+        assert_eq!(resolve(ip("203.0.113.9"), &spoofed, &trusted(), ForwardedHeader::XForwardedFor), ip("203.0.113.9"));
+        assert_eq!(resolve(ip("10.0.0.1"), &spoofed, &[], ForwardedHeader::XForwardedFor), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_x_forwarded_for_skips_trusted_hops() {
+        // The client prepended a fake address; only the hop our proxy saw counts
+        let chain = headers(&[("x-forwarded-for", "1.1.1.1, 203.0.113.9"), ("x-forwarded-for", "10.0.0.2")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &chain, &trusted(), ForwardedHeader::XForwardedFor), ip("203.0.113.9"));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.4")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &real_ip, &trusted(), ForwardedHeader::XRealIp), ip("198.51.100.4"));
+    }
+
+    #[test]
+    fn test_only_the_configured_header_is_read() {
+        // The proxy appended to X-Forwarded-For and passed the client's own headers through
+        let spoofed = headers(&[
+            ("forwarded", "for=1.2.3.4"),
+            ("x-real-ip", "5.6.7.8"),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+        assert_eq!(resolve(ip("10.0.0.1"), &spoofed, &trusted(), ForwardedHeader::XForwardedFor), ip("203.0.113.9"));
+
+        // A proxy that only sets X-Real-IP passes a client-sent X-Forwarded-For along
+        let spoofed = headers(&[("x-forwarded-for", "1.1.1.1"), ("x-real-ip", "198.51.100.4")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &spoofed, &trusted(), ForwardedHeader::XRealIp), ip("198.51.100.4"));
+
+        // Without the configured header the peer is all we know
+        let missing = headers(&[("forwarded", "for=1.2.3.4")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &missing, &trusted(), ForwardedHeader::XForwardedFor), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_forwarded_header() {
+        let chain = headers(&[
+            ("forwarded", r#"for=192.0.2.60;proto=http, for="[2001:db8:cafe::17]:4711""#),
+            ("x-forwarded-for", "1.1.1.1"),
+        ]);
+        assert_eq!(resolve(ip("10.0.0.1"), &chain, &trusted(), ForwardedHeader::Forwarded), ip("192.0.2.60"));
+
+        // Obfuscated identifiers stop the walk at the last known hop
+        let hidden = headers(&[("forwarded", "for=_hidden, for=10.0.0.7")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &hidden, &trusted(), ForwardedHeader::Forwarded), ip("10.0.0.7"));
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{NaiveDateTime, Utc};
+
+/// Where services get the current time, so expiry logic can be tested with a
+/// frozen clock. Times are naive UTC, like every timestamp in the schema.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    now: std::sync::Arc<std::sync::Mutex<NaiveDateTime>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl FakeClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self { now: std::sync::Arc::new(std::sync::Mutex::new(now)) }
+    }
+
+    /// Frozen at the current system time, truncated to whole seconds like JWT timestamps.
+    pub fn frozen() -> Self {
+        let now = chrono::DateTime::from_timestamp(Utc::now().timestamp(), 0).expect("valid timestamp");
+        Self::new(now.naive_utc())
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Clock for FakeClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.now.lock().unwrap()
+    }
+}
@@ -1,11 +1,142 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
 use diesel::pg::PgConnection;
-use diesel::r2d2::{self, ConnectionManager};
+use diesel::r2d2::{self, ConnectionManager, PooledConnection};
+use crate::common::{config::AppConfig, errors::AppError, metrics::PoolMetrics, unit_of_work::UnitOfWork};
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
+type DbConnection = PooledConnection<ConnectionManager<PgConnection>>;
 
-pub fn init(database_url: &str) -> DbPool {
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+/// Where a repository sends its queries: a fresh pooled connection per call,
+/// or the single connection of an open transaction.
+#[derive(Clone)]
+pub enum DbHandle {
+    Pool(DbPool),
+    Transaction(Arc<Mutex<DbConnection>>),
+}
+
+impl From<DbPool> for DbHandle {
+    fn from(pool: DbPool) -> Self {
+        DbHandle::Pool(pool)
+    }
+}
+
+pub fn init(config: &AppConfig) -> DbPool {
+    let manager = ConnectionManager::<PgConnection>::new(config.database.url.expose());
     r2d2::Pool::builder()
+        .max_size(config.database.pool_size)
+        .connection_timeout(Duration::from_secs(config.database.pool_timeout_secs))
+        .event_handler(Box::new(PoolMetrics::new(config.database.pool_size)))
         .build(manager)
         .expect("Failed to create database pool")
 }
+
+/// Runs synchronous Diesel work on the blocking thread pool, so repositories
+/// never stall the async runtime while waiting for a connection or a query.
+///
+/// Failing to get a connection within `DATABASE_POOL_TIMEOUT_SECS` (pool
+/// exhausted or database down) is reported as `ServiceUnavailable`.
+pub async fn run<T, F>(db: &DbHandle, query: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut PgConnection) -> Result<T, AppError> + Send + 'static,
+{
+    let db = db.clone();
+    // Timed from here, so the span also covers waiting for a blocking thread
+    let span = tracing::info_span!("db.query", db.system = "postgresql", db.pool_wait_ms = tracing::field::Empty);
+    blocking(move || {
+        let _entered = span.enter();
+        match db {
+            DbHandle::Pool(pool) => {
+                let started = Instant::now();
+                let mut conn = checkout(&pool)?;
+                span.record("db.pool_wait_ms", started.elapsed().as_secs_f64() * 1000.0);
+                query(&mut conn)
+            }
+            DbHandle::Transaction(conn) => query(&mut **lock(&conn)?),
+        }
+    })
+    .await
+}
+
+/// Runs repository calls in one transaction. `repositories` builds the
+/// repositories handed to each unit of work, all bound to its connection.
+pub struct DieselUnitOfWork<R> {
+    pool: DbPool,
+    repositories: fn(DbHandle) -> R,
+}
+
+impl<R> DieselUnitOfWork<R> {
+    pub fn new(pool: DbPool, repositories: fn(DbHandle) -> R) -> Self {
+        Self { pool, repositories }
+    }
+}
+
+#[async_trait]
+impl<R: Send> UnitOfWork for DieselUnitOfWork<R> {
+    type Repositories = R;
+
+    async fn transaction<T, F, Fut>(&self, work: F) -> Result<T, AppError>
+    where
+        T: Send,
+        F: FnOnce(R) -> Fut + Send,
+        Fut: Future<Output = Result<T, AppError>> + Send,
+    {
+        let pool = self.pool.clone();
+        let conn = blocking(move || {
+            let mut conn = checkout(&pool)?;
+            AnsiTransactionManager::begin_transaction(&mut *conn)?;
+            Ok(Arc::new(Mutex::new(conn)))
+        })
+        .await?;
+
+        // If this future is dropped before finishing, the connection goes back
+        // to the pool inside the open transaction and r2d2 discards it, which
+        // rolls the transaction back.
+        let result = work((self.repositories)(DbHandle::Transaction(conn.clone()))).await;
+
+        let commit = result.is_ok();
+        blocking(move || {
+            let mut conn = lock(&conn)?;
+            if commit {
+                AnsiTransactionManager::commit_transaction(&mut **conn)?;
+            } else if let Err(e) = AnsiTransactionManager::rollback_transaction(&mut **conn) {
+                tracing::error!("Failed to roll back transaction: {}", e);
+            }
+            Ok(())
+        })
+        .await?;
+
+        result
+    }
+}
+
+async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database task panicked: {}", e);
+            AppError::InternalError
+        })?
+}
+
+fn checkout(pool: &DbPool) -> Result<DbConnection, AppError> {
+    pool.get().map_err(|e| {
+        tracing::warn!("Failed to get DB connection: {}", e);
+        AppError::ServiceUnavailable("Database is busy, try again shortly".to_string())
+    })
+}
+
+fn lock(conn: &Mutex<DbConnection>) -> Result<std::sync::MutexGuard<'_, DbConnection>, AppError> {
+    conn.lock().map_err(|_| {
+        tracing::error!("Transaction connection poisoned by a panicking query");
+        AppError::InternalError
+    })
+}
@@ -1,14 +1,19 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{http::{header, StatusCode}, HttpResponse, ResponseError};
 use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 use validator::ValidationErrors;
 
+use super::middleware::current_request_id;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Validation error: {0}")]
     ValidationError(#[from] ValidationErrors),
 
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
     #[error("Resource not found: {0}")]
     NotFound(String),
 
@@ -26,6 +31,9 @@ pub enum AppError {
 
     #[error("Conflict: {0}")]
     Conflict(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
 }
 
 #[derive(Serialize)]
@@ -35,12 +43,16 @@ struct ErrorResponse {
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     details: Option<serde_json::Value>,
+    /// Matches the `X-Request-Id` response header, for quoting in bug reports.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl ResponseError for AppError {
     fn status_code(&self) -> StatusCode {
         match self {
             AppError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
 
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
@@ -48,6 +60,7 @@ impl ResponseError for AppError {
             AppError::Conflict(_) => StatusCode::CONFLICT,
             AppError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
@@ -59,11 +72,17 @@ impl ResponseError for AppError {
             _ => None,
         };
 
-        HttpResponse::build(status_code).json(ErrorResponse {
+        let mut response = HttpResponse::build(status_code);
+        if let AppError::ServiceUnavailable(_) = self {
+            response.insert_header((header::RETRY_AFTER, "1"));
+        }
+
+        response.json(ErrorResponse {
             code: status_code.as_u16(),
             error: status_code.canonical_reason().unwrap_or("Unknown").to_string(),
             message: self.to_string(),
             details,
+            request_id: current_request_id().map(|id| id.as_str().to_string()),
         })
     }
 }
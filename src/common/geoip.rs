@@ -0,0 +1,158 @@
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use maxminddb::{geoip2, MaxMindDbError, Reader};
+use crate::common::config::AppConfig;
+
+static GEOIP: OnceLock<GeoIp> = OnceLock::new();
+
+/// Where an IP address is, as far as the configured databases know.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2 code, e.g. `GB`.
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub asn: Option<i64>,
+    /// Organization owning the autonomous system, usually the ISP.
+    pub as_org: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl GeoLocation {
+    /// Human-readable place such as `"London, United Kingdom"`.
+    pub fn display_name(&self) -> Option<String> {
+        match (&self.city, &self.country) {
+            (Some(city), Some(country)) => Some(format!("{}, {}", city, country)),
+            (city, country) => city.clone().or_else(|| country.clone()),
+        }
+    }
+
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+}
+
+/// Offline IP geolocation backed by MaxMind-format (`.mmdb`) City and ASN
+/// databases, e.g. GeoLite2-City and GeoLite2-ASN. Either may be missing.
+pub struct GeoIp {
+    city: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIp {
+    pub fn new(city: Option<Reader<Vec<u8>>>, asn: Option<Reader<Vec<u8>>>) -> Self {
+        Self { city, asn }
+    }
+
+    pub fn open(city_path: Option<&str>, asn_path: Option<&str>) -> Result<Self, MaxMindDbError> {
+        let city = city_path.map(Reader::open_readfile).transpose()?;
+        let asn = asn_path.map(Reader::open_readfile).transpose()?;
+        Ok(Self::new(city, asn))
+    }
+
+    /// Returns `None` for unparsable or unknown addresses (including private ranges).
+    pub fn lookup(&self, ip_address: &str) -> Option<GeoLocation> {
+        let ip: IpAddr = ip_address.parse().ok()?;
+        let mut location = GeoLocation::default();
+
+        if let Some(record) = self.city.as_ref().and_then(|r| decode::<geoip2::City>(r, ip)) {
+            location.country_code = record.country.iso_code.map(str::to_string);
+            location.country = record.country.names.english.map(str::to_string);
+            location.city = record.city.names.english.map(str::to_string);
+            location.latitude = record.location.latitude;
+            location.longitude = record.location.longitude;
+        }
+
+        if let Some(record) = self.asn.as_ref().and_then(|r| decode::<geoip2::Asn>(r, ip)) {
+            location.asn = record.autonomous_system_number.map(i64::from);
+            location.as_org = record.autonomous_system_organization.map(str::to_string);
+        }
+
+        Some(location).filter(|l| *l != GeoLocation::default())
+    }
+}
+
+fn decode<'a, T: serde::Deserialize<'a>>(reader: &'a Reader<Vec<u8>>, ip: IpAddr) -> Option<T> {
+    reader.lookup(ip)
+        .and_then(|result| result.decode())
+        .unwrap_or_else(|e| {
+            tracing::warn!("GeoIP lookup for {} failed: {}", ip, e);
+            None
+        })
+}
+
+/// Opens the databases configured in `GEOIP_CITY_DB_PATH` / `GEOIP_ASN_DB_PATH` once at startup.
+pub fn init(config: &AppConfig) -> Result<(), MaxMindDbError> {
+    let geoip = GeoIp::open(config.auth.geoip_city_db_path.as_deref(), config.auth.geoip_asn_db_path.as_deref())?;
+    if GEOIP.set(geoip).is_err() {
+        tracing::warn!("GeoIP databases were already loaded, ignoring reload");
+    }
+    Ok(())
+}
+
+/// Looks `ip_address` up in the databases loaded by [`init`].
+pub fn lookup(ip_address: &str) -> Option<GeoLocation> {
+    GEOIP.get()?.lookup(ip_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use maxminddb_writer::{metadata::IpVersion, paths::IpAddrWithMask, Database};
+    use serde_json::json;
+    use super::*;
+
+    fn build<T: serde::Serialize>(database_type: &str, records: &[(&str, T)]) -> Reader<Vec<u8>> {
+        let mut db = Database::default();
+        db.metadata.ip_version = IpVersion::V6;
+        db.metadata.database_type = database_type.to_string();
+        db.metadata.binary_format_major_version = 2;
+        for (network, record) in records {
+            let data = db.insert_value(record).unwrap();
+            db.insert_node(network.parse::<IpAddrWithMask>().unwrap(), data);
+        }
+
+        Reader::from_source(db.write_to(Vec::new()).unwrap()).unwrap()
+    }
+
+    /// A tiny City + ASN database: `81.2.69.0/24` is London (AS20712) and
+    /// `89.160.20.0/24` is Linköping (AS29518).
+    fn fixture() -> GeoIp {
+        let city = build("GeoIP2-City", &[
+            ("::81.2.69.0/120", json!({
+                "city": { "names": { "en": "London" } },
+                "country": { "iso_code": "GB", "names": { "en": "United Kingdom" } },
+                "location": { "latitude": 51.5142, "longitude": -0.0931 },
+            })),
+            ("::89.160.20.0/120", json!({
+                "city": { "names": { "en": "Linköping" } },
+                "country": { "iso_code": "SE", "names": { "en": "Sweden" } },
+                "location": { "latitude": 58.4167, "longitude": 15.6167 },
+            })),
+        ]);
+        // Typed so the ASN is encoded as uint32 like in MaxMind's databases
+        let asn = build("GeoLite2-ASN", &[
+            ("::81.2.69.0/120", geoip2::Asn { autonomous_system_number: Some(20712), autonomous_system_organization: Some("Andrews & Arnold Ltd") }),
+            ("::89.160.20.0/120", geoip2::Asn { autonomous_system_number: Some(29518), autonomous_system_organization: Some("Bredband2 AB") }),
+        ]);
+
+        GeoIp::new(Some(city), Some(asn))
+    }
+
+    #[test]
+    fn test_lookup_city_and_asn() {
+        let geoip = fixture();
+
+        let location = geoip.lookup("81.2.69.142").unwrap();
+        assert_eq!(location.country_code.as_deref(), Some("GB"));
+        assert_eq!(location.display_name().as_deref(), Some("London, United Kingdom"));
+        assert_eq!(location.asn, Some(20712));
+        assert_eq!(location.as_org.as_deref(), Some("Andrews & Arnold Ltd"));
+        assert_eq!(location.coordinates(), Some((51.5142, -0.0931)));
+
+        assert!(geoip.lookup("127.0.0.1").is_none());
+        assert!(geoip.lookup("not an ip").is_none());
+        assert!(GeoIp::new(None, None).lookup("81.2.69.142").is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! Liveness and readiness probes.
+//!
+//! `/health/live` only says the process answers HTTP. `/health/ready` runs
+//! every registered [`HealthCheck`] and returns 503 unless all of them pass,
+//! with a JSON breakdown per component. Modules add their own checks next to
+//! their adapters; see `Adapters::diesel`.
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpResponse};
+use async_trait::async_trait;
+use diesel::RunQueryDsl;
+use serde::Serialize;
+
+use crate::app::AppState;
+use super::{database::{self, DbHandle, DbPool}, errors::AppError, migrations};
+
+/// A check that takes longer than this counts as failed.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Probes one dependency. `Ok` carries an optional detail for the report,
+/// `Err` the reason the component is down.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// The component's key in the report, e.g. `database`.
+    fn name(&self) -> &'static str;
+    async fn check(&self) -> Result<Option<String>, String>;
+}
+
+/// Adapts a closure returning a future, for checks that only call into an adapter.
+pub struct FnHealthCheck<F> {
+    name: &'static str,
+    check: F,
+}
+
+impl<F, Fut> FnHealthCheck<F>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Option<String>, String>> + Send + 'static,
+{
+    pub fn new(name: &'static str, check: F) -> Self {
+        Self { name, check }
+    }
+}
+
+#[async_trait]
+impl<F, Fut> HealthCheck for FnHealthCheck<F>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Option<String>, String>> + Send + 'static,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn check(&self) -> Result<Option<String>, String> {
+        (self.check)().await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub duration_ms: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<&'static str, ComponentHealth>,
+}
+
+/// The checks `/health/ready` runs.
+#[derive(Default)]
+pub struct HealthRegistry {
+    checks: Vec<Arc<dyn HealthCheck>>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, check: impl HealthCheck + 'static) -> &mut Self {
+        let name = check.name();
+        if self.checks.iter().any(|existing| existing.name() == name) {
+            tracing::warn!("Health check {} registered twice, using the last one", name);
+            self.checks.retain(|existing| existing.name() != name);
+        }
+        self.checks.push(Arc::new(check));
+        self
+    }
+
+    /// Runs every check concurrently, each within [`CHECK_TIMEOUT`].
+    pub async fn run(&self) -> HealthReport {
+        let running: Vec<_> = self.checks.iter()
+            .map(|check| {
+                let check = check.clone();
+                (check.name(), tokio::spawn(async move {
+                    let started = Instant::now();
+                    let outcome = match tokio::time::timeout(CHECK_TIMEOUT, check.check()).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+                    };
+                    (outcome, started.elapsed())
+                }))
+            })
+            .collect();
+
+        let mut checks = BTreeMap::new();
+        for (name, task) in running {
+            let (outcome, elapsed) = task.await
+                .unwrap_or_else(|e| (Err(format!("check panicked: {}", e)), Duration::ZERO));
+            let (status, detail) = match outcome {
+                Ok(detail) => (HealthStatus::Up, detail),
+                Err(reason) => (HealthStatus::Down, Some(reason)),
+            };
+            checks.insert(name, ComponentHealth { status, detail, duration_ms: elapsed.as_millis() });
+        }
+
+        let status = if checks.values().all(|c| c.status == HealthStatus::Up) { HealthStatus::Up } else { HealthStatus::Down };
+        HealthReport { status, checks }
+    }
+}
+
+/// The checks every deployment has: a `SELECT 1` through the pool and pending migrations.
+pub fn register(registry: &mut HealthRegistry, pool: &DbPool) {
+    let db_pool = pool.clone();
+    registry.register(FnHealthCheck::new("database", move || {
+        let pool = db_pool.clone();
+        async move {
+            database::run(&DbHandle::Pool(pool.clone()), |conn| {
+                diesel::sql_query("SELECT 1").execute(conn).map_err(AppError::from)
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+            let state = pool.state();
+            Ok(Some(format!("{} of {} connections idle", state.idle_connections, state.connections)))
+        }
+    }));
+
+    let migrations_pool = pool.clone();
+    registry.register(FnHealthCheck::new("migrations", move || {
+        let pool = migrations_pool.clone();
+        async move {
+            tokio::task::spawn_blocking(move || migrations::ensure_up_to_date(&pool))
+                .await
+                .map_err(|e| e.to_string())?
+                .map(|()| None)
+                .map_err(|e| e.to_string())
+        }
+    }));
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/health")
+            .route("/live", web::get().to(live))
+            .route("/ready", web::get().to(ready))
+    );
+}
+
+async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": HealthStatus::Up }))
+}
+
+async fn ready(state: web::Data<AppState>) -> HttpResponse {
+    let report = state.health.run().await;
+    match report.status {
+        HealthStatus::Up => HttpResponse::Ok().json(report),
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_report_is_down_when_any_check_fails_or_hangs() {
+        let mut registry = HealthRegistry::new();
+        registry
+            .register(FnHealthCheck::new("fine", || async { Ok(Some("all good".to_string())) }))
+            .register(FnHealthCheck::new("broken", || async { Err("no route to host".to_string()) }))
+            .register(FnHealthCheck::new("slow", || async {
+                tokio::time::sleep(CHECK_TIMEOUT * 2).await;
+                Ok(None)
+            }));
+
+        let report = registry.run().await;
+
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(report.checks["fine"].status, HealthStatus::Up);
+        assert_eq!(report.checks["broken"].detail.as_deref(), Some("no route to host"));
+        assert_eq!(report.checks["slow"].detail.as_deref(), Some("timed out after 2s"));
+    }
+}
@@ -0,0 +1,39 @@
+use uuid::Uuid;
+
+/// Where services get new ids and random tokens from, so tests can predict them.
+pub trait IdGenerator: Send + Sync {
+    fn new_id(&self) -> Uuid;
+}
+
+/// Random v4 UUIDs, which are also unguessable enough to serve as tokens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn new_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Hands out `00000000-0000-0000-0000-000000000001`, `...0002` and so on.
+/// Clones share the counter.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone, Default)]
+pub struct SequentialIdGenerator {
+    next: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl SequentialIdGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl IdGenerator for SequentialIdGenerator {
+    fn new_id(&self) -> Uuid {
+        let n = self.next.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        Uuid::from_u128(n.into())
+    }
+}
@@ -0,0 +1,137 @@
+//! Settings that can change while the server runs. On SIGHUP the layered
+//! configuration is loaded again and the reloadable settings are swapped in;
+//! everything else keeps its startup value until the next restart.
+use std::sync::{Arc, RwLock};
+
+use super::{
+    config::{AppConfig, ConfigArgs},
+    logging,
+};
+
+/// Shared handle to the current configuration. Clones see the same updates.
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    current: Arc<RwLock<Arc<AppConfig>>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: AppConfig) -> Self {
+        Self { current: Arc::new(RwLock::new(Arc::new(config))) }
+    }
+
+    /// A snapshot; hold it for one request or one email, not longer.
+    pub fn get(&self) -> Arc<AppConfig> {
+        self.current.read().expect("config lock poisoned").clone()
+    }
+
+    /// Takes the reloadable settings from `fresh`: `log.filter`,
+    /// `cors.allowed_origins`, `email.from` and `features`. Returns the
+    /// sections that also changed but only apply after a restart.
+    pub fn apply(&self, fresh: AppConfig) -> Vec<&'static str> {
+        let mut current = self.current.write().expect("config lock poisoned");
+        let mut next = AppConfig::clone(&current);
+        next.log.filter = fresh.log.filter.clone();
+        next.cors.allowed_origins = fresh.cors.allowed_origins.clone();
+        next.email.from = fresh.email.from.clone();
+        next.features = fresh.features.clone();
+
+        let restart_needed = [
+            ("server", next.server != fresh.server),
+            ("database", next.database != fresh.database),
+            ("auth", next.auth != fresh.auth),
+            ("email", next.email != fresh.email),
+            ("cors", next.cors != fresh.cors),
+            ("jobs", next.jobs != fresh.jobs),
+            ("log", next.log != fresh.log),
+            ("metrics", next.metrics != fresh.metrics),
+            ("tracing", next.tracing != fresh.tracing),
+        ]
+        .into_iter()
+        .filter_map(|(section, changed)| changed.then_some(section))
+        .collect();
+
+        *current = Arc::new(next);
+        restart_needed
+    }
+
+    /// Loads the configuration again with the flags the process started with.
+    /// An invalid configuration is logged and the current one kept.
+    pub fn reload(&self, args: &ConfigArgs) {
+        let fresh = match AppConfig::load(args) {
+            Ok(fresh) => fresh,
+            Err(e) => {
+                tracing::error!("Keeping the current configuration. {}", e);
+                return;
+            }
+        };
+
+        let filter = fresh.log.filter.clone();
+        if filter != self.get().log.filter
+            && let Err(e) = logging::set_filter(&filter)
+        {
+            tracing::error!("Failed to apply log filter {:?}: {}", filter, e);
+        }
+
+        let restart_needed = self.apply(fresh);
+        if restart_needed.is_empty() {
+            tracing::info!("Configuration reloaded");
+        } else {
+            tracing::warn!("Configuration reloaded; changes to [{}] apply after a restart", restart_needed.join("], ["));
+        }
+    }
+}
+
+/// Reloads `live` whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub fn reload_on_sighup(live: LiveConfig, args: ConfigArgs) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::error!("Can't listen for SIGHUP, configuration reload is disabled: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            live.reload(&args);
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn reload_on_sighup(_live: LiveConfig, _args: ConfigArgs) {
+    tracing::warn!("Configuration reload on SIGHUP is only supported on Unix");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_swaps_reloadable_settings_only() {
+        let live = LiveConfig::new(AppConfig::for_tests());
+        let before = live.get();
+
+        let mut fresh = AppConfig::for_tests();
+        fresh.email.from = "news@example.com".to_string();
+        fresh.cors.allowed_origins = vec!["https://app.example.com".to_string()];
+        fresh.features.insert("new_dashboard".to_string(), true);
+        fresh.log.filter = "debug".to_string();
+        fresh.server.port = 9000;
+        fresh.cors.max_age_secs = 60;
+
+        assert_eq!(live.apply(fresh), vec!["server", "cors"]);
+
+        let after = live.get();
+        assert_eq!(after.email.from, "news@example.com");
+        assert!(after.allows_origin("https://app.example.com"));
+        assert!(after.feature("new_dashboard"));
+        assert_eq!(after.log.filter, "debug");
+        assert_eq!(after.server.port, 8080);
+        assert_eq!(after.cors.max_age_secs, before.cors.max_age_secs);
+        // Snapshots taken earlier don't change under their holder
+        assert_eq!(before.email.from, "test@example.com");
+    }
+}
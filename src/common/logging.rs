@@ -1,12 +1,90 @@
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use std::sync::OnceLock;
 
-pub fn init() {
-    // If RUST_LOG is not set, default to "info"
+use tracing_appender::{non_blocking::WorkerGuard, rolling::{RollingFileAppender, Rotation}};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry};
+
+use super::{
+    config::{AppConfig, LogConfig, LogFormat, LogRotation},
+    telemetry::{self, TelemetryGuard},
+};
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Keeps log lines and spans flowing; buffered ones are flushed when it's
+/// dropped, so hold it until the process exits.
+pub struct LogGuard {
+    _telemetry: TelemetryGuard,
+    _writer: WorkerGuard,
+}
+
+/// Server logging, filtered by `log.filter` (`RUST_LOG`), in `log.format` to
+/// stdout or rolling files in `log.directory`, plus span export as configured
+/// under `[tracing]`. Lines are written by a background thread, so a slow
+/// disk or pipe doesn't hold up requests. The filter can be swapped later
+/// with [`set_filter`]; it also decides which spans are exported.
+pub fn init(config: &AppConfig) -> LogGuard {
+    let env_filter = EnvFilter::try_new(&config.log.filter).unwrap_or_else(|_| EnvFilter::new("info,actix_web=info"));
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    FILTER.set(handle).ok();
+
+    let (tracer, telemetry) = telemetry::init(&config.tracing).unwrap_or_else(|e| {
+        eprintln!("Failed to set up tracing: {}", e);
+        std::process::exit(1);
+    });
+    let (writer, writer_guard) = match &config.log.directory {
+        Some(directory) => tracing_appender::non_blocking(rolling_files(&config.log, directory).unwrap_or_else(|e| {
+            eprintln!("Failed to open log files in {}: {}", directory, e);
+            std::process::exit(1);
+        })),
+        None => tracing_appender::non_blocking(std::io::stdout()),
+    };
+
+    let fmt = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(config.log.directory.is_none());
+    let fmt = match config.log.format {
+        LogFormat::Pretty => fmt.pretty().boxed(),
+        LogFormat::Compact => fmt.compact().boxed(),
+        // Lists every span in scope, so lines carry the request's `request_id`
+        LogFormat::Json => fmt.json().with_span_list(true).boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt)
+        .with(tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)))
+        .init();
+    LogGuard { _telemetry: telemetry, _writer: writer_guard }
+}
+
+/// Files named `{directory}/app.log.{date}`, e.g. `app.log.2026-10-19` when rotated daily.
+fn rolling_files(log: &LogConfig, directory: &str) -> Result<RollingFileAppender, tracing_appender::rolling::InitError> {
+    let rotation = match log.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let builder = RollingFileAppender::builder().rotation(rotation).filename_prefix("app.log");
+    match log.max_files {
+        Some(max_files) => builder.max_log_files(max_files),
+        None => builder,
+    }
+    .build(directory)
+}
+
+/// Replaces the filter installed by [`init`] without restarting.
+pub fn set_filter(filter: &str) -> Result<(), String> {
+    let env_filter = EnvFilter::try_new(filter).map_err(|e| e.to_string())?;
+    let handle = FILTER.get().ok_or("logging is not initialized")?;
+    handle.reload(env_filter).map_err(|e| e.to_string())
+}
+
+/// Logging for command line tools: warnings and errors only (unless `RUST_LOG`
+/// says otherwise), written to stderr so stdout stays clean for output.
+pub fn init_cli() {
     let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info,actix_web=info"));
+        .unwrap_or_else(|_| EnvFilter::new("warn"));
 
     tracing_subscriber::registry()
         .with(env_filter)
-        .with(tracing_subscriber::fmt::layer().pretty())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 }
@@ -0,0 +1,195 @@
+//! Prometheus metrics, exposed at `/metrics` in the text format.
+//!
+//! The endpoint is never public: it's served without authentication on
+//! `metrics.bind_address` (a separate listener, e.g. `127.0.0.1:9100`),
+//! and on the API's own address only with `Authorization: Bearer {metrics.token}`.
+//! With neither configured it isn't served at all.
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Instant;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, HttpRequest, HttpResponse,
+};
+use prometheus::{
+    core::Collector, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use r2d2::{event, HandleEvent};
+use sha2::{Digest, Sha256};
+
+use crate::app::AppState;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+fn registered<C: Collector + Clone + 'static>(collector: C) -> C {
+    REGISTRY.register(Box::new(collector.clone())).expect("metric registered twice");
+    collector
+}
+
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| registered(
+    IntCounterVec::new(Opts::new("http_requests_total", "HTTP requests by method, route pattern and status"), &["method", "route", "status"]).unwrap(),
+));
+
+static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| registered(
+    HistogramVec::new(
+        HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by method, route pattern and status"),
+        &["method", "route", "status"],
+    ).unwrap(),
+));
+
+static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| registered(
+    IntGaugeVec::new(Opts::new("db_pool_connections", "Open database connections by state (idle, active)"), &["state"]).unwrap(),
+));
+
+static DB_POOL_MAX_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| registered(
+    IntGauge::new("db_pool_max_connections", "Configured database pool size").unwrap(),
+));
+
+static DB_POOL_WAIT: LazyLock<Histogram> = LazyLock::new(|| registered(
+    Histogram::with_opts(
+        HistogramOpts::new("db_pool_wait_seconds", "Time spent waiting for a database connection")
+            .buckets(vec![0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0]),
+    ).unwrap(),
+));
+
+static DB_POOL_TIMEOUTS: LazyLock<IntCounter> = LazyLock::new(|| registered(
+    IntCounter::new("db_pool_timeouts_total", "Requests that gave up waiting for a database connection").unwrap(),
+));
+
+static EMAILS: LazyLock<IntCounterVec> = LazyLock::new(|| registered(
+    IntCounterVec::new(Opts::new("emails_sent_total", "Emails handed to the provider by template and outcome (success, failure)"), &["template", "outcome"]).unwrap(),
+));
+
+static LOGINS: LazyLock<IntCounterVec> = LazyLock::new(|| registered(
+    IntCounterVec::new(Opts::new("logins_total", "Sign-in attempts by outcome and failure reason"), &["outcome", "reason"]).unwrap(),
+));
+
+static REFRESH_TOKEN_ROTATIONS: LazyLock<IntCounter> = LazyLock::new(|| registered(
+    IntCounter::new("refresh_token_rotations_total", "Refresh tokens exchanged for a new pair").unwrap(),
+));
+
+static ACTIVE_SESSIONS: LazyLock<IntGauge> = LazyLock::new(|| registered(
+    IntGauge::new("active_sessions", "Sessions that are neither revoked, expired nor idle, across all instances").unwrap(),
+));
+
+pub fn record_email(template: &str, sent: bool) {
+    EMAILS.with_label_values(&[template, if sent { "success" } else { "failure" }]).inc();
+}
+
+pub fn record_login_success() {
+    LOGINS.with_label_values(&["success", ""]).inc();
+}
+
+/// `reason` is a fixed identifier such as `wrong_password`, never user input.
+pub fn record_login_failure(reason: &'static str) {
+    LOGINS.with_label_values(&["failure", reason]).inc();
+}
+
+pub fn record_refresh_token_rotation() {
+    REFRESH_TOKEN_ROTATIONS.inc();
+}
+
+/// Keeps the pool gauges current from r2d2's connection events.
+#[derive(Debug, Default)]
+pub struct PoolMetrics {
+    open: AtomicI64,
+    active: AtomicI64,
+}
+
+impl PoolMetrics {
+    pub fn new(max_size: u32) -> Self {
+        DB_POOL_MAX_CONNECTIONS.set(max_size as i64);
+        Self::default()
+    }
+
+    fn publish(&self) {
+        let (open, active) = (self.open.load(Ordering::Relaxed), self.active.load(Ordering::Relaxed));
+        DB_POOL_CONNECTIONS.with_label_values(&["active"]).set(active);
+        DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(open - active);
+    }
+}
+
+impl HandleEvent for PoolMetrics {
+    fn handle_acquire(&self, _: event::AcquireEvent) {
+        self.open.fetch_add(1, Ordering::Relaxed);
+        self.publish();
+    }
+
+    fn handle_release(&self, _: event::ReleaseEvent) {
+        self.open.fetch_sub(1, Ordering::Relaxed);
+        self.publish();
+    }
+
+    fn handle_checkout(&self, event: event::CheckoutEvent) {
+        DB_POOL_WAIT.observe(event.duration().as_secs_f64());
+        self.active.fetch_add(1, Ordering::Relaxed);
+        self.publish();
+    }
+
+    fn handle_checkin(&self, _: event::CheckinEvent) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+        self.publish();
+    }
+
+    fn handle_timeout(&self, _: event::TimeoutEvent) {
+        DB_POOL_TIMEOUTS.inc();
+    }
+}
+
+/// Counts and times every request under its route pattern (e.g. `/posts/{id}`),
+/// so ids don't explode the label space.
+pub async fn record_http(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+
+    let result = next.call(req).await;
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+    HTTP_REQUESTS.with_label_values(&labels).inc();
+    HTTP_REQUEST_DURATION.with_label_values(&labels).observe(started.elapsed().as_secs_f64());
+    result
+}
+
+/// `/metrics` on the API's address, for scrapers presenting `metrics.token`.
+pub async fn scrape_with_token(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    let Some(token) = &state.config.metrics.token else {
+        return HttpResponse::NotFound().finish();
+    };
+    let presented = req.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Digests, so the comparison takes as long wherever the first differing byte is
+    if presented.map(Sha256::digest) != Some(Sha256::digest(token.expose())) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    scrape(state).await
+}
+
+/// `/metrics` on the dedicated listener, which is only reachable by whoever can reach its address.
+pub async fn scrape(state: web::Data<AppState>) -> HttpResponse {
+    // Sessions live in the database, so the gauge is read when scraped rather than counted in-process
+    match state.auth.count_active_sessions().await {
+        Ok(count) => ACTIVE_SESSIONS.set(count),
+        Err(e) => tracing::warn!("Failed to count active sessions for metrics: {:?}", e),
+    }
+
+    let mut body = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut body) {
+        tracing::error!("Failed to encode metrics: {}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok().content_type(prometheus::TEXT_FORMAT).body(body)
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(scrape_with_token));
+}
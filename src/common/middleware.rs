@@ -0,0 +1,163 @@
+//! Request correlation. Every request carries an id: the caller's
+//! `X-Request-Id` when it sends a usable one, a fresh UUID otherwise. The id is
+//! a field of the request's root span, so it appears on every log line and
+//! exported span of the request, is echoed in the `X-Request-Id` response
+//! header, and is included in error bodies.
+//!
+//! The id, the client's address and user agent are also kept in a task-local
+//! [`RequestContext`] while the request is handled, for code such as the audit
+//! log that runs far from the handler.
+use std::net::IpAddr;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    web, Error, HttpMessage,
+};
+use opentelemetry::{global, propagation::Extractor};
+use tracing::Span;
+use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use super::{client_ip, config::AppConfig};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+/// The id of the request being handled. Also available as a request extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Uses `incoming` unless it's missing, longer than 128 characters or
+    /// contains anything but visible ASCII, which would let callers forge log lines.
+    fn from_header(incoming: Option<&HeaderValue>) -> Self {
+        incoming
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic()))
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(|| Self(Uuid::new_v4().to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Who sent the request being handled.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub id: RequestId,
+    /// Resolved through `TRUSTED_PROXIES` and `FORWARDED_HEADER`, like the `ClientIp` extractor.
+    pub client_ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+/// The request this code runs for, if it runs within one.
+pub fn current_request() -> Option<RequestContext> {
+    REQUEST.try_with(RequestContext::clone).ok()
+}
+
+/// The id of the request this code runs for, if it runs within one.
+pub fn current_request_id() -> Option<RequestId> {
+    REQUEST.try_with(|request| request.id.clone()).ok()
+}
+
+/// Assigns the request id. Must wrap `TracingLogger`, which reads it for the root span.
+pub async fn request_id(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+    req.extensions_mut().insert(id.clone());
+
+    let context = RequestContext {
+        client_ip: resolve_client_ip(&req),
+        user_agent: req.headers().get("User-Agent").and_then(|h| h.to_str().ok()).map(str::to_string),
+        id: id.clone(),
+    };
+
+    let header = HeaderValue::from_str(id.as_str()).expect("request ids are visible ASCII");
+    let mut response = REQUEST.scope(context, next.call(req)).await?;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+    Ok(response)
+}
+
+/// The caller's address, resolved through `TRUSTED_PROXIES` and `FORWARDED_HEADER`. `None` without a peer address.
+fn resolve_client_ip(req: &ServiceRequest) -> Option<IpAddr> {
+    let config = req.app_data::<web::Data<AppConfig>>();
+    let trusted = config.map(|config| config.server.trusted_proxies.as_slice()).unwrap_or_default();
+    let header = config.map(|config| config.server.forwarded_header).unwrap_or_default();
+    req.peer_addr().map(|peer| client_ip::resolve(peer.ip(), req.headers(), trusted, header))
+}
+
+/// The root span of each request: OpenTelemetry's HTTP server fields, our
+/// request id, and the caller's trace from `traceparent` as parent.
+pub struct RequestSpan;
+
+impl RootSpanBuilder for RequestSpan {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let request_id = request.extensions().get::<RequestId>().map(|id| id.0.clone()).unwrap_or_default();
+        let method = request.method().as_str();
+        let route = request.match_pattern().unwrap_or_else(|| "default".to_string());
+        let user_agent = request.headers().get("User-Agent").and_then(|h| h.to_str().ok()).unwrap_or("");
+        // Not `connection_info()`, which believes any `X-Forwarded-For`
+        let client_ip = resolve_client_ip(request).map(|ip| ip.to_string()).unwrap_or_default();
+
+        let span = tracing::info_span!(
+            "HTTP request",
+            http.method = %method,
+            http.route = %route,
+            http.target = %request.uri().path_and_query().map(|p| p.as_str()).unwrap_or(""),
+            http.client_ip = %client_ip,
+            http.user_agent = %user_agent,
+            http.status_code = tracing::field::Empty,
+            otel.name = %format!("{} {}", method, route),
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+            exception.message = tracing::field::Empty,
+            exception.details = tracing::field::Empty,
+            request_id = %request_id,
+        );
+        let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(request.headers())));
+        // Fails only when no OpenTelemetry layer is installed, e.g. in tests
+        let _ = span.set_parent(parent);
+        span
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incoming_request_id_is_kept_only_when_safe() {
+        let kept = RequestId::from_header(Some(&HeaderValue::from_static("edge-7f3a:42")));
+        assert_eq!(kept.as_str(), "edge-7f3a:42");
+
+        for unsafe_id in ["", "two words", &"x".repeat(129)] {
+            let replaced = RequestId::from_header(Some(&HeaderValue::from_str(unsafe_id).unwrap()));
+            assert!(Uuid::parse_str(replaced.as_str()).is_ok(), "{:?} was kept", unsafe_id);
+        }
+        assert!(Uuid::parse_str(RequestId::from_header(None).as_str()).is_ok());
+    }
+}
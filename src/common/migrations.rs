@@ -0,0 +1,98 @@
+use diesel::pg::{Pg, PgConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use diesel::migration::MigrationSource;
+use serde::Serialize;
+use thiserror::Error;
+use crate::common::{config::AppConfig, database::DbPool};
+
+/// The `migrations/` directory, compiled into the binary. `schema.rs` is
+/// generated from the same migrations, so the database matches the code
+/// exactly when none of them are pending.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Failed to get a database connection: {0}")]
+    Connection(#[from] r2d2::Error),
+
+    #[error("Migration failed: {0}")]
+    Migration(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("The database schema is behind this build, pending migrations: {}", .0.join(", "))]
+    Pending(Vec<String>),
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for MigrationError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        MigrationError::Migration(error)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    /// The migration's directory name, e.g. `2026-02-15-101604-0000_create_users`.
+    pub name: String,
+    pub applied: bool,
+}
+
+/// Applies every pending migration. Returns the names of those applied.
+pub fn run_pending(pool: &DbPool) -> Result<Vec<String>, MigrationError> {
+    let mut conn = pool.get()?;
+    let pending = pending_names(&mut conn)?;
+    conn.run_pending_migrations(MIGRATIONS)?;
+    Ok(pending)
+}
+
+/// Reverts the most recently applied migration. Returns its name.
+pub fn revert_last(pool: &DbPool) -> Result<String, MigrationError> {
+    let mut conn = pool.get()?;
+    let version = conn.revert_last_migration(MIGRATIONS)?;
+
+    let migrations = MigrationSource::<Pg>::migrations(&MIGRATIONS)?;
+    Ok(migrations.iter()
+        .find(|migration| migration.name().version() == version)
+        .map(|migration| migration.name().to_string())
+        .unwrap_or_else(|| version.to_string()))
+}
+
+pub fn status(pool: &DbPool) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let mut conn = pool.get()?;
+    let applied = conn.applied_migrations()?;
+
+    let migrations = MigrationSource::<Pg>::migrations(&MIGRATIONS)?;
+    Ok(migrations.iter()
+        .map(|migration| MigrationStatus {
+            name: migration.name().to_string(),
+            applied: applied.contains(&migration.name().version()),
+        })
+        .collect())
+}
+
+/// Fails if any embedded migration hasn't been applied, so the server never
+/// runs queries against an outdated schema.
+pub fn ensure_up_to_date(pool: &DbPool) -> Result<(), MigrationError> {
+    let mut conn = pool.get()?;
+    let pending = pending_names(&mut conn)?;
+    if !pending.is_empty() {
+        return Err(MigrationError::Pending(pending));
+    }
+    Ok(())
+}
+
+/// Startup gate for the server and the worker: applies pending migrations when
+/// `RUN_MIGRATIONS_ON_BOOT` is set, then insists the schema is up to date.
+pub fn prepare_on_startup(pool: &DbPool, config: &AppConfig) -> Result<(), MigrationError> {
+    if config.database.run_migrations_on_boot {
+        for name in run_pending(pool)? {
+            tracing::info!("Applied migration {}", name);
+        }
+    }
+    ensure_up_to_date(pool)
+}
+
+fn pending_names(conn: &mut PgConnection) -> Result<Vec<String>, MigrationError> {
+    Ok(conn.pending_migrations(MIGRATIONS)?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect())
+}
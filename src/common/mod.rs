@@ -1,6 +1,17 @@
+pub mod client_ip;
+pub mod clock;
 pub mod config;
 pub mod errors;
+pub mod geoip;
+pub mod health;
+pub mod id_generator;
+pub mod live_config;
 pub mod database;
 pub mod logging;
+pub mod metrics;
 pub mod middleware;
+pub mod migrations;
+pub mod signed_token;
+pub mod telemetry;
+pub mod unit_of_work;
 pub mod user_agent_parser;
@@ -0,0 +1,143 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::common::{config::AppConfig, errors::AppError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Stateless, tamper-proof tokens for links in emails (unsubscribe, "this
+/// wasn't me", ...).
+///
+/// A token is `base64url(payload).base64url(mac)` where the payload is
+/// `"{expires_at}:{data}"` (`0` when it never expires). The `purpose` is
+/// mixed into the MAC so a token minted for one link can't be replayed on
+/// another endpoint.
+///
+/// Tokens are always signed with the current key; keys retired by a rotation
+/// are still accepted when verifying so links already sent keep working.
+/// Keys are derived from the secrets rather than used as they are, so link
+/// tokens never share key material with the JWTs signed by the same secret.
+pub struct TokenSigner {
+    key: Vec<u8>,
+    previous_keys: Vec<Vec<u8>>,
+}
+
+impl TokenSigner {
+    pub fn new(secret: &str) -> Self {
+        Self { key: derive_key(secret), previous_keys: Vec::new() }
+    }
+
+    /// Signs with `JWT_SECRET` and also accepts `JWT_PREVIOUS_SECRETS`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            key: derive_key(config.auth.jwt_secret.expose()),
+            previous_keys: config.auth.jwt_previous_secrets.iter().map(|s| derive_key(s.expose())).collect(),
+        }
+    }
+
+    pub fn sign(&self, purpose: &str, data: &str, expires_at: Option<NaiveDateTime>) -> String {
+        let expires_at = expires_at.map(|e| e.and_utc().timestamp()).unwrap_or(0);
+        let payload = format!("{}:{}", expires_at, data);
+        let mac = self.mac(purpose, &payload);
+
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(payload), URL_SAFE_NO_PAD.encode(mac))
+    }
+
+    /// Returns the signed data if the token is authentic and not expired.
+    pub fn verify(&self, purpose: &str, token: &str, now: NaiveDateTime) -> Result<String, AppError> {
+        let invalid = || AppError::Unauthorized("Invalid or expired link".to_string());
+
+        let (payload, mac) = token.split_once('.').ok_or_else(invalid)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        let mac = URL_SAFE_NO_PAD.decode(mac).map_err(|_| invalid())?;
+        let payload = String::from_utf8(payload).map_err(|_| invalid())?;
+
+        let authentic = std::iter::once(&self.key).chain(&self.previous_keys).any(|key| {
+            let mut expected = hmac(key, purpose);
+            expected.update(payload.as_bytes());
+            expected.verify_slice(&mac).is_ok()
+        });
+        if !authentic {
+            return Err(invalid());
+        }
+
+        let (expires_at, data) = payload.split_once(':').ok_or_else(invalid)?;
+        let expires_at: i64 = expires_at.parse().map_err(|_| invalid())?;
+        if expires_at != 0 && expires_at < now.and_utc().timestamp() {
+            return Err(invalid());
+        }
+
+        Ok(data.to_string())
+    }
+
+    fn mac(&self, purpose: &str, payload: &str) -> Vec<u8> {
+        let mut mac = hmac(&self.key, purpose);
+        mac.update(payload.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+fn derive_key(secret: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(b"signed-link");
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hmac(key: &[u8], purpose: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(purpose.as_bytes());
+    mac.update(&[0]);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_round_trip_and_purpose_binding() {
+        let signer = TokenSigner::new("secret");
+        let now = Utc::now().naive_utc();
+        let token = signer.sign("unsubscribe", "user:digest", None);
+
+        assert_eq!(signer.verify("unsubscribe", &token, now).unwrap(), "user:digest");
+        assert!(signer.verify("not-me", &token, now).is_err());
+        assert!(TokenSigner::new("other").verify("unsubscribe", &token, now).is_err());
+    }
+
+    #[test]
+    fn test_does_not_sign_with_the_raw_secret() {
+        let token = TokenSigner::new("secret").sign("unsubscribe", "user:digest", None);
+        let (payload, mac) = token.split_once('.').unwrap();
+
+        let mut raw = hmac(b"secret", "unsubscribe");
+        raw.update(&URL_SAFE_NO_PAD.decode(payload).unwrap());
+        assert!(raw.verify_slice(&URL_SAFE_NO_PAD.decode(mac).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_expiry_and_tampering() {
+        let signer = TokenSigner::new("secret");
+        let now = Utc::now().naive_utc();
+        let token = signer.sign("not-me", "session", Some(now + Duration::hours(1)));
+
+        assert!(signer.verify("not-me", &token, now).is_ok());
+        assert!(signer.verify("not-me", &token, now + Duration::hours(2)).is_err());
+
+        let (_, mac) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode("0:session"), mac);
+        assert!(signer.verify("not-me", &forged, now).is_err());
+    }
+
+    #[test]
+    fn test_accepts_previous_keys_after_rotation() {
+        let now = Utc::now().naive_utc();
+        let old_token = TokenSigner::new("old").sign("unsubscribe", "user:digest", None);
+
+        let rotated = TokenSigner { previous_keys: vec![derive_key("old")], ..TokenSigner::new("new") };
+        assert_eq!(rotated.verify("unsubscribe", &old_token, now).unwrap(), "user:digest");
+        assert!(TokenSigner::new("old").verify("unsubscribe", &rotated.sign("unsubscribe", "x", None), now).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! OpenTelemetry tracing.
+//!
+//! Every request gets a root span from `tracing_actix_web::TracingLogger`
+//! (see `common::middleware::RequestSpan`), continuing the trace of an
+//! incoming W3C `traceparent` header. Application services and repositories
+//! add child spans with `#[instrument]`, and `database::run` adds a `db.query`
+//! span around each checkout and query.
+//!
+//! `tracing.exporter` picks where finished spans go: an OTLP/HTTP collector,
+//! or for machines without one, stdout or a file with one JSON span per line.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    error::{OTelSdkError, OTelSdkResult},
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracer, SdkTracerProvider, SpanData, SpanExporter},
+    Resource,
+};
+use serde_json::{json, Map};
+
+use super::config::{TraceExporter, TracingConfig};
+
+/// Flushes buffered spans when dropped, so keep it alive until the process exits.
+#[must_use = "spans still buffered are lost when the guard is dropped"]
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush spans: {}", e);
+        }
+    }
+}
+
+/// Installs the `traceparent` propagator and builds the tracer spans are
+/// exported through. `None` when `tracing.exporter` is `none`.
+pub fn init(config: &TracingConfig) -> Result<(Option<SdkTracer>, TelemetryGuard), String> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let builder = SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build());
+    let provider = match config.exporter {
+        TraceExporter::None => return Ok((None, TelemetryGuard { provider: None })),
+        TraceExporter::Otlp => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(&config.otlp_endpoint)
+                .build()
+                .map_err(|e| format!("OTLP exporter for {}: {}", config.otlp_endpoint, e))?;
+            builder.with_batch_exporter(exporter).build()
+        }
+        TraceExporter::Stdout => builder.with_batch_exporter(JsonLinesExporter::stdout()).build(),
+        TraceExporter::File => {
+            let exporter = JsonLinesExporter::file(&config.file_path)
+                .map_err(|e| format!("tracing.file_path {}: {}", config.file_path, e))?;
+            builder.with_batch_exporter(exporter).build()
+        }
+    };
+
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    global::set_tracer_provider(provider.clone());
+    Ok((Some(tracer), TelemetryGuard { provider: Some(provider) }))
+}
+
+/// Writes each span as one JSON object per line, for checking traces without a collector.
+#[derive(Debug)]
+pub struct JsonLinesExporter {
+    target: Mutex<Target>,
+    service_name: Option<String>,
+}
+
+#[derive(Debug)]
+enum Target {
+    Stdout,
+    File(File),
+}
+
+impl JsonLinesExporter {
+    pub fn stdout() -> Self {
+        Self { target: Mutex::new(Target::Stdout), service_name: None }
+    }
+
+    /// Appends to `path`, creating it if needed.
+    pub fn file(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { target: Mutex::new(Target::File(file)), service_name: None })
+    }
+
+    fn to_json(&self, span: &SpanData) -> serde_json::Value {
+        let parent = span.parent_span_id.to_string();
+        json!({
+            "service": self.service_name,
+            "trace_id": span.span_context.trace_id().to_string(),
+            "span_id": span.span_context.span_id().to_string(),
+            "parent_span_id": (parent != "0000000000000000").then_some(parent),
+            "name": span.name,
+            "kind": format!("{:?}", span.span_kind).to_lowercase(),
+            "start_time": timestamp(span.start_time),
+            "duration_ms": span.end_time.duration_since(span.start_time).unwrap_or_default().as_secs_f64() * 1000.0,
+            "status": format!("{:?}", span.status),
+            "attributes": attributes(&span.attributes),
+            "events": span.events.events.iter()
+                .map(|event| json!({
+                    "name": event.name,
+                    "time": timestamp(event.timestamp),
+                    "attributes": attributes(&event.attributes),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl SpanExporter for JsonLinesExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let mut lines = Vec::new();
+        for span in &batch {
+            serde_json::to_writer(&mut lines, &self.to_json(span)).map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+            lines.push(b'\n');
+        }
+
+        let mut target = self.target.lock().map_err(|_| OTelSdkError::InternalFailure("exporter lock poisoned".to_string()))?;
+        let written = match &mut *target {
+            Target::Stdout => io::stdout().lock().write_all(&lines),
+            Target::File(file) => file.write_all(&lines),
+        };
+        written.map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        let target = self.target.get_mut().map_err(|_| OTelSdkError::InternalFailure("exporter lock poisoned".to_string()))?;
+        let flushed = match target {
+            Target::Stdout => io::stdout().flush(),
+            Target::File(file) => file.flush(),
+        };
+        flushed.map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.service_name = resource.get(&"service.name".into()).map(|name| name.to_string());
+    }
+}
+
+fn timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn attributes(attributes: &[KeyValue]) -> Map<String, serde_json::Value> {
+    attributes.iter()
+        .map(|kv| {
+            let value = match &kv.value {
+                Value::Bool(b) => json!(b),
+                Value::I64(i) => json!(i),
+                Value::F64(f) => json!(f),
+                other => json!(other.to_string()),
+            };
+            (kv.key.to_string(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opentelemetry::propagation::TextMapPropagator;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_file_exporter_continues_the_incoming_trace() {
+        let path = std::env::temp_dir().join(format!("spans-{}.jsonl", uuid::Uuid::new_v4()));
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(JsonLinesExporter::file(path.to_str().unwrap()).unwrap())
+            .with_resource(Resource::builder().with_service_name("test-api").build())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let headers = HashMap::from([(
+            "traceparent".to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+        )]);
+        let incoming = TraceContextPropagator::new().extract(&headers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!("HTTP request");
+            request.set_parent(incoming).unwrap();
+            let _entered = request.enter();
+            tracing::info_span!("db.query", db.system = "postgresql").in_scope(|| {});
+        });
+        provider.shutdown().unwrap();
+
+        let spans: Vec<serde_json::Value> = std::fs::read_to_string(&path).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(&path).ok();
+
+        let (query, request) = (&spans[0], &spans[1]);
+        assert_eq!(query["name"], "db.query");
+        assert_eq!(query["attributes"]["db.system"], "postgresql");
+        assert_eq!(query["parent_span_id"], request["span_id"]);
+        assert_eq!(request["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(request["parent_span_id"], "00f067aa0ba902b7");
+        assert_eq!(request["service"], "test-api");
+    }
+}
@@ -0,0 +1,111 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::common::errors::AppError;
+
+/// Runs several repository calls, possibly from different modules, as one
+/// atomic operation. `work` receives repositories that all share the
+/// transaction; it commits if `work` returns `Ok` and rolls back otherwise.
+///
+/// Keep side effects such as sending email out of `work`, since they can't be
+/// rolled back.
+#[async_trait]
+pub trait UnitOfWork: Send + Sync {
+    type Repositories: Send;
+
+    async fn transaction<T, F, Fut>(&self, work: F) -> Result<T, AppError>
+    where
+        T: Send,
+        F: FnOnce(Self::Repositories) -> Fut + Send,
+        Fut: Future<Output = Result<T, AppError>> + Send;
+}
+
+/// `work` with its result type erased, see [`DynUnitOfWork`].
+pub type BoxedWork<'a, R> = Box<dyn FnOnce(R) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>> + Send + 'a>;
+
+/// The object-safe form of [`UnitOfWork`], so a unit of work can be shared as
+/// `Arc<dyn DynUnitOfWork<R>>` and swapped like any other adapter. Every
+/// `UnitOfWork` implements it, and the `Arc` implements `UnitOfWork` again.
+#[async_trait]
+pub trait DynUnitOfWork<R>: Send + Sync {
+    async fn transaction_boxed<'a>(&self, work: BoxedWork<'a, R>) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl<U: UnitOfWork> DynUnitOfWork<U::Repositories> for U {
+    async fn transaction_boxed<'a>(&self, work: BoxedWork<'a, U::Repositories>) -> Result<(), AppError> {
+        self.transaction(work).await
+    }
+}
+
+#[async_trait]
+impl<R: Send + 'static> UnitOfWork for Arc<dyn DynUnitOfWork<R>> {
+    type Repositories = R;
+
+    async fn transaction<T, F, Fut>(&self, work: F) -> Result<T, AppError>
+    where
+        T: Send,
+        F: FnOnce(R) -> Fut + Send,
+        Fut: Future<Output = Result<T, AppError>> + Send,
+    {
+        // The result is handed back through `output` rather than the return type.
+        // `(**self)` dispatches to the wrapped unit of work; `self.` would pick
+        // the blanket impl for this `Arc` and recurse.
+        let mut output = None;
+        let slot = &mut output;
+        (**self).transaction_boxed(Box::new(move |repositories| Box::pin(async move {
+            *slot = Some(work(repositories).await?);
+            Ok(())
+        }))).await?;
+
+        Ok(output.expect("a committed transaction has a result"))
+    }
+}
+
+/// Hands `work` clones of in-memory repositories. Changes are applied as they
+/// happen and are not rolled back on error.
+#[cfg(any(test, feature = "testing"))]
+pub struct InMemoryUnitOfWork<R> {
+    repositories: R,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl<R> InMemoryUnitOfWork<R> {
+    pub fn new(repositories: R) -> Self {
+        Self { repositories }
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+#[async_trait]
+impl<R: Clone + Send + Sync> UnitOfWork for InMemoryUnitOfWork<R> {
+    type Repositories = R;
+
+    async fn transaction<T, F, Fut>(&self, work: F) -> Result<T, AppError>
+    where
+        T: Send,
+        F: FnOnce(R) -> Fut + Send,
+        Fut: Future<Output = Result<T, AppError>> + Send,
+    {
+        work(self.repositories.clone()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shared_unit_of_work_passes_results_and_errors_through() {
+        let unit_of_work: Arc<dyn DynUnitOfWork<u32>> = Arc::new(InMemoryUnitOfWork::new(41));
+
+        let answer = unit_of_work.transaction(|n| async move { Ok(n + 1) }).await.unwrap();
+        assert_eq!(answer, 42);
+
+        let failed: Result<(), AppError> = unit_of_work
+            .transaction(|_| async { Err(AppError::Conflict("taken".to_string())) })
+            .await;
+        assert!(matches!(failed, Err(AppError::Conflict(_))));
+    }
+}
@@ -1,90 +1,268 @@
-/// Parses a raw User-Agent string into a human-readable device name.
-///
-/// Examples:
-/// - `"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 ... Chrome/120.0"` → `"Chrome on Windows"`
-/// - `"PostmanRuntime/7.36.0"` → `"PostmanRuntime"`
-/// - `"Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) ... Firefox/121.0"` → `"Firefox on macOS"`
-pub fn parse_device_name(user_agent: &str) -> String {
-    let browser = detect_browser(user_agent);
-    let os = detect_os(user_agent);
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+use thiserror::Error;
+
+/// Rules compiled into the binary, used unless `USER_AGENT_RULES_PATH` is set.
+const EMBEDDED_RULES: &str = include_str!("user_agent_rules.yaml");
+
+/// Browser families that are API clients or command line tools rather than browsers.
+const CLI_CLIENTS: &[&str] = &[
+    "Postman", "Insomnia", "Thunder Client", "curl", "Wget", "HTTPie",
+    "python-requests", "Go-http-client", "okhttp", "axios",
+];
+
+static PARSER: OnceLock<UserAgentParser> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DeviceClass {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+    Cli,
+    Unknown,
+}
 
-    match (browser, os) {
-        (Some(b), Some(o)) => format!("{} on {}", b, o),
-        (Some(b), None) => b.to_string(),
-        (None, Some(o)) => format!("Unknown browser on {}", o),
-        (None, None) => "Unknown device".to_string(),
+/// What we could learn about a client from its User-Agent header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedUserAgent {
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub device_class: DeviceClass,
+    /// Rendering engine, e.g. `Blink`, `WebKit` or `Gecko`.
+    pub engine: Option<String>,
+}
+
+impl ParsedUserAgent {
+    /// Human-readable name such as `"Chrome on Windows"`, `"Postman"` or `"Unknown device"`.
+    pub fn device_name(&self) -> String {
+        match (&self.browser, &self.os) {
+            (Some(b), Some(o)) => format!("{} on {}", b, o),
+            (Some(b), None) => b.clone(),
+            (None, Some(o)) => format!("Unknown browser on {}", o),
+            (None, None) => "Unknown device".to_string(),
+        }
     }
 }
 
-fn detect_browser(ua: &str) -> Option<&'static str> {
-    // Order matters: check more specific strings first
-    if ua.contains("PostmanRuntime") {
-        return Some("Postman");
+#[derive(Debug, Error)]
+pub enum UserAgentRulesError {
+    #[error("Failed to read user agent rules from {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+
+    #[error("Invalid user agent rules: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Invalid regex '{pattern}' in user agent rules: {source}")]
+    Regex { pattern: String, source: regex::Error },
+}
+
+/// A rules file in the uap-core `regexes.yaml` format. Unknown keys are ignored.
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    user_agent_parsers: Vec<RawRule>,
+    #[serde(default)]
+    os_parsers: Vec<RawRule>,
+    #[serde(default)]
+    device_parsers: Vec<RawRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRule {
+    regex: String,
+    regex_flag: Option<String>,
+    #[serde(flatten)]
+    replacements: HashMap<String, String>,
+}
+
+/// A compiled rule with its family and `v1`..`v3` replacements.
+struct Rule {
+    regex: Regex,
+    family: Option<String>,
+    versions: [Option<String>; 3],
+}
+
+impl Rule {
+    fn compile(raw: RawRule, family_key: &str, version_prefix: &str) -> Result<Self, UserAgentRulesError> {
+        let pattern = match raw.regex_flag.as_deref() {
+            Some("i") => format!("(?i){}", raw.regex),
+            _ => raw.regex,
+        };
+        let regex = Regex::new(&pattern).map_err(|source| UserAgentRulesError::Regex { pattern, source })?;
+        let mut replacements = raw.replacements;
+
+        Ok(Self {
+            regex,
+            family: replacements.remove(family_key),
+            versions: [1, 2, 3].map(|i| replacements.remove(&format!("{}v{}_replacement", version_prefix, i))),
+        })
     }
-    if ua.contains("Insomnia") {
-        return Some("Insomnia");
+
+    /// Returns the family and up to three version parts of the first match.
+    fn apply(&self, ua: &str) -> Option<(Option<String>, Vec<String>)> {
+        let caps = self.regex.captures(ua)?;
+        let family = match &self.family {
+            Some(replacement) => Some(substitute(replacement, &caps)),
+            None => group(&caps, 1),
+        };
+        let versions = self.versions.iter()
+            .enumerate()
+            .map_while(|(i, replacement)| match replacement {
+                Some(replacement) => Some(substitute(replacement, &caps)).filter(|v| !v.is_empty()),
+                None => group(&caps, i + 2),
+            })
+            .collect();
+
+        Some((family.filter(|f| !f.is_empty()), versions))
     }
-    if ua.contains("curl") {
-        return Some("curl");
+}
+
+fn group(caps: &Captures, i: usize) -> Option<String> {
+    caps.get(i).map(|m| m.as_str().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Replaces `$1`..`$9` with the matching capture group (empty when unmatched).
+fn substitute(replacement: &str, caps: &Captures) -> String {
+    let mut out = replacement.to_string();
+    for i in 1..=9 {
+        let placeholder = format!("${}", i);
+        if out.contains(&placeholder) {
+            out = out.replace(&placeholder, caps.get(i).map_or("", |m| m.as_str()));
+        }
+    }
+    out.trim().to_string()
+}
+
+fn first_match(rules: &[Rule], ua: &str) -> Option<(Option<String>, Vec<String>)> {
+    rules.iter().find_map(|rule| rule.apply(ua))
+}
+
+pub struct UserAgentParser {
+    browsers: Vec<Rule>,
+    os: Vec<Rule>,
+    devices: Vec<Rule>,
+}
+
+impl UserAgentParser {
+    pub fn from_yaml(yaml: &str) -> Result<Self, UserAgentRulesError> {
+        let file: RulesFile = serde_yaml::from_str(yaml)?;
+        let compile = |rules: Vec<RawRule>, family_key: &str, version_prefix: &str| {
+            rules.into_iter()
+                .map(|raw| Rule::compile(raw, family_key, version_prefix))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            browsers: compile(file.user_agent_parsers, "family_replacement", "")?,
+            os: compile(file.os_parsers, "os_replacement", "os_")?,
+            devices: compile(file.device_parsers, "device_replacement", "device_")?,
+        })
     }
-    if ua.contains("Thunder Client") {
-        return Some("Thunder Client");
+
+    pub fn embedded() -> Self {
+        Self::from_yaml(EMBEDDED_RULES).expect("embedded user agent rules are valid")
     }
-    // Edge must come before Chrome (Edge UA contains "Chrome")
-    if ua.contains("Edg/") || ua.contains("Edge/") {
-        return Some("Edge");
+
+    pub fn parse(&self, ua: &str) -> ParsedUserAgent {
+        let (browser, browser_version) = split(first_match(&self.browsers, ua));
+        let (os, os_version) = split(first_match(&self.os, ua));
+        let device = first_match(&self.devices, ua).and_then(|(family, _)| family);
+
+        let device_class = detect_device_class(ua, browser.as_deref(), os.as_deref(), device.as_deref());
+        let engine = detect_engine(ua, os.as_deref()).map(str::to_string);
+
+        ParsedUserAgent { browser, browser_version, os, os_version, device_class, engine }
     }
-    // Opera/OPR must come before Chrome
-    if ua.contains("OPR/") || ua.contains("Opera") {
-        return Some("Opera");
+}
+
+fn split(found: Option<(Option<String>, Vec<String>)>) -> (Option<String>, Option<String>) {
+    match found {
+        Some((family, versions)) => (family, Some(versions.join(".")).filter(|v| !v.is_empty())),
+        None => (None, None),
     }
-    // Brave embeds "Chrome" too, check for Brave first
-    if ua.contains("Brave") {
-        return Some("Brave");
+}
+
+/// Loads the rules once at startup. Without a path, the embedded rules are used.
+pub fn init(rules_path: Option<&str>) -> Result<(), UserAgentRulesError> {
+    let parser = match rules_path {
+        Some(path) => {
+            let yaml = std::fs::read_to_string(Path::new(path))
+                .map_err(|source| UserAgentRulesError::Io { path: path.to_string(), source })?;
+            UserAgentParser::from_yaml(&yaml)?
+        }
+        None => UserAgentParser::embedded(),
+    };
+
+    if PARSER.set(parser).is_err() {
+        tracing::warn!("User agent rules were already loaded, ignoring reload");
     }
-    // Vivaldi
-    if ua.contains("Vivaldi") {
-        return Some("Vivaldi");
+    Ok(())
+}
+
+/// Parses a raw User-Agent string with the rules loaded by [`init`].
+///
+/// Examples of [`ParsedUserAgent::device_name`]:
+/// - `"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 ... Chrome/120.0"` → `"Chrome on Windows"`
+/// - `"PostmanRuntime/7.36.0"` → `"Postman"`
+/// - `"Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) ... Firefox/121.0"` → `"Firefox on macOS"`
+pub fn parse(user_agent: &str) -> ParsedUserAgent {
+    PARSER.get_or_init(UserAgentParser::embedded).parse(user_agent)
+}
+
+fn detect_device_class(ua: &str, browser: Option<&str>, os: Option<&str>, device: Option<&str>) -> DeviceClass {
+    if device == Some("Spider") {
+        return DeviceClass::Bot;
     }
-    // Samsung Internet
-    if ua.contains("SamsungBrowser") {
-        return Some("Samsung Internet");
+    if browser.is_some_and(|b| CLI_CLIENTS.contains(&b)) {
+        return DeviceClass::Cli;
     }
-    // Chrome must come before Safari (Safari UA doesn't contain "Chrome")
-    if ua.contains("Chrome/") || ua.contains("CriOS/") {
-        return Some("Chrome");
+    // Android tablets omit "Mobile"; iPadOS 13+ pretends to be a Mac unless it's in the UA
+    if device == Some("iPad") || ua.contains("Tablet") || (os == Some("Android") && !ua.contains("Mobile")) {
+        return DeviceClass::Tablet;
     }
-    // Safari (must be after Chrome/Edge/Opera checks)
-    if ua.contains("Safari/") {
-        return Some("Safari");
+    if ua.contains("Mobi") || matches!(os, Some("iOS" | "Android")) {
+        return DeviceClass::Mobile;
     }
-    if ua.contains("Firefox/") || ua.contains("FxiOS/") {
-        return Some("Firefox");
+    if os.is_some() {
+        return DeviceClass::Desktop;
     }
 
-    None
+    DeviceClass::Unknown
 }
 
-fn detect_os(ua: &str) -> Option<&'static str> {
-    if ua.contains("Windows") {
-        return Some("Windows");
+fn detect_engine(ua: &str, os: Option<&str>) -> Option<&'static str> {
+    // Order matters: Blink and EdgeHTML UAs also claim AppleWebKit, and every
+    // iOS browser is WebKit regardless of its brand
+    if ua.contains("Trident/") || ua.contains("MSIE ") {
+        return Some("Trident");
+    }
+    if ua.contains("Edge/") {
+        return Some("EdgeHTML");
     }
-    // iOS must come before macOS (iOS UA contains "Mac OS X")
-    if ua.contains("iPhone") || ua.contains("iPad") || ua.contains("iPod") {
-        return Some("iOS");
+    if os == Some("iOS") && ua.contains("AppleWebKit") {
+        return Some("WebKit");
     }
-    if ua.contains("Macintosh") || ua.contains("Mac OS X") {
-        return Some("macOS");
+    if ua.contains("Chrome/") || ua.contains("Chromium/") {
+        return Some("Blink");
     }
-    // Android must come before Linux (Android UA contains "Linux")
-    if ua.contains("Android") {
-        return Some("Android");
+    if ua.contains("AppleWebKit") {
+        return Some("WebKit");
     }
-    if ua.contains("CrOS") {
-        return Some("ChromeOS");
+    if ua.contains("Gecko/") {
+        return Some("Gecko");
     }
-    if ua.contains("Linux") {
-        return Some("Linux");
+    if ua.contains("Presto/") {
+        return Some("Presto");
     }
 
     None
@@ -97,48 +275,105 @@ mod tests {
     #[test]
     fn test_chrome_windows() {
         let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
-        assert_eq!(parse_device_name(ua), "Chrome on Windows");
+        assert_eq!(parse(ua).device_name(), "Chrome on Windows");
     }
 
     #[test]
     fn test_firefox_macos() {
         let ua = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:121.0) Gecko/20100101 Firefox/121.0";
-        assert_eq!(parse_device_name(ua), "Firefox on macOS");
+        assert_eq!(parse(ua).device_name(), "Firefox on macOS");
     }
 
     #[test]
     fn test_safari_macos() {
         let ua = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15";
-        assert_eq!(parse_device_name(ua), "Safari on macOS");
+        assert_eq!(parse(ua).device_name(), "Safari on macOS");
     }
 
     #[test]
     fn test_edge_windows() {
         let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0";
-        assert_eq!(parse_device_name(ua), "Edge on Windows");
+        assert_eq!(parse(ua).device_name(), "Edge on Windows");
     }
 
     #[test]
     fn test_postman() {
         let ua = "PostmanRuntime/7.36.0";
-        assert_eq!(parse_device_name(ua), "Postman");
+        assert_eq!(parse(ua).device_name(), "Postman");
     }
 
     #[test]
     fn test_chrome_android() {
         let ua = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
-        assert_eq!(parse_device_name(ua), "Chrome on Android");
+        assert_eq!(parse(ua).device_name(), "Chrome on Android");
     }
 
     #[test]
     fn test_safari_ios() {
         let ua = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1";
-        assert_eq!(parse_device_name(ua), "Safari on iOS");
+        assert_eq!(parse(ua).device_name(), "Safari on iOS");
+    }
+
+    #[test]
+    fn test_versions_class_and_engine() {
+        let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.130 Safari/537.36 Edg/120.0.2210.91";
+        let parsed = parse(ua);
+        assert_eq!(parsed.browser.as_deref(), Some("Edge"));
+        assert_eq!(parsed.browser_version.as_deref(), Some("120.0.2210"));
+        assert_eq!(parsed.os_version.as_deref(), Some("10"));
+        assert_eq!(parsed.device_class, DeviceClass::Desktop);
+        assert_eq!(parsed.engine.as_deref(), Some("Blink"));
+
+        let ua = "Mozilla/5.0 (iPad; CPU OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/120.0.6099.119 Mobile/15E148 Safari/604.1";
+        let parsed = parse(ua);
+        assert_eq!(parsed.device_name(), "Chrome on iOS");
+        assert_eq!(parsed.os_version.as_deref(), Some("17.2"));
+        assert_eq!(parsed.device_class, DeviceClass::Tablet);
+        assert_eq!(parsed.engine.as_deref(), Some("WebKit"));
+
+        let ua = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+        assert_eq!(parse(ua).device_class, DeviceClass::Mobile);
+        assert_eq!(parse("curl/8.4.0").device_class, DeviceClass::Cli);
+    }
+
+    #[test]
+    fn test_bots() {
+        let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        let parsed = parse(ua);
+        assert_eq!(parsed.device_class, DeviceClass::Bot);
+        assert_eq!(parsed.browser.as_deref(), Some("Googlebot"));
+        assert_eq!(parsed.browser_version.as_deref(), Some("2.1"));
+
+        let headless = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36";
+        assert_eq!(parse(headless).device_class, DeviceClass::Bot);
+        let firefox = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:121.0) Gecko/20100101 Firefox/121.0";
+        assert_eq!(parse(firefox).device_class, DeviceClass::Desktop);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let yaml = r#"
+user_agent_parsers:
+  - regex: '(MyApp)/(\d+)\.(\d+)'
+    family_replacement: 'My $1'
+os_parsers:
+  - regex: 'Build (\d+)'
+    os_replacement: 'MyOS'
+    os_v1_replacement: '$1'
+"#;
+        let parser = UserAgentParser::from_yaml(yaml).unwrap();
+        let parsed = parser.parse("MyApp/3.1 Build 42");
+        assert_eq!(parsed.device_name(), "My MyApp on MyOS");
+        assert_eq!(parsed.browser_version.as_deref(), Some("3.1"));
+        assert_eq!(parsed.os_version.as_deref(), Some("42"));
+
+        assert!(UserAgentParser::from_yaml("user_agent_parsers:\n  - regex: '('\n").is_err());
     }
 
     #[test]
     fn test_unknown() {
         let ua = "some-random-client/1.0";
-        assert_eq!(parse_device_name(ua), "Unknown device");
+        assert_eq!(parse(ua).device_name(), "Unknown device");
+        assert_eq!(parse(ua).device_class, DeviceClass::Unknown);
     }
 }
@@ -1,53 +1,64 @@
-mod modules;
-mod common;
-mod schema;
-
-use actix_cors::Cors;
-use actix_web::{web, App, HttpServer, http::header};
-use common::{config::AppConfig, logging, database::DbPool};
-
+use actix_web::{web, App, HttpServer};
+use clap::Parser;
+use rust_modular_hexagonal_api_template::{app::{build_app, AppState}, common, modules};
+use common::{config::{AppConfig, ConfigArgs}, live_config::{self, LiveConfig}, logging, metrics};
 
+/// Serves the HTTP API.
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load env/config first so RUST_LOG is available
-    let config = AppConfig::init();
+    let cli = Cli::parse();
+    let config = AppConfig::init(&cli.config);
     
-    // Init logging after env is loaded
-    logging::init();
-    
-    let app_config = config.clone();
-    let pool = common::database::init(&config.database_url);
-    let config_pool = pool.clone();
-
-    let server_addr = format!("{}:{}", config.server_address, config.server_port);
+    // Init logging after env is loaded; log lines and spans are flushed when `_logging` drops
+    let _logging = logging::init(&config);
 
-    tracing::info!("Starting server at http://{}", server_addr);
-
-    let allowed_origin = config.app_url.clone();
+    common::user_agent_parser::init(config.auth.user_agent_rules_path.as_deref())
+        .expect("Failed to load user agent rules");
+    common::geoip::init(&config).expect("Failed to open GeoIP databases");
+    
+    let pool = common::database::init(&config);
+    if let Err(e) = common::migrations::prepare_on_startup(&pool, &config) {
+        tracing::error!("{}. Run `admin migrate up` or set RUN_MIGRATIONS_ON_BOOT=true.", e);
+        std::process::exit(1);
+    }
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin(&allowed_origin)
-            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
-            .allowed_headers(vec![header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
-            .supports_credentials()
-            .max_age(3600);
+    // SIGHUP reloads CORS origins, the email sender, the log filter and feature flags
+    let live_config = LiveConfig::new(config.clone());
+    live_config::reload_on_sighup(live_config.clone(), cli.config);
+    let state = AppState::new(pool.clone(), live_config.clone());
 
-        App::new()
-            .app_data(web::Data::new(DbPool::clone(&config_pool))) // Need to create pool outside
-            .app_data(web::Data::new(app_config.clone()))
-            .wrap(cors)
-            .wrap(actix_web::middleware::Logger::default()) // Use standard Logger for visible request logs
-            .configure(modules::auth::interfaces::http::routes::config)
-            .configure(modules::users::interfaces::http::routes::config)
-            .configure(modules::posts::interfaces::http::routes::config)
+    if config.jobs.in_process {
+        modules::jobs::infrastructure::worker::spawn(pool, live_config, state.clock.clone());
+    }
+    let server_addr = format!("{}:{}", config.server.address, config.server.port);
 
+    if let Some(metrics_addr) = config.metrics.bind_address.clone() {
+        let state = state.clone();
+        let metrics_server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .route("/metrics", web::get().to(metrics::scrape))
+        })
+        .workers(1)
+        // Stops with the runtime when the API server shuts down
+        .disable_signals()
+        .bind(&metrics_addr)?
+        .run();
+        tracing::info!("Serving metrics at http://{}/metrics", metrics_addr);
+        actix_web::rt::spawn(metrics_server);
+    }
 
-            .route("/", web::get().to(|| async { "Hello from Rust Hexagonal API!" }))
-    })
+    tracing::info!("Starting server at http://{}", server_addr);
 
-    .bind(server_addr)?
-    .run()
-    .await
+    HttpServer::new(move || build_app(state.clone()))
+        .bind(server_addr)?
+        .run()
+        .await
 }
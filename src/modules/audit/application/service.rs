@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::common::{clock::Clock, errors::AppError, middleware::current_request};
+use crate::modules::audit::domain::{
+    entity::{AuditEntry, AuditEvent, AuditFilter, AuditRecord, ChainVerification, GENESIS_HASH},
+    repository::AuditRepository,
+    service::AuditLog,
+};
+use tracing::instrument;
+
+/// Events read per query while verifying the chain.
+const VERIFY_BATCH: i64 = 1_000;
+
+pub struct AuditService<R: AuditRepository> {
+    repo: R,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: AuditRepository> AuditService<R> {
+    pub fn new(repo: R, clock: Arc<dyn Clock>) -> Self {
+        Self { repo, clock }
+    }
+
+    #[instrument(name = "AuditService::search", skip_all)]
+    pub async fn search(&self, filter: AuditFilter, limit: i64, offset: i64) -> Result<Vec<AuditEvent>, AppError> {
+        self.repo.search(filter, limit, offset).await
+    }
+
+    /// What happened to the user's account, newest first.
+    #[instrument(name = "AuditService::security_activity", skip_all, fields(%user_id))]
+    pub async fn security_activity(&self, user_id: Uuid, limit: i64, offset: i64) -> Result<Vec<AuditEvent>, AppError> {
+        let filter = AuditFilter { target_id: Some(user_id), ..AuditFilter::default() };
+        self.repo.search(filter, limit, offset).await
+    }
+
+    /// Recomputes every hash from the first event on and checks each event
+    /// points at its predecessor.
+    #[instrument(name = "AuditService::verify_chain", skip_all)]
+    pub async fn verify_chain(&self) -> Result<ChainVerification, AppError> {
+        let mut verification = ChainVerification { checked: 0, first_broken_id: None, last_hash: None };
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let mut after_id = 0;
+
+        loop {
+            let events = self.repo.chain(after_id, VERIFY_BATCH).await?;
+            let Some(last) = events.last() else { break };
+            after_id = last.id;
+
+            for event in &events {
+                verification.checked += 1;
+                if event.prev_hash != prev_hash || !event.is_intact() {
+                    verification.first_broken_id = Some(event.id);
+                    return Ok(verification);
+                }
+                prev_hash = event.hash.clone();
+            }
+            verification.last_hash = Some(prev_hash.clone());
+        }
+
+        Ok(verification)
+    }
+}
+
+#[async_trait]
+impl<R: AuditRepository> AuditLog for AuditService<R> {
+    async fn record(&self, entry: AuditEntry) {
+        let action = entry.action;
+        let mut record = AuditRecord::new(entry, self.clock.now());
+        if let Some(request) = current_request() {
+            record.ip_address = request.client_ip.map(|ip| ip.to_string());
+            record.user_agent = request.user_agent;
+            record.request_id = Some(request.id.as_str().to_string());
+        }
+
+        if let Err(e) = self.repo.append(record).await {
+            tracing::error!("Failed to record audit event {}: {:?}", action, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::clock::FakeClock;
+    use crate::modules::audit::{domain::entity::AuditAction, infrastructure::memory_repository::InMemoryAuditRepository};
+
+    fn service() -> (AuditService<InMemoryAuditRepository>, InMemoryAuditRepository) {
+        let repo = InMemoryAuditRepository::new();
+        (AuditService::new(repo.clone(), Arc::new(FakeClock::frozen())), repo)
+    }
+
+    #[tokio::test]
+    async fn test_events_are_chained() {
+        let (audit, _) = service();
+        let user_id = Uuid::new_v4();
+        audit.record(AuditEntry::new(AuditAction::LoginFailed).target(user_id).with("reason", "wrong_password")).await;
+        audit.record(AuditEntry::new(AuditAction::LoginSucceeded).actor(user_id).target(user_id)).await;
+
+        let events = audit.security_activity(user_id, 10, 0).await.unwrap();
+        let (newest, oldest) = (&events[0], &events[1]);
+        assert_eq!(newest.action, "login_succeeded");
+        assert_eq!(oldest.prev_hash, GENESIS_HASH);
+        assert_eq!(newest.prev_hash, oldest.hash);
+        assert_eq!(oldest.metadata["reason"], "wrong_password");
+
+        let verification = audit.verify_chain().await.unwrap();
+        assert!(verification.is_intact());
+        assert_eq!(verification.checked, 2);
+        assert_eq!(verification.last_hash.as_deref(), Some(newest.hash.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain_finds_edited_and_removed_events() {
+        let (audit, repo) = service();
+        for role in ["editor", "admin", "auditor"] {
+            audit.record(AuditEntry::new(AuditAction::RoleAssigned).target(Uuid::new_v4()).with("role", role)).await;
+        }
+        let ids: Vec<i64> = repo.events().iter().map(|e| e.id).collect();
+
+        repo.tamper(|events| events[1].metadata["role"] = "viewer".into());
+        assert_eq!(audit.verify_chain().await.unwrap().first_broken_id, Some(ids[1]));
+
+        // Removing an event breaks the link of the one after it
+        let (audit, repo) = service();
+        for _ in 0..3 {
+            audit.record(AuditEntry::new(AuditAction::Logout)).await;
+        }
+        let ids: Vec<i64> = repo.events().iter().map(|e| e.id).collect();
+        repo.tamper(|events| { events.remove(1); });
+        assert_eq!(audit.verify_chain().await.unwrap().first_broken_id, Some(ids[2]));
+    }
+}
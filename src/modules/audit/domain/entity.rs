@@ -0,0 +1,231 @@
+use chrono::{NaiveDateTime, Timelike};
+use diesel::{Queryable, Selectable, Insertable, Identifiable};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use strum::{Display, EnumString};
+use uuid::Uuid;
+use crate::schema::audit_events;
+
+/// The `prev_hash` of the first event in the chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditAction {
+    LoginSucceeded,
+    /// `reason` is `unknown_email` (with the `email_sha256` of the lowercased
+    /// address tried), `wrong_password` or `account_disabled`.
+    LoginFailed,
+    TokenRefreshed,
+    /// A refresh with a revoked, expired or wrong token. `reason` says which;
+    /// `invalid_token` may mean a stolen token was replayed.
+    RefreshFailed,
+    Logout,
+    PasswordResetRequested,
+    PasswordReset,
+    /// The user followed the "this wasn't me" link of a new sign-in alert.
+    UnrecognizedSignInReported,
+    /// One session, by its owner.
+    SessionRevoked,
+    /// All sessions of the target, or all but `except_session_id`.
+    SessionsRevoked,
+    UserCreated,
+    UserDeactivated,
+    UserVerified,
+    RoleAssigned,
+    RoleRemoved,
+    AuditLogExported,
+    /// An admin sent a template to an address of their choice.
+    TestEmailSent,
+    /// An admin took an address off the suppression list.
+    EmailUnsuppressed,
+}
+
+/// A security-relevant action, as reported by the service performing it.
+/// The audit log adds the time and the details of the current request.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    /// Who did it. `None` for anonymous requests, the admin CLI and jobs.
+    pub actor_id: Option<Uuid>,
+    /// The user whose account was affected.
+    pub target_id: Option<Uuid>,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction) -> Self {
+        Self { action, actor_id: None, target_id: None, metadata: serde_json::Map::new() }
+    }
+
+    pub fn actor(mut self, actor_id: impl Into<Option<Uuid>>) -> Self {
+        self.actor_id = actor_id.into();
+        self
+    }
+
+    pub fn target(mut self, target_id: impl Into<Option<Uuid>>) -> Self {
+        self.target_id = target_id.into();
+        self
+    }
+
+    pub fn with(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Stores the SHA-256 of `value` as lowercase hex rather than the value,
+    /// for input that may hold a secret, e.g. a password typed into the email
+    /// field. Events are never deleted.
+    pub fn with_digest(self, key: &str, value: &str) -> Self {
+        self.with(key, format!("{:x}", Sha256::digest(value.as_bytes())))
+    }
+}
+
+/// An event ready to be chained to the latest stored one.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub occurred_at: NaiveDateTime,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+impl AuditRecord {
+    pub fn new(entry: AuditEntry, occurred_at: NaiveDateTime) -> Self {
+        // Postgres keeps microseconds; anything finer would change the hash on the way back
+        let occurred_at = occurred_at.with_nanosecond(occurred_at.nanosecond() / 1_000 * 1_000).unwrap_or(occurred_at);
+        Self {
+            occurred_at,
+            action: entry.action.to_string(),
+            actor_id: entry.actor_id,
+            target_id: entry.target_id,
+            ip_address: None,
+            user_agent: None,
+            request_id: None,
+            metadata: serde_json::Value::Object(entry.metadata),
+        }
+    }
+
+    /// SHA-256 over `prev_hash` and every field, as lowercase hex.
+    pub fn hash(&self, prev_hash: &str) -> String {
+        // A JSON array keeps field boundaries unambiguous, and object keys are
+        // serialized sorted, so JSONB's reordering doesn't matter
+        let content = serde_json::json!([
+            prev_hash,
+            self.occurred_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            self.action,
+            self.actor_id,
+            self.target_id,
+            self.ip_address,
+            self.user_agent,
+            self.request_id,
+            self.metadata,
+        ]);
+        format!("{:x}", Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    pub fn chained_to(self, prev_hash: String) -> NewAuditEvent {
+        let hash = self.hash(&prev_hash);
+        NewAuditEvent {
+            occurred_at: self.occurred_at,
+            action: self.action,
+            actor_id: self.actor_id,
+            target_id: self.target_id,
+            ip_address: self.ip_address,
+            user_agent: self.user_agent,
+            request_id: self.request_id,
+            metadata: self.metadata,
+            prev_hash,
+            hash,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = audit_events)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub occurred_at: NaiveDateTime,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEvent {
+    /// Whether the stored hash still matches the event's fields.
+    pub fn is_intact(&self) -> bool {
+        let record = AuditRecord {
+            occurred_at: self.occurred_at,
+            action: self.action.clone(),
+            actor_id: self.actor_id,
+            target_id: self.target_id,
+            ip_address: self.ip_address.clone(),
+            user_agent: self.user_agent.clone(),
+            request_id: self.request_id.clone(),
+            metadata: self.metadata.clone(),
+        };
+        record.hash(&self.prev_hash) == self.hash
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = audit_events)]
+pub struct NewAuditEvent {
+    pub occurred_at: NaiveDateTime,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// Narrows an audit search. Unset fields match everything; `from` is inclusive, `to` exclusive.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.actor_id.is_none_or(|id| event.actor_id == Some(id))
+            && self.target_id.is_none_or(|id| event.target_id == Some(id))
+            && self.action.is_none_or(|action| event.action == action.to_string())
+            && self.from.is_none_or(|from| event.occurred_at >= from)
+            && self.to.is_none_or(|to| event.occurred_at < to)
+    }
+}
+
+/// The outcome of walking the hash chain from the first event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainVerification {
+    pub checked: u64,
+    /// The first event whose hash or link to its predecessor doesn't match.
+    pub first_broken_id: Option<i64>,
+    /// Keep this somewhere else to detect later removal of the newest events,
+    /// which the chain alone can't reveal.
+    pub last_hash: Option<String>,
+}
+
+impl ChainVerification {
+    pub fn is_intact(&self) -> bool {
+        self.first_broken_id.is_none()
+    }
+}
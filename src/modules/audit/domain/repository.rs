@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use super::entity::{AuditEvent, AuditFilter, AuditRecord};
+use crate::common::errors::AppError;
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Chains the record to the newest event and stores it. Appends are
+    /// serialized, so the chain never forks.
+    async fn append(&self, record: AuditRecord) -> Result<AuditEvent, AppError>;
+    /// Newest first.
+    async fn search(&self, filter: AuditFilter, limit: i64, offset: i64) -> Result<Vec<AuditEvent>, AppError>;
+    /// Events with an id above `after_id`, oldest first, for walking the chain.
+    async fn chain(&self, after_id: i64, limit: i64) -> Result<Vec<AuditEvent>, AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn AuditRepository>`.
+#[async_trait]
+impl<T: AuditRepository + ?Sized> AuditRepository for std::sync::Arc<T> {
+    async fn append(&self, record: AuditRecord) -> Result<AuditEvent, AppError> {
+        (**self).append(record).await
+    }
+
+    async fn search(&self, filter: AuditFilter, limit: i64, offset: i64) -> Result<Vec<AuditEvent>, AppError> {
+        (**self).search(filter, limit, offset).await
+    }
+
+    async fn chain(&self, after_id: i64, limit: i64) -> Result<Vec<AuditEvent>, AppError> {
+        (**self).chain(after_id, limit).await
+    }
+}
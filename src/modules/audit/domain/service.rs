@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use super::entity::AuditEntry;
+
+/// Where services report security-relevant actions.
+#[async_trait]
+pub trait AuditLog: Send + Sync {
+    /// Stores the entry with the time and the current request's client.
+    /// The action already happened, so failures are logged rather than returned.
+    async fn record(&self, entry: AuditEntry);
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn AuditLog>`.
+#[async_trait]
+impl<T: AuditLog + ?Sized> AuditLog for std::sync::Arc<T> {
+    async fn record(&self, entry: AuditEntry) {
+        (**self).record(entry).await
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use crate::common::errors::AppError;
+use crate::modules::audit::domain::{
+    entity::{AuditEvent, AuditFilter, AuditRecord, GENESIS_HASH},
+    repository::AuditRepository,
+};
+
+/// Keeps audit events in memory for tests. Clones share the same data.
+#[derive(Clone, Default)]
+pub struct InMemoryAuditRepository {
+    events: Arc<Mutex<Vec<AuditEvent>>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All events, oldest first.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Changes stored events behind the chain's back, which the database doesn't allow.
+    pub fn tamper(&self, change: impl FnOnce(&mut Vec<AuditEvent>)) {
+        change(&mut self.events.lock().unwrap());
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn append(&self, record: AuditRecord) -> Result<AuditEvent, AppError> {
+        let mut events = self.events.lock().unwrap();
+        let prev_hash = events.last().map_or_else(|| GENESIS_HASH.to_string(), |e| e.hash.clone());
+        let new = record.chained_to(prev_hash);
+        let event = AuditEvent {
+            id: events.last().map_or(1, |e| e.id + 1),
+            occurred_at: new.occurred_at,
+            action: new.action,
+            actor_id: new.actor_id,
+            target_id: new.target_id,
+            ip_address: new.ip_address,
+            user_agent: new.user_agent,
+            request_id: new.request_id,
+            metadata: new.metadata,
+            prev_hash: new.prev_hash,
+            hash: new.hash,
+        };
+        events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn search(&self, filter: AuditFilter, limit: i64, offset: i64) -> Result<Vec<AuditEvent>, AppError> {
+        let events = self.events.lock().unwrap();
+        Ok(events.iter().rev()
+            .filter(|e| filter.matches(e))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn chain(&self, after_id: i64, limit: i64) -> Result<Vec<AuditEvent>, AppError> {
+        let events = self.events.lock().unwrap();
+        Ok(events.iter().filter(|e| e.id > after_id).take(limit.max(0) as usize).cloned().collect())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod diesel_repository;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory_repository;
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::common::errors::AppError;
+use crate::modules::audit::domain::entity::{AuditAction, AuditEvent, AuditFilter};
+
+/// Filters of the admin search and export. Times are UTC, e.g. `2026-10-19T09:00:00`.
+#[derive(Debug, Deserialize)]
+pub struct AuditQueryDto {
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl AuditQueryDto {
+    pub fn filter(&self) -> Result<AuditFilter, AppError> {
+        let action = self.action.as_deref()
+            .map(|action| AuditAction::from_str(action).map_err(|_| AppError::BadRequest(format!("Unknown audit action '{}'", action))))
+            .transpose()?;
+
+        Ok(AuditFilter {
+            actor_id: self.actor_id,
+            target_id: self.target_id,
+            action,
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQueryDto {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEventDto {
+    pub id: i64,
+    pub occurred_at: NaiveDateTime,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl From<AuditEvent> for AuditEventDto {
+    fn from(e: AuditEvent) -> Self {
+        Self {
+            id: e.id,
+            occurred_at: e.occurred_at,
+            action: e.action,
+            actor_id: e.actor_id,
+            target_id: e.target_id,
+            ip_address: e.ip_address,
+            user_agent: e.user_agent,
+            request_id: e.request_id,
+            metadata: e.metadata,
+            prev_hash: e.prev_hash,
+            hash: e.hash,
+        }
+    }
+}
+
+/// An event in a user's own security activity. Leaves out who acted, which
+/// may be an admin, and the chain fields.
+#[derive(Debug, Serialize)]
+pub struct SecurityActivityDto {
+    pub action: String,
+    pub occurred_at: NaiveDateTime,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+}
+
+impl From<AuditEvent> for SecurityActivityDto {
+    fn from(e: AuditEvent) -> Self {
+        Self {
+            action: e.action,
+            occurred_at: e.occurred_at,
+            ip_address: e.ip_address,
+            user_agent: e.user_agent,
+            details: e.metadata,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaginationDto {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// `(limit, offset)` of a 1-based page, at most 100 per page.
+pub fn page_bounds(page: Option<i64>, per_page: Option<i64>) -> (i64, i64) {
+    let limit = per_page.filter(|n| *n > 0).unwrap_or(50).min(100);
+    let offset = page.filter(|n| *n > 0).map_or(0, |page| (page - 1) * limit);
+    (limit, offset)
+}
+
+const CSV_COLUMNS: [&str; 11] = [
+    "id", "occurred_at", "action", "actor_id", "target_id", "ip_address",
+    "user_agent", "request_id", "metadata", "prev_hash", "hash",
+];
+
+/// Renders events as RFC 4180 CSV with a header row.
+pub fn to_csv(events: &[AuditEvent]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push_str("\r\n");
+    for e in events {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let fields = [
+            e.id.to_string(),
+            e.occurred_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            e.action.clone(),
+            optional(e.actor_id.map(|id| id.to_string())),
+            optional(e.target_id.map(|id| id.to_string())),
+            optional(e.ip_address.clone()),
+            optional(e.user_agent.clone()),
+            optional(e.request_id.clone()),
+            e.metadata.to_string(),
+            e.prev_hash.clone(),
+            e.hash.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes the field when needed. Text a spreadsheet would run as a formula,
+/// such as a user agent starting with `=`, gets a leading `'`; the JSON
+/// export keeps the exact values.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_fields_are_quoted_and_defused() {
+        assert_eq!(csv_field("login_failed"), "login_failed");
+        assert_eq!(csv_field(r#"{"reason":"wrong_password","x":1}"#), r#""{""reason"":""wrong_password"",""x"":1}""#);
+        assert_eq!(csv_field("curl/8.0\nforged,row"), "\"curl/8.0\nforged,row\"");
+        assert_eq!(csv_field("=HYPERLINK(\"http://evil\")"), "\"'=HYPERLINK(\"\"http://evil\"\")\"");
+    }
+}
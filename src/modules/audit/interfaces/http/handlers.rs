@@ -0,0 +1,95 @@
+use std::sync::Arc;
+use actix_web::{http::header, web, HttpResponse};
+use crate::app::AppState;
+use crate::common::errors::AppError;
+use crate::modules::audit::{
+    application::service::AuditService,
+    domain::{entity::{AuditAction, AuditEntry}, repository::AuditRepository, service::AuditLog},
+};
+use crate::modules::auth::interfaces::http::middleware::{AuthenticatedUser, RequireAdmin};
+use super::dto::{page_bounds, to_csv, AuditEventDto, AuditQueryDto, ExportFormat, ExportQueryDto, PaginationDto, SecurityActivityDto};
+
+pub type AuditServiceImpl = AuditService<Arc<dyn AuditRepository>>;
+
+/// Most events a single export returns; narrow the filters to get older ones.
+const EXPORT_MAX_ROWS: i64 = 10_000;
+
+pub async fn list_events(
+    _admin: RequireAdmin,
+    state: web::Data<AppState>,
+    query: web::Query<AuditQueryDto>,
+) -> Result<HttpResponse, AppError> {
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+    let events = state.audit.search(query.filter()?, limit, offset).await?;
+
+    let dtos: Vec<AuditEventDto> = events.into_iter().map(AuditEventDto::from).collect();
+    Ok(HttpResponse::Ok().json(dtos))
+}
+
+/// Downloads the newest matching events. Exports are audited themselves.
+pub async fn export_events(
+    admin: RequireAdmin,
+    state: web::Data<AppState>,
+    query: web::Query<AuditQueryDto>,
+    export: web::Query<ExportQueryDto>,
+) -> Result<HttpResponse, AppError> {
+    let filter = query.filter()?;
+    let mut events = state.audit.search(filter.clone(), EXPORT_MAX_ROWS + 1, 0).await?;
+    let truncated = events.len() > EXPORT_MAX_ROWS as usize;
+    events.truncate(EXPORT_MAX_ROWS as usize);
+
+    state.audit.record(
+        AuditEntry::new(AuditAction::AuditLogExported)
+            .actor(admin.0.user_id)
+            .with("format", if export.format == ExportFormat::Csv { "csv" } else { "json" })
+            .with("rows", events.len())
+            .with("filter", serde_json::json!({
+                "actor_id": filter.actor_id,
+                "target_id": filter.target_id,
+                "action": filter.action.map(|action| action.to_string()),
+                "from": filter.from,
+                "to": filter.to,
+            })),
+    ).await;
+
+    let mut response = HttpResponse::Ok();
+    response.insert_header(("X-Export-Truncated", truncated.to_string()));
+    Ok(match export.format {
+        ExportFormat::Csv => response
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"audit-events.csv\""))
+            .body(to_csv(&events)),
+        ExportFormat::Json => response
+            .insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"audit-events.json\""))
+            .json(events.into_iter().map(AuditEventDto::from).collect::<Vec<_>>()),
+    })
+}
+
+pub async fn verify_chain(
+    _admin: RequireAdmin,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let verification = state.audit.verify_chain().await?;
+    if !verification.is_intact() {
+        tracing::error!("Audit log chain is broken at event {:?}", verification.first_broken_id);
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "intact": verification.is_intact(),
+        "checked": verification.checked,
+        "first_broken_id": verification.first_broken_id,
+        "last_hash": verification.last_hash,
+    })))
+}
+
+pub async fn security_activity(
+    user: AuthenticatedUser,
+    state: web::Data<AppState>,
+    query: web::Query<PaginationDto>,
+) -> Result<HttpResponse, AppError> {
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+    let events = state.audit.security_activity(user.user_id, limit, offset).await?;
+
+    let dtos: Vec<SecurityActivityDto> = events.into_iter().map(SecurityActivityDto::from).collect();
+    Ok(HttpResponse::Ok().json(dtos))
+}
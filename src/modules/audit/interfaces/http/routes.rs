@@ -0,0 +1,14 @@
+use actix_web::web;
+use super::handlers::{export_events, list_events, security_activity, verify_chain};
+
+/// Must be registered before the users module, whose `/users` scope would
+/// otherwise take `/users/me/security-activity`.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin/audit")
+            .route("/events", web::get().to(list_events))
+            .route("/events/export", web::get().to(export_events))
+            .route("/verify", web::get().to(verify_chain))
+    );
+    cfg.route("/users/me/security-activity", web::get().to(security_activity));
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use crate::common::{clock::Clock, config::AppConfig, errors::AppError};
+use crate::modules::auth::domain::repository::{SessionRepository, verification::VerificationTokenRepository};
+use tracing::instrument;
+
+/// Purges auth data that can no longer be used. Run periodically by the job worker.
+pub struct AuthCleanupService<S: SessionRepository, T: VerificationTokenRepository> {
+    session_repo: S,
+    token_repo: T,
+    clock: Arc<dyn Clock>,
+    config: AppConfig,
+}
+
+impl<S: SessionRepository, T: VerificationTokenRepository> AuthCleanupService<S, T> {
+    pub fn new(session_repo: S, token_repo: T, clock: Arc<dyn Clock>, config: AppConfig) -> Self {
+        Self { session_repo, token_repo, clock, config }
+    }
+
+    /// Deletes sessions that stopped being usable more than
+    /// `SESSION_RETENTION_DAYS` ago, whether through expiry, revocation or
+    /// idleness.
+    #[instrument(name = "AuthCleanupService::purge_stale_sessions", skip_all)]
+    pub async fn purge_stale_sessions(&self) -> Result<usize, AppError> {
+        let cutoff = self.clock.now() - chrono::Duration::days(self.config.auth.session_retention_days);
+        self.session_repo.delete_stale(cutoff, self.config.auth.session_idle_timeout()).await
+    }
+
+    /// Deletes expired email verification and password reset tokens.
+    #[instrument(name = "AuthCleanupService::purge_expired_tokens", skip_all)]
+    pub async fn purge_expired_tokens(&self) -> Result<usize, AppError> {
+        self.token_repo.delete_expired(self.clock.now()).await
+    }
+}
@@ -1,3 +1,4 @@
 pub mod token_service;
 pub mod service;
+pub mod cleanup;
 
@@ -1,45 +1,59 @@
+use std::sync::Arc;
 use uuid::Uuid;
-use chrono::Utc;
-use crate::common::{errors::AppError, config::AppConfig};
+use crate::common::{errors::AppError, config::AppConfig, clock::Clock, geoip, id_generator::IdGenerator, metrics, signed_token::TokenSigner, unit_of_work::UnitOfWork};
 use crate::modules::users::domain::{entity::{User, NewUser}, repository::UserRepository};
 use crate::modules::auth::{
     domain::{
-        entity::{UserSession, NewUserSession, token::{NewEmailVerificationToken, NewPasswordResetToken}},
-        repository::{SessionRepository, verification::VerificationTokenRepository},
+        entity::{UserSession, NewUserSession, NOT_ME_TOKEN_PURPOSE, token::{NewEmailVerificationToken, NewPasswordResetToken}},
+        repository::{AuthRepositories, SessionRepository, verification::VerificationTokenRepository},
+        risk::{detect_impossible_travel, LoginRisk, RiskSignal, Sighting},
     },
     infrastructure::password_service::PasswordService,
     application::token_service::TokenService,
 };
-use crate::modules::email::domain::service::{EmailService, EmailRecipient};
+use crate::modules::email::domain::service::{EmailService, EmailRecipient, SignInAlert};
+use crate::modules::audit::domain::{entity::{AuditAction, AuditEntry}, service::AuditLog};
+use tracing::instrument;
 
-pub struct AuthService<U, S, V, E> 
+pub struct AuthService<U, S, V, E, W> 
 where 
     U: UserRepository, 
     S: SessionRepository,
     V: VerificationTokenRepository,
-    E: EmailService
+    E: EmailService,
+    W: UnitOfWork<Repositories = AuthRepositories<U, S, V>>,
 {
     user_repo: U,
     session_repo: S,
     verification_repo: V,
     email_service: E,
-    token_service: TokenService,
+    unit_of_work: W,
+    token_service: Arc<TokenService>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    audit: Arc<dyn AuditLog>,
     config: AppConfig,
 }
 
-impl<U, S, V, E> AuthService<U, S, V, E>
+impl<U, S, V, E, W> AuthService<U, S, V, E, W>
 where 
     U: UserRepository, 
     S: SessionRepository,
     V: VerificationTokenRepository,
-    E: EmailService 
+    E: EmailService,
+    W: UnitOfWork<Repositories = AuthRepositories<U, S, V>>,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_repo: U, 
         session_repo: S, 
         verification_repo: V, 
         email_service: E, 
-        token_service: TokenService, 
+        unit_of_work: W,
+        token_service: Arc<TokenService>, 
+        clock: Arc<dyn Clock>,
+        ids: Arc<dyn IdGenerator>,
+        audit: Arc<dyn AuditLog>,
         config: AppConfig
     ) -> Self {
         Self {
@@ -47,13 +61,18 @@ where
             session_repo,
             verification_repo,
             email_service,
+            unit_of_work,
             token_service,
+            clock,
+            ids,
+            audit,
             config,
         }
     }
 
-    pub async fn register(&self, email: String, password: String) -> Result<User, AppError> {
-        if self.user_repo.find_by_email(&email)?.is_some() {
+    #[instrument(name = "AuthService::register", skip_all)]
+    pub async fn register(&self, email: String, password: String, locale: Option<String>) -> Result<User, AppError> {
+        if self.user_repo.find_by_email(&email).await?.is_some() {
             return Err(AppError::Conflict("Email already exists".to_string()));
         }
 
@@ -62,28 +81,32 @@ where
         let new_user = NewUser {
             email: email.clone(),
             password_hash,
+            locale,
         };
 
-        let user = self.user_repo.create(new_user)?;
-
         // Generate verification token
-        let token = Uuid::new_v4().to_string(); // Use a simpler token or same logic
+        let token = self.ids.new_id().to_string();
         let token_hash = PasswordService::hash_password(&token)?;
         
-        let expiration = Utc::now().naive_utc() + chrono::Duration::hours(24);
+        let expiration = self.clock.now() + self.config.auth.email_verification_lifetime();
 
-        let new_token = NewEmailVerificationToken {
-            user_id: user.id,
-            token_hash,
-            expires_at: expiration,
-        };
-
-        self.verification_repo.create_email_verification(new_token)?;
+        // A user without a verification token could never verify, so both are created or neither
+        let user = self.unit_of_work.transaction(|repos| async move {
+            let user = repos.users.create(new_user).await?;
+            repos.tokens.create_email_verification(NewEmailVerificationToken {
+                user_id: user.id,
+                token_hash,
+                expires_at: expiration,
+            }).await?;
+            Ok(user)
+        }).await?;
 
         // Send email
         let recipient = EmailRecipient {
             email: email.clone(),
             name: None,
+            locale: user.locale.clone(),
+            user_id: Some(user.id),
         };
 
         self.email_service.send_verification_email(&recipient, &format!("{}:{}", user.id, token)).await?;
@@ -92,31 +115,88 @@ where
         Ok(user)
     }
 
+    /// Creates a user directly, without a verification email. For operators, e.g. to bootstrap an admin.
+    #[instrument(name = "AuthService::create_user", skip_all)]
+    pub async fn create_user(&self, email: String, password: String, locale: Option<String>, verified: bool) -> Result<User, AppError> {
+        if self.user_repo.find_by_email(&email).await?.is_some() {
+            return Err(AppError::Conflict("Email already exists".to_string()));
+        }
+
+        let new_user = NewUser {
+            email,
+            password_hash: PasswordService::hash_password(&password)?,
+            locale,
+        };
+
+        let user = self.unit_of_work.transaction(|repos| async move {
+            let user = repos.users.create(new_user).await?;
+            if verified {
+                repos.users.verify_user(user.id).await?;
+            }
+            repos.users.find_by_id(user.id).await?.ok_or(AppError::InternalError)
+        }).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::UserCreated).target(user.id).with("verified", verified)).await;
+        Ok(user)
+    }
+
+    /// Blocks future sign-ins and ends every session of the user.
+    #[instrument(name = "AuthService::deactivate_user", skip_all, fields(%user_id))]
+    pub async fn deactivate_user(&self, user_id: Uuid) -> Result<(), AppError> {
+        let user = self.user_repo.find_by_id(user_id).await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        if !user.is_active {
+            return Err(AppError::Conflict("User is already deactivated".to_string()));
+        }
+
+        self.unit_of_work.transaction(|repos| async move {
+            repos.users.set_active(user.id, false).await?;
+            repos.sessions.revoke_all_for_user(user.id).await
+        }).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::UserDeactivated).target(user_id)).await;
+        Ok(())
+    }
+
+    #[instrument(name = "AuthService::login", skip_all)]
     pub async fn login(&self, email: String, password: String, user_agent: Option<String>, ip_address: Option<String>) -> Result<(String, String), AppError> {
-        let user = self.user_repo.find_by_email(&email)?
-            .ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
+        let Some(user) = self.user_repo.find_by_email(&email).await? else {
+            metrics::record_login_failure("unknown_email");
+            self.audit.record(AuditEntry::new(AuditAction::LoginFailed).with("reason", "unknown_email").with_digest("email_sha256", &email.trim().to_lowercase())).await;
+            return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+        };
 
         if !PasswordService::verify_password(&password, &user.password_hash)? {
+            metrics::record_login_failure("wrong_password");
+            self.audit.record(AuditEntry::new(AuditAction::LoginFailed).target(user.id).with("reason", "wrong_password")).await;
             return Err(AppError::Unauthorized("Invalid credentials".to_string()));
         }
 
         if !user.is_active {
+            metrics::record_login_failure("account_disabled");
+            self.audit.record(AuditEntry::new(AuditAction::LoginFailed).target(user.id).with("reason", "account_disabled")).await;
             return Err(AppError::Forbidden("Account is disabled".to_string()));
         }
 
         // Update last login timestamp
-        self.user_repo.update_last_login(user.id)?;
+        self.user_repo.update_last_login(user.id, self.clock.now()).await?;
         
-        let (session, refresh_token) = self.create_session(user.id, user_agent, ip_address).await?;
+        let (session, refresh_token) = self.create_session(&user, user_agent, ip_address).await?;
         
-        let roles = self.user_repo.get_roles(user.id)?;
-        let access_token = self.token_service.generate_access_token(user.id, session.id, roles)?;
+        let roles = self.user_repo.get_roles(user.id).await?;
+        let access_token = self.token_service.generate_access_token(user.id, session.id, roles, self.clock.now())?;
+
+        metrics::record_login_success();
+        self.audit.record(
+            AuditEntry::new(AuditAction::LoginSucceeded).actor(user.id).target(user.id).with("session_id", session.id.to_string()),
+        ).await;
 
         // Return "session_id:refresh_token"
         let combined_refresh_token = format!("{}:{}", session.id, refresh_token);
         Ok((access_token, combined_refresh_token)) 
     }
 
+    #[instrument(name = "AuthService::verify_email", skip_all)]
     pub async fn verify_email(&self, token: String) -> Result<(), AppError> {
         let parts: Vec<&str> = token.split(':').collect();
         if parts.len() != 2 {
@@ -126,7 +206,7 @@ where
         let user_id = Uuid::parse_str(parts[0]).map_err(|_| AppError::Unauthorized("Invalid token format".to_string()))?;
         let token_raw = parts[1];
         
-        let verification = self.verification_repo.find_email_verification_by_user(user_id)?
+        let verification = self.verification_repo.find_email_verification_by_user(user_id).await?
             .ok_or(AppError::Unauthorized("Invalid or expired token".to_string()))?;
 
         if verification.used {
@@ -137,16 +217,17 @@ where
              return Err(AppError::Unauthorized("Invalid token".to_string()));
         }
         
-        if verification.expires_at < Utc::now().naive_utc() {
+        if verification.expires_at < self.clock.now() {
              return Err(AppError::Unauthorized("Token expired".to_string()));
         }
         
-        self.user_repo.verify_user(user_id)?;
-        
-        self.verification_repo.mark_email_verification_as_used(verification.id)?;
-        Ok(())
+        self.unit_of_work.transaction(|repos| async move {
+            repos.users.verify_user(user_id).await?;
+            repos.tokens.mark_email_verification_as_used(verification.id).await
+        }).await
     }
 
+    #[instrument(name = "AuthService::refresh_token", skip_all)]
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<(String, String), AppError> {
         let parts: Vec<&str> = refresh_token.split(':').collect();
         if parts.len() != 2 {
@@ -156,34 +237,55 @@ where
         let session_id = Uuid::parse_str(parts[0]).map_err(|_| AppError::Unauthorized("Invalid token format".to_string()))?;
         let token_raw = parts[1];
 
-        let session = self.session_repo.find_by_id(session_id)?
+        let session = self.session_repo.find_by_id(session_id).await?
             .ok_or_else(|| AppError::Unauthorized("Session not found".to_string()))?;
 
+        let refresh_failed = |reason: &str| {
+            AuditEntry::new(AuditAction::RefreshFailed)
+                .target(session.user_id)
+                .with("reason", reason)
+                .with("session_id", session_id.to_string())
+        };
+
         if session.is_revoked {
+            self.audit.record(refresh_failed("session_revoked")).await;
             return Err(AppError::Unauthorized("Session revoked".to_string()));
         }
 
-        if session.expires_at < Utc::now().naive_utc() {
+        let now = self.clock.now();
+        if session.expires_at < now {
+            self.audit.record(refresh_failed("session_expired")).await;
             return Err(AppError::Unauthorized("Session expired".to_string()));
         }
 
+        if !session.is_usable(now, self.config.auth.session_idle_timeout()) {
+            self.session_repo.revoke(session_id).await?;
+            self.audit.record(refresh_failed("session_idle")).await;
+            return Err(AppError::Unauthorized("Session expired due to inactivity".to_string()));
+        }
+
         if !PasswordService::verify_password(token_raw, &session.refresh_token_hash)? {
             // Potential reuse/theft detection: revoke session?
-             self.session_repo.revoke(session_id)?;
+             self.session_repo.revoke(session_id).await?;
+             self.audit.record(refresh_failed("invalid_token")).await;
              return Err(AppError::Unauthorized("Invalid refresh token".to_string()));
         }
 
         // Generate new pair
-        let new_refresh_token = self.token_service.generate_refresh_token();
+        let new_refresh_token = self.ids.new_id().to_string();
         let new_hash = PasswordService::hash_password(&new_refresh_token)?;
         
-        let new_expires_at = Utc::now().naive_utc() + chrono::Duration::days(self.config.jwt_refresh_expiration_days);
+        let new_expires_at = now + chrono::Duration::days(self.config.auth.refresh_token_days);
         
-        self.session_repo.update_refresh_token(session.id, new_hash, new_expires_at)?;
+        self.session_repo.update_refresh_token(session.id, new_hash, new_expires_at).await?;
+        metrics::record_refresh_token_rotation();
+        self.audit.record(
+            AuditEntry::new(AuditAction::TokenRefreshed).actor(session.user_id).target(session.user_id).with("session_id", session.id.to_string()),
+        ).await;
         
         // Get roles for access token
-        let roles = self.user_repo.get_roles(session.user_id)?;
-        let access_token = self.token_service.generate_access_token(session.user_id, session.id, roles)?;
+        let roles = self.user_repo.get_roles(session.user_id).await?;
+        let access_token = self.token_service.generate_access_token(session.user_id, session.id, roles, now)?;
         
         // Return combined token
         let combined_refresh_token = format!("{}:{}", session.id, new_refresh_token);
@@ -191,8 +293,9 @@ where
         Ok((access_token, combined_refresh_token))
     }
 
+    #[instrument(name = "AuthService::request_email_verification", skip_all)]
     pub async fn request_email_verification(&self, email: &str) -> Result<(), AppError> {
-        let user = self.user_repo.find_by_email(email)?
+        let user = self.user_repo.find_by_email(email).await?
             .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         if user.is_verified {
@@ -200,7 +303,7 @@ where
         }
 
         // Generate new token
-        let token = Uuid::new_v4().to_string();
+        let token = self.ids.new_id().to_string();
         let token_hash = PasswordService::hash_password(&token)?;
         
         // Save to DB
@@ -208,47 +311,54 @@ where
         let new_token = NewEmailVerificationToken {
             user_id: user.id,
             token_hash,
-            expires_at: chrono::Utc::now().naive_utc() + chrono::Duration::hours(24),
+            expires_at: self.clock.now() + self.config.auth.email_verification_lifetime(),
         };
         
-        self.verification_repo.create_email_verification(new_token)?;
+        self.verification_repo.create_email_verification(new_token).await?;
         
         use crate::modules::email::domain::service::EmailRecipient;
         // Send email
         let recipient = EmailRecipient {
             email: email.to_string(),
             name: None,
+            locale: user.locale.clone(),
+            user_id: Some(user.id),
         };
         self.email_service.send_verification_email(&recipient, &format!("{}:{}", user.id, token)).await?;
         
         Ok(())
     }
 
+    #[instrument(name = "AuthService::request_password_reset", skip_all)]
     pub async fn request_password_reset(&self, email: &str) -> Result<(), AppError> {
-        let user = self.user_repo.find_by_email(email)?
+        let user = self.user_repo.find_by_email(email).await?
             .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
             
-        let token = Uuid::new_v4().to_string();
+        let token = self.ids.new_id().to_string();
         let token_hash = PasswordService::hash_password(&token)?;
         
         let reset_token = NewPasswordResetToken {
             user_id: user.id,
             token_hash,
-            expires_at: Utc::now().naive_utc() + chrono::Duration::minutes(15), 
+            expires_at: self.clock.now() + self.config.auth.password_reset_lifetime(),
         };
 
-        self.verification_repo.create_password_reset(reset_token)?;
+        self.verification_repo.create_password_reset(reset_token).await?;
 
         let recipient = EmailRecipient {
             email: email.to_string(),
             name: None,
+            locale: user.locale.clone(),
+            user_id: Some(user.id),
         };
 
         self.email_service.send_password_reset_email(&recipient, &format!("{}:{}", user.id, token)).await?;
 
+        self.audit.record(AuditEntry::new(AuditAction::PasswordResetRequested).target(user.id)).await;
         Ok(())
     }
 
+    #[instrument(name = "AuthService::reset_password", skip_all)]
     pub async fn reset_password(&self, token: &str, new_password: &str) -> Result<(), AppError> {
         let parts: Vec<&str> = token.split(':').collect();
         if parts.len() != 2 {
@@ -258,7 +368,7 @@ where
         let user_id = Uuid::parse_str(parts[0]).map_err(|_| AppError::Unauthorized("Invalid token format".to_string()))?;
         let token_raw = parts[1];
 
-        let reset_token = self.verification_repo.find_password_reset_by_user(user_id)?
+        let reset_token = self.verification_repo.find_password_reset_by_user(user_id).await?
             .ok_or(AppError::Unauthorized("Invalid or expired token".to_string()))?;
 
         if reset_token.used {
@@ -269,52 +379,675 @@ where
              return Err(AppError::Unauthorized("Invalid token".to_string()));
         }
 
-        if reset_token.expires_at < Utc::now().naive_utc() {
+        if reset_token.expires_at < self.clock.now() {
              return Err(AppError::ValidationError(validator::ValidationErrors::new())); 
         }
 
         let password_hash = PasswordService::hash_password(new_password)?;
         
-        self.user_repo.update_password(user_id, &password_hash)?;
-        
-        self.verification_repo.mark_password_reset_as_used(reset_token.id)?;
-        
+        // Marking the token used in the same transaction keeps it single-use
+        self.unit_of_work.transaction(|repos| async move {
+            repos.users.update_password(user_id, &password_hash).await?;
+            repos.tokens.mark_password_reset_as_used(reset_token.id).await
+        }).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::PasswordReset).actor(user_id).target(user_id)).await;
+        Ok(())
+    }
+
+    /// Handles a "this wasn't me" link from a new sign-in alert: revokes the
+    /// reported session and emails the user a password reset link.
+    #[instrument(name = "AuthService::report_unrecognized_sign_in", skip_all)]
+    pub async fn report_unrecognized_sign_in(&self, token: &str) -> Result<(), AppError> {
+        let signer = TokenSigner::from_config(&self.config);
+        let session_id = signer.verify(NOT_ME_TOKEN_PURPOSE, token, self.clock.now())?;
+        let session_id = Uuid::parse_str(&session_id).map_err(|_| AppError::Unauthorized("Invalid token format".to_string()))?;
+
+        let session = self.session_repo.find_by_id(session_id).await?
+            .ok_or_else(|| AppError::Unauthorized("Session not found".to_string()))?;
+        // The link stays valid until the session would have expired; following
+        // it again must not send another reset email. A session that is merely
+        // revoked, e.g. by the attacker logging out, still gets its first report.
+        if !self.session_repo.mark_reported(session.id).await? {
+            return Ok(());
+        }
+        self.session_repo.revoke(session.id).await?;
+
+        let user = self.user_repo.find_by_id(session.user_id).await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        tracing::warn!("User {} reported sign-in {} as not theirs, session revoked", user.id, session.id);
+        self.audit.record(
+            AuditEntry::new(AuditAction::UnrecognizedSignInReported).actor(user.id).target(user.id).with("session_id", session.id.to_string()),
+        ).await;
+
+        self.request_password_reset(&user.email).await
+    }
+
+    #[instrument(name = "AuthService::logout", skip_all, fields(%user_id, %session_id))]
+    pub async fn logout(&self, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+        self.session_repo.revoke(session_id).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::Logout).actor(user_id).target(user_id).with("session_id", session_id.to_string())).await;
+        Ok(())
+    }
+
+    /// `actor_id` is the user themselves, or `None` for the admin CLI.
+    #[instrument(name = "AuthService::revoke_all_sessions", skip_all, fields(%user_id))]
+    pub async fn revoke_all_sessions(&self, user_id: Uuid, actor_id: Option<Uuid>) -> Result<(), AppError> {
+        self.session_repo.revoke_all_for_user(user_id).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::SessionsRevoked).actor(actor_id).target(user_id)).await;
         Ok(())
     }
 
-    pub fn logout(&self, session_id: Uuid) -> Result<(), AppError> {
-        self.session_repo.revoke(session_id)
+    #[instrument(name = "AuthService::revoke_other_sessions", skip_all, fields(%user_id, %current_session_id))]
+    pub async fn revoke_other_sessions(&self, user_id: Uuid, current_session_id: Uuid) -> Result<(), AppError> {
+        self.session_repo.revoke_all_for_user_except(user_id, current_session_id).await?;
+
+        self.audit.record(
+            AuditEntry::new(AuditAction::SessionsRevoked).actor(user_id).target(user_id).with("except_session_id", current_session_id.to_string()),
+        ).await;
+        Ok(())
     }
 
-    pub fn revoke_all_sessions(&self, user_id: Uuid) -> Result<(), AppError> {
-        self.session_repo.revoke_all_for_user(user_id)
+    #[instrument(name = "AuthService::revoke_session", skip_all, fields(%user_id, %session_id))]
+    pub async fn revoke_session(&self, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+        let session = self.find_owned_session(user_id, session_id).await?;
+        self.session_repo.revoke(session.id).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::SessionRevoked).actor(user_id).target(user_id).with("session_id", session.id.to_string())).await;
+        Ok(())
+    }
+
+    #[instrument(name = "AuthService::rename_session", skip_all, fields(%user_id, %session_id))]
+    pub async fn rename_session(&self, user_id: Uuid, session_id: Uuid, device_name: &str) -> Result<UserSession, AppError> {
+        let device_name = device_name.trim();
+        if device_name.is_empty() {
+            return Err(AppError::BadRequest("Device name must not be blank".to_string()));
+        }
+
+        let session = self.find_owned_session(user_id, session_id).await?;
+        self.session_repo.update_device_name(session.id, device_name).await
+    }
+
+    /// Sessions of other users are reported as missing so their ids can't be probed.
+    async fn find_owned_session(&self, user_id: Uuid, session_id: Uuid) -> Result<UserSession, AppError> {
+        self.session_repo.find_by_id(session_id).await?
+            .filter(|s| s.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))
     }
 
     // Helper to create session
-    async fn create_session(&self, user_id: Uuid, user_agent: Option<String>, ip_address: Option<String>) -> Result<(UserSession, String), AppError> {
-        let refresh_token = self.token_service.generate_refresh_token();
+    async fn create_session(&self, user: &User, user_agent: Option<String>, ip_address: Option<String>) -> Result<(UserSession, String), AppError> {
+        let refresh_token = self.ids.new_id().to_string();
         let refresh_hash = PasswordService::hash_password(&refresh_token)?;
 
-        let expires_at = Utc::now().naive_utc() + chrono::Duration::days(self.config.jwt_refresh_expiration_days);
+        let now = self.clock.now();
+        let expires_at = now + chrono::Duration::days(self.config.auth.refresh_token_days);
         
-        let device_name = user_agent.as_deref().map(crate::common::user_agent_parser::parse_device_name);
+        let parsed = user_agent.as_deref().map(crate::common::user_agent_parser::parse);
+        let device_name = parsed.as_ref().map(|p| p.device_name());
+
+        let location = ip_address.as_deref().and_then(geoip::lookup).unwrap_or_default();
+        let risk = self.assess_login_risk(user, device_name.as_deref(), ip_address.as_deref(), &location, now).await?;
+        let place = location.display_name();
 
         let new_session = NewUserSession {
-            user_id,
+            user_id: user.id,
             refresh_token_hash: refresh_hash,
             user_agent,
             ip_address,
             device_name,
             expires_at,
+            browser: parsed.as_ref().and_then(|p| p.browser.clone()),
+            browser_version: parsed.as_ref().and_then(|p| p.browser_version.clone()),
+            os: parsed.as_ref().and_then(|p| p.os.clone()),
+            os_version: parsed.as_ref().and_then(|p| p.os_version.clone()),
+            device_class: parsed.as_ref().map(|p| p.device_class.to_string()),
+            engine: parsed.as_ref().and_then(|p| p.engine.clone()),
+            country_code: location.country_code,
+            country: location.country,
+            city: location.city,
+            asn: location.asn,
+            as_org: location.as_org,
+            latitude: location.latitude,
+            longitude: location.longitude,
+            risk_score: risk.score(),
         };
 
-        let session = self.session_repo.create(new_session)?;
+        let session = self.session_repo.create(new_session).await?;
+
+        if risk.is_suspicious() {
+            tracing::info!("Risky sign-in for user {} (session {}): {:?}", user.id, session.id, risk.signals);
+            self.send_new_sign_in_alert(user, &session, place).await;
+        }
         
         Ok((session, refresh_token))
     }
 
-    pub fn get_active_sessions(&self, user_id: Uuid) -> Result<Vec<UserSession>, AppError> {
-        self.session_repo.find_active_by_user(user_id)
+    async fn assess_login_risk(
+        &self,
+        user: &User,
+        device_name: Option<&str>,
+        ip_address: Option<&str>,
+        location: &geoip::GeoLocation,
+        now: chrono::NaiveDateTime,
+    ) -> Result<LoginRisk, AppError> {
+        let mut risk = LoginRisk::default();
+
+        // The very first sign-in is expected to come from an unknown device
+        let lookback = now - chrono::Duration::days(self.config.auth.new_sign_in_lookback_days);
+        if user.last_login_at.is_some() && !self.session_repo.has_recent_session(user.id, device_name, ip_address, lookback).await? {
+            risk.add(Some(RiskSignal::NewDevice));
+        }
+
+        if let Some((latitude, longitude)) = location.coordinates() {
+            let previous = self.session_repo.find_latest_located(user.id).await?.and_then(|s| {
+                Some(Sighting { latitude: s.latitude?, longitude: s.longitude?, at: s.last_used_at })
+            });
+            if let Some(previous) = previous {
+                risk.add(detect_impossible_travel(previous, Sighting { latitude, longitude, at: now }));
+            }
+        }
+
+        Ok(risk)
+    }
+
+    /// Failing to send the alert must not block the sign-in, so errors are only logged.
+    async fn send_new_sign_in_alert(&self, user: &User, session: &UserSession, location: Option<String>) {
+        let token = TokenSigner::from_config(&self.config)
+            .sign(NOT_ME_TOKEN_PURPOSE, &session.id.to_string(), Some(session.expires_at));
+
+        let recipient = EmailRecipient {
+            email: user.email.clone(),
+            name: None,
+            locale: user.locale.clone(),
+            user_id: Some(user.id),
+        };
+        let alert = SignInAlert {
+            device: session.device_name.clone().unwrap_or_else(|| "Unknown device".to_string()),
+            ip_address: session.ip_address.clone(),
+            location,
+            signed_in_at: session.created_at,
+        };
+
+        if let Err(e) = self.email_service.send_new_sign_in_alert(&recipient, &alert, &token).await {
+            tracing::warn!("Failed to send new sign-in alert for session {}: {:?}", session.id, e);
+        }
+    }
+
+    #[instrument(name = "AuthService::get_active_sessions", skip_all, fields(%user_id))]
+    pub async fn get_active_sessions(&self, user_id: Uuid) -> Result<Vec<UserSession>, AppError> {
+        let idle_since = self.clock.now() - self.config.auth.session_idle_timeout();
+        self.session_repo.find_active_by_user(user_id, idle_since).await
+    }
+
+    /// Sessions of all users that can still be used, for the `active_sessions` gauge.
+    #[instrument(name = "AuthService::count_active_sessions", skip_all)]
+    pub async fn count_active_sessions(&self) -> Result<i64, AppError> {
+        let idle_since = self.clock.now() - self.config.auth.session_idle_timeout();
+        self.session_repo.count_active(idle_since).await
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::common::{clock::FakeClock, id_generator::SequentialIdGenerator, unit_of_work::InMemoryUnitOfWork};
+    use crate::modules::auth::infrastructure::{
+        memory_repository::InMemorySessionRepository,
+        memory_token_repository::InMemoryVerificationTokenRepository,
+    };
+    use crate::modules::audit::{application::service::AuditService, infrastructure::memory_repository::InMemoryAuditRepository};
+    use crate::modules::email::infrastructure::recording::RecordingEmailService;
+    use crate::modules::users::infrastructure::memory_repository::InMemoryUserRepository;
+
+    const EMAIL: &str = "alice@example.com";
+    const PASSWORD: &str = "Password123!";
+    const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+    const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+    type Repositories = AuthRepositories<InMemoryUserRepository, InMemorySessionRepository, InMemoryVerificationTokenRepository>;
+
+    struct Harness {
+        service: AuthService<
+            InMemoryUserRepository,
+            InMemorySessionRepository,
+            InMemoryVerificationTokenRepository,
+            RecordingEmailService,
+            InMemoryUnitOfWork<Repositories>,
+        >,
+        users: InMemoryUserRepository,
+        sessions: InMemorySessionRepository,
+        emails: RecordingEmailService,
+        audit: InMemoryAuditRepository,
+        clock: FakeClock,
+    }
+
+    fn harness() -> Harness {
+        harness_with(AppConfig::for_tests())
+    }
+
+    fn harness_with(config: AppConfig) -> Harness {
+        let clock = FakeClock::frozen();
+        let repos = AuthRepositories {
+            users: InMemoryUserRepository::new(),
+            sessions: InMemorySessionRepository::with_clock(Arc::new(clock.clone())),
+            tokens: InMemoryVerificationTokenRepository::new(),
+        };
+        let emails = RecordingEmailService::new();
+        let audit = InMemoryAuditRepository::new();
+        let service = AuthService::new(
+            repos.users.clone(),
+            repos.sessions.clone(),
+            repos.tokens.clone(),
+            emails.clone(),
+            InMemoryUnitOfWork::new(repos.clone()),
+            Arc::new(TokenService::new(config.clone())),
+            Arc::new(clock.clone()),
+            Arc::new(SequentialIdGenerator::new()),
+            Arc::new(AuditService::new(audit.clone(), Arc::new(clock.clone()))),
+            config,
+        );
+        Harness { service, users: repos.users, sessions: repos.sessions, emails, audit, clock }
+    }
+
+    impl Harness {
+        async fn register(&self) -> User {
+            self.service.register(EMAIL.to_string(), PASSWORD.to_string(), None).await.unwrap()
+        }
+
+        async fn login(&self, password: &str) -> Result<(String, String), AppError> {
+            self.service.login(EMAIL.to_string(), password.to_string(), Some(FIREFOX.to_string()), Some("203.0.113.1".to_string())).await
+        }
+
+        fn session_id(refresh_token: &str) -> Uuid {
+            Uuid::parse_str(refresh_token.split(':').next().unwrap()).unwrap()
+        }
+    }
+
+    fn assert_unauthorized<T: std::fmt::Debug>(result: Result<T, AppError>) {
+        assert!(matches!(result, Err(AppError::Unauthorized(_))), "expected Unauthorized, got {:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_register_sends_verification_email() {
+        let h = harness();
+        let user = h.register().await;
+
+        assert!(!user.is_verified);
+        let email = h.emails.last_sent(EMAIL, "verification").unwrap();
+        assert_eq!(email.recipient.user_id, Some(user.id));
+        assert!(email.token.unwrap().starts_with(&user.id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_duplicate_email() {
+        let h = harness();
+        h.register().await;
+
+        let result = h.service.register(EMAIL.to_string(), PASSWORD.to_string(), None).await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_marks_user_verified_once() {
+        let h = harness();
+        let user = h.register().await;
+        let token = h.emails.last_token(EMAIL, "verification").unwrap();
+
+        h.service.verify_email(token.clone()).await.unwrap();
+        assert!(h.users.find_by_id(user.id).await.unwrap().unwrap().is_verified);
+
+        // The token is used up and there is no other one
+        assert_unauthorized(h.service.verify_email(token).await);
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_rejects_wrong_token() {
+        let h = harness();
+        let user = h.register().await;
+
+        assert_unauthorized(h.service.verify_email(format!("{}:not-the-token", user.id)).await);
+        assert_unauthorized(h.service.verify_email("garbage".to_string()).await);
+        assert!(!h.users.find_by_id(user.id).await.unwrap().unwrap().is_verified);
+    }
+
+    #[tokio::test]
+    async fn test_resent_verification_replaces_the_previous_token() {
+        let h = harness();
+        h.register().await;
+        let first = h.emails.last_token(EMAIL, "verification").unwrap();
+
+        h.service.request_email_verification(EMAIL).await.unwrap();
+        let second = h.emails.last_token(EMAIL, "verification").unwrap();
+
+        assert_unauthorized(h.service.verify_email(first).await);
+        h.service.verify_email(second).await.unwrap();
+        let result = h.service.request_email_verification(EMAIL).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_checks_credentials() {
+        let h = harness();
+        let user = h.register().await;
+
+        assert_unauthorized(h.login("WrongPassword1!").await);
+        assert_unauthorized(h.service.login(" Bob@example.com".to_string(), PASSWORD.to_string(), None, None).await);
+
+        let (access_token, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let claims = h.service.token_service.verify_access_token(&access_token, h.clock.now()).unwrap();
+        assert_eq!(claims.sub, user.id);
+        assert_eq!(claims.session_id, Harness::session_id(&refresh_token));
+        assert!(h.users.find_by_id(user.id).await.unwrap().unwrap().last_login_at.is_some());
+
+        let audited: Vec<(String, Option<Uuid>, serde_json::Value)> = h.audit.events().into_iter()
+            .map(|e| (e.action, e.actor_id, e.metadata))
+            .collect();
+        assert_eq!(audited, vec![
+            ("login_failed".to_string(), None, serde_json::json!({"reason": "wrong_password"})),
+            ("login_failed".to_string(), None, serde_json::json!({
+                "reason": "unknown_email",
+                // SHA-256 of "bob@example.com"; the address as typed isn't stored
+                "email_sha256": "5ff860bf1190596c7188ab851db691f0f3169c453936e9e1eba2f9a47f7a0018",
+            })),
+            ("login_succeeded".to_string(), Some(user.id), serde_json::json!({"session_id": claims.session_id.to_string()})),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_the_refresh_token() {
+        let h = harness();
+        h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+
+        let (access_token, rotated) = h.service.refresh_token(&refresh_token).await.unwrap();
+        assert_ne!(rotated, refresh_token);
+        assert_eq!(Harness::session_id(&rotated), Harness::session_id(&refresh_token));
+        assert!(h.service.token_service.verify_access_token(&access_token, h.clock.now()).is_ok());
+
+        let (_, again) = h.service.refresh_token(&rotated).await.unwrap();
+        assert_ne!(again, rotated);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_reuse_revokes_the_session() {
+        let h = harness();
+        h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let (_, rotated) = h.service.refresh_token(&refresh_token).await.unwrap();
+
+        // Replaying the old token looks like theft, so the whole session goes
+        assert_unauthorized(h.service.refresh_token(&refresh_token).await);
+        assert_unauthorized(h.service.refresh_token(&rotated).await);
+        let session = h.sessions.find_by_id(Harness::session_id(&rotated)).await.unwrap().unwrap();
+        assert!(session.is_revoked);
+        let reasons: Vec<_> = h.audit.events().into_iter()
+            .filter(|e| e.action == "refresh_failed")
+            .map(|e| e.metadata["reason"].clone())
+            .collect();
+        assert_eq!(reasons, ["invalid_token", "session_revoked"]);
+    }
+
+    #[tokio::test]
+    async fn test_logout_ends_the_session() {
+        let h = harness();
+        let user = h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let session_id = Harness::session_id(&refresh_token);
+
+        h.service.logout(user.id, session_id).await.unwrap();
+
+        assert_unauthorized(h.service.refresh_token(&refresh_token).await);
+        assert!(h.service.get_active_sessions(user.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_full_account_lifecycle() {
+        let h = harness();
+        let user = h.register().await;
+        h.service.verify_email(h.emails.last_token(EMAIL, "verification").unwrap()).await.unwrap();
+
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let (_, refresh_token) = h.service.refresh_token(&refresh_token).await.unwrap();
+        assert_eq!(h.service.get_active_sessions(user.id).await.unwrap().len(), 1);
+
+        h.service.logout(user.id, Harness::session_id(&refresh_token)).await.unwrap();
+        assert!(h.service.get_active_sessions(user.id).await.unwrap().is_empty());
+        assert!(h.users.find_by_id(user.id).await.unwrap().unwrap().is_verified);
+    }
+
+    #[tokio::test]
+    async fn test_reset_password() {
+        let h = harness();
+        h.register().await;
+
+        h.service.request_password_reset(EMAIL).await.unwrap();
+        let token = h.emails.last_token(EMAIL, "password_reset").unwrap();
+        h.service.reset_password(&token, "NewPassword456!").await.unwrap();
+
+        assert_unauthorized(h.login(PASSWORD).await);
+        assert!(h.login("NewPassword456!").await.is_ok());
+
+        // Single use
+        assert_unauthorized(h.service.reset_password(&token, "Another789!").await);
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_rejects_wrong_token() {
+        let h = harness();
+        let user = h.register().await;
+        h.service.request_password_reset(EMAIL).await.unwrap();
+
+        assert_unauthorized(h.service.reset_password(&format!("{}:not-the-token", user.id), "NewPassword456!").await);
+        assert!(h.login(PASSWORD).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_for_unknown_email_sends_nothing() {
+        let h = harness();
+
+        let result = h.service.request_password_reset("nobody@example.com").await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert!(h.emails.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deactivated_user_cannot_sign_in() {
+        let h = harness();
+        let user = h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+
+        h.service.deactivate_user(user.id).await.unwrap();
+
+        assert!(matches!(h.login(PASSWORD).await, Err(AppError::Forbidden(_))));
+        assert_unauthorized(h.service.refresh_token(&refresh_token).await);
+        assert!(matches!(h.service.deactivate_user(user.id).await, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_of_other_users_are_hidden() {
+        let h = harness();
+        h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let session_id = Harness::session_id(&refresh_token);
+
+        let result = h.service.revoke_session(Uuid::new_v4(), session_id).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert!(h.service.refresh_token(&refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_session_can_be_renamed_but_not_blanked() {
+        let h = harness();
+        let user = h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+        let session_id = Harness::session_id(&refresh_token);
+
+        let session = h.service.rename_session(user.id, session_id, "  Work laptop ").await.unwrap();
+        assert_eq!(session.device_name.as_deref(), Some("Work laptop"));
+
+        let result = h.service.rename_session(user.id, session_id, "   ").await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        let session = h.sessions.find_by_id(session_id).await.unwrap().unwrap();
+        assert_eq!(session.device_name.as_deref(), Some("Work laptop"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_from_new_device_sends_alert() {
+        let h = harness();
+        h.register().await;
+
+        // The first sign-in is expected to come from an unknown device
+        h.login(PASSWORD).await.unwrap();
+        h.login(PASSWORD).await.unwrap();
+        assert!(h.emails.last_sent(EMAIL, "new_sign_in").is_none());
+
+        let (_, refresh_token) = h.service
+            .login(EMAIL.to_string(), PASSWORD.to_string(), Some(IPHONE.to_string()), Some("198.51.100.7".to_string()))
+            .await
+            .unwrap();
+        let token = h.emails.last_token(EMAIL, "new_sign_in").unwrap();
+
+        // "This wasn't me" revokes that session and starts a password reset
+        h.service.report_unrecognized_sign_in(&token).await.unwrap();
+        assert_unauthorized(h.service.refresh_token(&refresh_token).await);
+        assert!(h.emails.last_sent(EMAIL, "password_reset").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reporting_unrecognized_sign_in_twice_sends_one_reset() {
+        let h = harness();
+        h.register().await;
+        h.login(PASSWORD).await.unwrap();
+        h.service
+            .login(EMAIL.to_string(), PASSWORD.to_string(), Some(IPHONE.to_string()), Some("198.51.100.7".to_string()))
+            .await
+            .unwrap();
+        let token = h.emails.last_token(EMAIL, "new_sign_in").unwrap();
+
+        h.service.report_unrecognized_sign_in(&token).await.unwrap();
+        let reset_token = h.emails.last_token(EMAIL, "password_reset").unwrap();
+        h.service.report_unrecognized_sign_in(&token).await.unwrap();
+
+        let resets = h.emails.sent().iter().filter(|e| e.template == "password_reset").count();
+        assert_eq!(resets, 1);
+        let reports = h.audit.events().iter().filter(|e| e.action == "unrecognized_sign_in_reported").count();
+        assert_eq!(reports, 1);
+        // The reset link from the first report still works
+        h.service.reset_password(&reset_token, "NewPassword1!").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reporting_revoked_sign_in_still_resets_password() {
+        let h = harness();
+        let user = h.register().await;
+        h.login(PASSWORD).await.unwrap();
+        let (_, refresh_token) = h.service
+            .login(EMAIL.to_string(), PASSWORD.to_string(), Some(IPHONE.to_string()), Some("198.51.100.7".to_string()))
+            .await
+            .unwrap();
+        let token = h.emails.last_token(EMAIL, "new_sign_in").unwrap();
+
+        // The attacker signed out before the user saw the alert
+        h.service.logout(user.id, Harness::session_id(&refresh_token)).await.unwrap();
+        h.service.report_unrecognized_sign_in(&token).await.unwrap();
+
+        assert!(h.emails.last_sent(EMAIL, "password_reset").is_some());
+        assert!(h.audit.events().iter().any(|e| e.action == "unrecognized_sign_in_reported"));
+    }
+
+    #[tokio::test]
+    async fn test_verification_token_expires_after_24_hours() {
+        let h = harness();
+        h.register().await;
+
+        h.clock.advance(Duration::hours(24) + Duration::seconds(1));
+        assert_unauthorized(h.service.verify_email(h.emails.last_token(EMAIL, "verification").unwrap()).await);
+
+        // Still valid at the very end of its lifetime
+        h.service.request_email_verification(EMAIL).await.unwrap();
+        h.clock.advance(Duration::hours(24));
+        h.service.verify_email(h.emails.last_token(EMAIL, "verification").unwrap()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_token_expires_after_15_minutes() {
+        let h = harness();
+        h.register().await;
+
+        h.service.request_password_reset(EMAIL).await.unwrap();
+        h.clock.advance(Duration::minutes(15) + Duration::seconds(1));
+        let token = h.emails.last_token(EMAIL, "password_reset").unwrap();
+        assert!(h.service.reset_password(&token, "NewPassword456!").await.is_err());
+
+        h.service.request_password_reset(EMAIL).await.unwrap();
+        h.clock.advance(Duration::minutes(15));
+        let token = h.emails.last_token(EMAIL, "password_reset").unwrap();
+        h.service.reset_password(&token, "NewPassword456!").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_lifetime_is_configurable() {
+        let mut config = AppConfig::for_tests();
+        config.auth.password_reset_minutes = 60;
+        let h = harness_with(config);
+        h.register().await;
+
+        h.service.request_password_reset(EMAIL).await.unwrap();
+        h.clock.advance(Duration::minutes(60));
+        let token = h.emails.last_token(EMAIL, "password_reset").unwrap();
+        h.service.reset_password(&token, "NewPassword456!").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_access_token_expires_with_the_clock() {
+        let h = harness();
+        h.register().await;
+        let (access_token, _) = h.login(PASSWORD).await.unwrap();
+        let tokens = &h.service.token_service;
+
+        h.clock.advance(Duration::minutes(15) - Duration::seconds(1));
+        assert!(tokens.verify_access_token(&access_token, h.clock.now()).is_ok());
+        h.clock.advance(Duration::seconds(1));
+        assert_unauthorized(tokens.verify_access_token(&access_token, h.clock.now()));
+    }
+
+    #[tokio::test]
+    async fn test_idle_session_expires() {
+        let h = harness();
+        let user = h.register().await;
+        let (_, refresh_token) = h.login(PASSWORD).await.unwrap();
+
+        // Each refresh counts as use and restarts the 72 hour idle timeout
+        h.clock.advance(Duration::hours(72) - Duration::seconds(1));
+        let (_, refresh_token) = h.service.refresh_token(&refresh_token).await.unwrap();
+
+        h.clock.advance(Duration::hours(72));
+        assert!(h.service.get_active_sessions(user.id).await.unwrap().is_empty());
+        assert_unauthorized(h.service.refresh_token(&refresh_token).await);
+        let session = h.sessions.find_by_id(Harness::session_id(&refresh_token)).await.unwrap().unwrap();
+        assert!(session.is_revoked);
+    }
+
+    #[tokio::test]
+    async fn test_session_expires_after_refresh_lifetime() {
+        let mut config = AppConfig::for_tests();
+        config.auth.session_idle_timeout_hours = 24 * 30;
+        let h = harness_with(config);
+        h.register().await;
+        let (_, kept) = h.login(PASSWORD).await.unwrap();
+        let (_, abandoned) = h.login(PASSWORD).await.unwrap();
+
+        h.clock.advance(Duration::days(7) - Duration::seconds(1));
+        let (_, kept) = h.service.refresh_token(&kept).await.unwrap();
+
+        // Refreshing extends the session, the other one runs out
+        h.clock.advance(Duration::seconds(1));
+        assert_unauthorized(h.service.refresh_token(&abandoned).await);
+        assert!(h.service.refresh_token(&kept).await.is_ok());
+    }
+}
@@ -1,9 +1,11 @@
 use jsonwebtoken::{encode, decode, Header, Algorithm, Validation, EncodingKey, DecodingKey};
-use chrono::{Utc, Duration};
+use chrono::{Duration, NaiveDateTime};
 use uuid::Uuid;
 use crate::common::{errors::AppError, config::AppConfig};
 use crate::modules::auth::domain::token::Claims;
 
+/// Signs and verifies access tokens. The caller passes the current time so
+/// expiry follows the service's `Clock`.
 pub struct TokenService {
     config: AppConfig,
 }
@@ -13,10 +15,11 @@ impl TokenService {
         Self { config }
     }
 
-    pub fn generate_access_token(&self, user_id: Uuid, session_id: Uuid, roles: Vec<String>) -> Result<String, AppError> {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::minutes(self.config.jwt_access_expiration_min))
+    pub fn generate_access_token(&self, user_id: Uuid, session_id: Uuid, roles: Vec<String>, now: NaiveDateTime) -> Result<String, AppError> {
+        let expiration = now
+            .checked_add_signed(Duration::minutes(self.config.auth.access_token_minutes))
             .expect("valid timestamp")
+            .and_utc()
             .timestamp() as usize;
 
         let claims = Claims {
@@ -24,13 +27,13 @@ impl TokenService {
             session_id,
             roles,
             exp: expiration,
-            iat: Utc::now().timestamp() as usize,
+            iat: now.and_utc().timestamp() as usize,
         };
 
         encode(
             &Header::default(),
             &claims,
-            &EncodingKey::from_secret(self.config.jwt_secret.as_bytes()),
+            &EncodingKey::from_secret(self.config.auth.jwt_secret.expose().as_bytes()),
         )
         .map_err(|e| {
             tracing::error!("Token generation failed: {}", e);
@@ -38,20 +41,43 @@ impl TokenService {
         })
     }
 
-    pub fn generate_refresh_token(&self) -> String {
-        Uuid::new_v4().to_string()
+    /// Accepts tokens signed with `JWT_SECRET` or, during a key rotation, any of `JWT_PREVIOUS_SECRETS`.
+    /// A token is expired from its `exp` second on (RFC 7519 4.1.4).
+    pub fn verify_access_token(&self, token: &str, now: NaiveDateTime) -> Result<Claims, AppError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        // Checked below against `now` instead of the system time
+        validation.validate_exp = false;
+
+        let claims = std::iter::once(&self.config.auth.jwt_secret)
+            .chain(&self.config.auth.jwt_previous_secrets)
+            .find_map(|secret| decode::<Claims>(token, &DecodingKey::from_secret(secret.expose().as_bytes()), &validation).ok())
+            .map(|data| data.claims)
+            .ok_or_else(|| AppError::Unauthorized("Invalid token".to_string()))?;
+
+        if claims.exp as i64 <= now.and_utc().timestamp() {
+            return Err(AppError::Unauthorized("Invalid token".to_string()));
+        }
+        Ok(claims)
     }
+}
 
-    pub fn verify_access_token(&self, token: &str) -> Result<Claims, AppError> {
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.leeway = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::clock::{Clock, FakeClock};
 
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.config.jwt_secret.as_bytes()),
-            &validation,
-        )
-        .map(|data| data.claims)
-        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))
+    #[test]
+    fn test_access_token_expires_at_exp() {
+        let config = AppConfig::for_tests();
+        let lifetime = Duration::minutes(config.auth.access_token_minutes);
+        let tokens = TokenService::new(config);
+        let clock = FakeClock::frozen();
+        let token = tokens.generate_access_token(Uuid::new_v4(), Uuid::new_v4(), vec![], clock.now()).unwrap();
+
+        clock.advance(lifetime - Duration::seconds(1));
+        assert!(tokens.verify_access_token(&token, clock.now()).is_ok());
+
+        clock.advance(Duration::seconds(1));
+        assert!(tokens.verify_access_token(&token, clock.now()).is_err());
     }
 }
@@ -5,6 +5,10 @@ use uuid::Uuid;
 use crate::schema::user_sessions;
 use crate::modules::users::domain::entity::User;
 
+/// `TokenSigner` purpose of the "this wasn't me" links in new sign-in alerts.
+/// The signed data is the session id.
+pub const NOT_ME_TOKEN_PURPOSE: &str = "not-me";
+
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable, Associations)]
 #[diesel(belongs_to(User))]
 #[diesel(table_name = user_sessions)]
@@ -19,6 +23,33 @@ pub struct UserSession {
     pub expires_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
     pub last_used_at: NaiveDateTime,
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    /// One of `desktop`, `mobile`, `tablet`, `bot`, `cli` or `unknown`.
+    pub device_class: Option<String>,
+    pub engine: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub asn: Option<i64>,
+    pub as_org: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 0-100, see `domain::risk::LoginRisk`.
+    pub risk_score: i32,
+    pub revoked_at: Option<NaiveDateTime>,
+    /// When the owner followed the "this wasn't me" link of its sign-in alert.
+    pub reported_at: Option<NaiveDateTime>,
+}
+
+impl UserSession {
+    /// Whether the session can still be used: not revoked, not past its
+    /// absolute expiry and used within the idle timeout.
+    pub fn is_usable(&self, now: NaiveDateTime, idle_timeout: chrono::Duration) -> bool {
+        !self.is_revoked && self.expires_at > now && self.last_used_at + idle_timeout > now
+    }
 }
 
 #[derive(Debug, Insertable)]
@@ -30,5 +61,70 @@ pub struct NewUserSession {
     pub ip_address: Option<String>,
     pub device_name: Option<String>,
     pub expires_at: NaiveDateTime,
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub device_class: Option<String>,
+    pub engine: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub asn: Option<i64>,
+    pub as_org: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub risk_score: i32,
 }
 pub mod token;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn session(last_used_at: NaiveDateTime, expires_at: NaiveDateTime) -> UserSession {
+        UserSession {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            refresh_token_hash: String::new(),
+            user_agent: None,
+            ip_address: None,
+            device_name: None,
+            is_revoked: false,
+            expires_at,
+            created_at: last_used_at,
+            last_used_at,
+            browser: None,
+            browser_version: None,
+            os: None,
+            os_version: None,
+            device_class: None,
+            engine: None,
+            country_code: None,
+            country: None,
+            city: None,
+            asn: None,
+            as_org: None,
+            latitude: None,
+            longitude: None,
+            risk_score: 0,
+            revoked_at: None,
+            reported_at: None,
+        }
+    }
+
+    #[test]
+    fn test_is_usable_enforces_idle_timeout_and_expiry() {
+        let now = Utc::now().naive_utc();
+        let idle_timeout = Duration::hours(72);
+
+        assert!(session(now - Duration::hours(1), now + Duration::days(1)).is_usable(now, idle_timeout));
+        assert!(!session(now - Duration::hours(73), now + Duration::days(1)).is_usable(now, idle_timeout));
+        assert!(!session(now - Duration::hours(1), now - Duration::seconds(1)).is_usable(now, idle_timeout));
+
+        let mut revoked = session(now, now + Duration::days(1));
+        revoked.is_revoked = true;
+        assert!(!revoked.is_usable(now, idle_timeout));
+    }
+}
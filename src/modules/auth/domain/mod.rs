@@ -1,5 +1,6 @@
 pub mod entity;
 
 pub mod repository;
+pub mod risk;
 pub mod token;
 
@@ -1,15 +1,100 @@
+use async_trait::async_trait;
 use uuid::Uuid;
 use super::entity::{UserSession, NewUserSession};
 use crate::common::errors::AppError;
 
-pub trait SessionRepository {
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
 
-    fn create(&self, session: NewUserSession) -> Result<UserSession, AppError>;
-    fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError>;
-    fn update_last_used(&self, id: Uuid) -> Result<(), AppError>;
-    fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError>;
-    fn revoke(&self, id: Uuid) -> Result<(), AppError>;
-    fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError>;
-    fn find_active_by_user(&self, user_id: Uuid) -> Result<Vec<UserSession>, AppError>;
+    async fn create(&self, session: NewUserSession) -> Result<UserSession, AppError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError>;
+    async fn update_last_used(&self, id: Uuid) -> Result<(), AppError>;
+    async fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError>;
+    async fn revoke(&self, id: Uuid) -> Result<(), AppError>;
+    /// Sets `reported_at` unless it is already set. Returns whether this call set it.
+    async fn mark_reported(&self, id: Uuid) -> Result<bool, AppError>;
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError>;
+    async fn revoke_all_for_user_except(&self, user_id: Uuid, keep_session_id: Uuid) -> Result<(), AppError>;
+    async fn update_device_name(&self, id: Uuid, device_name: &str) -> Result<UserSession, AppError>;
+    /// Sessions that are neither revoked nor expired and were used after `idle_since`.
+    async fn find_active_by_user(&self, user_id: Uuid, idle_since: chrono::NaiveDateTime) -> Result<Vec<UserSession>, AppError>;
+    /// How many sessions of all users are neither revoked nor expired and were used after `idle_since`.
+    async fn count_active(&self, idle_since: chrono::NaiveDateTime) -> Result<i64, AppError>;
+    /// Deletes sessions that expired, were revoked or went idle before `cutoff`. Returns how many.
+    async fn delete_stale(&self, cutoff: chrono::NaiveDateTime, idle_timeout: chrono::Duration) -> Result<usize, AppError>;
+    /// The user's most recently used session with known coordinates.
+    async fn find_latest_located(&self, user_id: Uuid) -> Result<Option<UserSession>, AppError>;
+    /// Whether the user has a session from this device and IP address that was used after `since`.
+    async fn has_recent_session(&self, user_id: Uuid, device_name: Option<&str>, ip_address: Option<&str>, since: chrono::NaiveDateTime) -> Result<bool, AppError>;
 }
+
+/// Lets services hold a shared, swappable `Arc<dyn SessionRepository>`.
+#[async_trait]
+impl<T: SessionRepository + ?Sized> SessionRepository for std::sync::Arc<T> {
+    async fn create(&self, session: NewUserSession) -> Result<UserSession, AppError> {
+        (**self).create(session).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError> {
+        (**self).find_by_id(id).await
+    }
+
+    async fn update_last_used(&self, id: Uuid) -> Result<(), AppError> {
+        (**self).update_last_used(id).await
+    }
+
+    async fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        (**self).update_refresh_token(id, new_hash, new_expires_at).await
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<(), AppError> {
+        (**self).revoke(id).await
+    }
+
+    async fn mark_reported(&self, id: Uuid) -> Result<bool, AppError> {
+        (**self).mark_reported(id).await
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError> {
+        (**self).revoke_all_for_user(user_id).await
+    }
+
+    async fn revoke_all_for_user_except(&self, user_id: Uuid, keep_session_id: Uuid) -> Result<(), AppError> {
+        (**self).revoke_all_for_user_except(user_id, keep_session_id).await
+    }
+
+    async fn update_device_name(&self, id: Uuid, device_name: &str) -> Result<UserSession, AppError> {
+        (**self).update_device_name(id, device_name).await
+    }
+
+    async fn find_active_by_user(&self, user_id: Uuid, idle_since: chrono::NaiveDateTime) -> Result<Vec<UserSession>, AppError> {
+        (**self).find_active_by_user(user_id, idle_since).await
+    }
+
+    async fn count_active(&self, idle_since: chrono::NaiveDateTime) -> Result<i64, AppError> {
+        (**self).count_active(idle_since).await
+    }
+
+    async fn delete_stale(&self, cutoff: chrono::NaiveDateTime, idle_timeout: chrono::Duration) -> Result<usize, AppError> {
+        (**self).delete_stale(cutoff, idle_timeout).await
+    }
+
+    async fn find_latest_located(&self, user_id: Uuid) -> Result<Option<UserSession>, AppError> {
+        (**self).find_latest_located(user_id).await
+    }
+
+    async fn has_recent_session(&self, user_id: Uuid, device_name: Option<&str>, ip_address: Option<&str>, since: chrono::NaiveDateTime) -> Result<bool, AppError> {
+        (**self).has_recent_session(user_id, device_name, ip_address, since).await
+    }
+}
+
+/// The repositories auth use cases change together, handed out bound to one
+/// transaction by a `UnitOfWork`.
+#[derive(Clone)]
+pub struct AuthRepositories<U, S, V> {
+    pub users: U,
+    pub sessions: S,
+    pub tokens: V,
+}
+
 pub mod verification;
@@ -1,22 +1,59 @@
+use async_trait::async_trait;
 
 use uuid::Uuid;
 use crate::modules::auth::domain::entity::token::{EmailVerificationToken, NewEmailVerificationToken, PasswordResetToken, NewPasswordResetToken};
 
 use crate::common::errors::AppError;
 
-pub trait VerificationTokenRepository {
+#[async_trait]
+pub trait VerificationTokenRepository: Send + Sync {
 
-    fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError>;
-    fn find_email_verification_by_user(&self, user_id: Uuid) -> Result<Option<EmailVerificationToken>, AppError>;
+    async fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError>;
+    async fn find_email_verification_by_user(&self, user_id: Uuid) -> Result<Option<EmailVerificationToken>, AppError>;
     // In real app we might search by token hash, but we only have hash. Logic: Find by user, check all valid tokens?
     // Or normally we pass token ID + Token string.
     // If we only send Token String (random), we need to look up which user it belongs to?
     // Usually the link is /verify?token=XYZ&id=USER_ID.
     // So we find by UserID, then verify hash.
     
-    fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError>;
-    fn find_password_reset_by_user(&self, user_id: Uuid) -> Result<Option<PasswordResetToken>, AppError>;
+    async fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError>;
+    async fn find_password_reset_by_user(&self, user_id: Uuid) -> Result<Option<PasswordResetToken>, AppError>;
     
-    fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError>;
-    fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError>;
+    async fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError>;
+    async fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError>;
+
+    /// Deletes email verification and password reset tokens that expired before `before`. Returns how many.
+    async fn delete_expired(&self, before: chrono::NaiveDateTime) -> Result<usize, AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn VerificationTokenRepository>`.
+#[async_trait]
+impl<T: VerificationTokenRepository + ?Sized> VerificationTokenRepository for std::sync::Arc<T> {
+    async fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError> {
+        (**self).create_email_verification(token).await
+    }
+
+    async fn find_email_verification_by_user(&self, user_id: Uuid) -> Result<Option<EmailVerificationToken>, AppError> {
+        (**self).find_email_verification_by_user(user_id).await
+    }
+
+    async fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError> {
+        (**self).create_password_reset(token).await
+    }
+
+    async fn find_password_reset_by_user(&self, user_id: Uuid) -> Result<Option<PasswordResetToken>, AppError> {
+        (**self).find_password_reset_by_user(user_id).await
+    }
+
+    async fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        (**self).mark_email_verification_as_used(token_id).await
+    }
+
+    async fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        (**self).mark_password_reset_as_used(token_id).await
+    }
+
+    async fn delete_expired(&self, before: chrono::NaiveDateTime) -> Result<usize, AppError> {
+        (**self).delete_expired(before).await
+    }
 }
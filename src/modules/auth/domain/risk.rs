@@ -0,0 +1,124 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Anything faster than a commercial flight can't be the same person travelling.
+const MAX_TRAVEL_SPEED_KMH: f64 = 1000.0;
+
+/// GeoIP coordinates are approximate, so short hops are never suspicious.
+const MIN_TRAVEL_DISTANCE_KM: f64 = 500.0;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RiskSignal {
+    /// The device/IP pair wasn't used recently by this user.
+    NewDevice,
+    /// The user would have had to travel `distance_km` in `hours` since their last sign-in.
+    ImpossibleTravel { distance_km: f64, hours: f64 },
+}
+
+impl RiskSignal {
+    fn weight(&self) -> i32 {
+        match self {
+            RiskSignal::NewDevice => 30,
+            RiskSignal::ImpossibleTravel { .. } => 60,
+        }
+    }
+}
+
+/// Where and when a user was seen.
+#[derive(Debug, Clone, Copy)]
+pub struct Sighting {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub at: NaiveDateTime,
+}
+
+/// The risk signals of a sign-in and their combined score from 0 to 100.
+#[derive(Debug, Default)]
+pub struct LoginRisk {
+    pub signals: Vec<RiskSignal>,
+}
+
+impl LoginRisk {
+    pub fn add(&mut self, signal: Option<RiskSignal>) {
+        self.signals.extend(signal);
+    }
+
+    pub fn score(&self) -> i32 {
+        self.signals.iter().map(RiskSignal::weight).sum::<i32>().min(100)
+    }
+
+    pub fn is_suspicious(&self) -> bool {
+        !self.signals.is_empty()
+    }
+}
+
+/// Great-circle distance between two coordinates.
+pub fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+pub fn detect_impossible_travel(previous: Sighting, current: Sighting) -> Option<RiskSignal> {
+    let distance_km = distance_km((previous.latitude, previous.longitude), (current.latitude, current.longitude));
+    if distance_km < MIN_TRAVEL_DISTANCE_KM {
+        return None;
+    }
+
+    let hours = (current.at - previous.at).num_seconds().max(0) as f64 / 3600.0;
+    if hours > 0.0 && distance_km / hours <= MAX_TRAVEL_SPEED_KMH {
+        return None;
+    }
+
+    Some(RiskSignal::ImpossibleTravel {
+        distance_km: distance_km.round(),
+        hours: (hours * 100.0).round() / 100.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    const LONDON: (f64, f64) = (51.5142, -0.0931);
+    const LINKOPING: (f64, f64) = (58.4167, 15.6167);
+    const SYDNEY: (f64, f64) = (-33.8688, 151.2093);
+
+    fn sighting((latitude, longitude): (f64, f64), at: NaiveDateTime) -> Sighting {
+        Sighting { latitude, longitude, at }
+    }
+
+    #[test]
+    fn test_impossible_travel() {
+        let now = Utc::now().naive_utc();
+        let in_london = sighting(LONDON, now - Duration::hours(1));
+
+        // ~1,300 km in an hour is too fast, but fine in a day
+        assert!(detect_impossible_travel(in_london, sighting(LINKOPING, now)).is_some());
+        assert!(detect_impossible_travel(sighting(LONDON, now - Duration::days(1)), sighting(LINKOPING, now)).is_none());
+        // Nearby locations are within GeoIP noise, however quick
+        assert!(detect_impossible_travel(in_london, sighting((51.75, -1.25), now)).is_none());
+
+        let Some(RiskSignal::ImpossibleTravel { distance_km, .. }) = detect_impossible_travel(in_london, sighting(SYDNEY, now)) else {
+            panic!("expected impossible travel");
+        };
+        assert!((16_900.0..17_100.0).contains(&distance_km));
+    }
+
+    #[test]
+    fn test_score() {
+        let mut risk = LoginRisk::default();
+        assert!(!risk.is_suspicious());
+
+        risk.add(Some(RiskSignal::NewDevice));
+        risk.add(None);
+        risk.add(Some(RiskSignal::ImpossibleTravel { distance_km: 1300.0, hours: 1.0 }));
+        assert_eq!(risk.score(), 90);
+        assert!(risk.is_suspicious());
+    }
+}
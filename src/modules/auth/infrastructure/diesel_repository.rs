@@ -1,101 +1,199 @@
+use async_trait::async_trait;
 use diesel::prelude::*;
 use uuid::Uuid;
-use crate::common::{database::DbPool, errors::AppError};
+use crate::common::{database::{self, DbHandle}, errors::AppError};
 use crate::modules::auth::domain::{entity::{UserSession, NewUserSession}, repository::SessionRepository};
 use crate::schema::user_sessions;
+use tracing::instrument;
 
 pub struct DieselSessionRepository {
-    pool: DbPool,
+    db: DbHandle,
 }
 
 impl DieselSessionRepository {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
     }
 }
 
+#[async_trait]
 impl SessionRepository for DieselSessionRepository {
-    fn create(&self, session: NewUserSession) -> Result<UserSession, AppError> {
-        let mut conn = self.pool.get().map_err(|e| {
-            tracing::error!("Failed to get DB connection: {}", e);
-            AppError::InternalError
-        })?;
-        
-        diesel::insert_into(user_sessions::table)
-            .values(&session)
-            .get_result(&mut conn)
-            .map_err(AppError::from)
+    #[instrument(name = "SessionRepository::create", skip_all)]
+    async fn create(&self, session: NewUserSession) -> Result<UserSession, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(user_sessions::table)
+                .values(&session)
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        user_sessions::table
-            .find(id)
-            .first::<UserSession>(&mut conn)
-            .optional()
-            .map_err(AppError::from)
+    #[instrument(name = "SessionRepository::find_by_id", skip_all)]
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError> {
+        database::run(&self.db, move |conn| {
+            user_sessions::table
+                .find(id)
+                .first::<UserSession>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn update_last_used(&self, id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|e| {
-            tracing::error!("Failed to get DB connection: {}", e);
-            AppError::InternalError
-        })?;
-        
-        diesel::update(user_sessions::table.find(id))
-            .set(user_sessions::last_used_at.eq(diesel::dsl::now))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "SessionRepository::update_last_used", skip_all)]
+    async fn update_last_used(&self, id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table.find(id))
+                .set(user_sessions::last_used_at.eq(diesel::dsl::now))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::update_refresh_token", skip_all)]
+    async fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table.find(id))
+                .set((
+                    user_sessions::refresh_token_hash.eq(new_hash),
+                    user_sessions::expires_at.eq(new_expires_at),
+                    user_sessions::last_used_at.eq(diesel::dsl::now)
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::revoke", skip_all)]
+    async fn revoke(&self, id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table.find(id).filter(user_sessions::is_revoked.eq(false)))
+                .set((user_sessions::is_revoked.eq(true), user_sessions::revoked_at.eq(diesel::dsl::now.nullable())))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::mark_reported", skip_all)]
+    async fn mark_reported(&self, id: Uuid) -> Result<bool, AppError> {
+        database::run(&self.db, move |conn| {
+            // Conditional, so of two concurrent reports only one gets `true`
+            diesel::update(user_sessions::table.find(id).filter(user_sessions::reported_at.is_null()))
+                .set(user_sessions::reported_at.eq(diesel::dsl::now.nullable()))
+                .execute(conn)
+                .map(|updated| updated > 0)
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::revoke_all_for_user", skip_all)]
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table
+                    .filter(user_sessions::user_id.eq(user_id))
+                    .filter(user_sessions::is_revoked.eq(false)))
+                .set((user_sessions::is_revoked.eq(true), user_sessions::revoked_at.eq(diesel::dsl::now.nullable())))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::revoke_all_for_user_except", skip_all)]
+    async fn revoke_all_for_user_except(&self, user_id: Uuid, keep_session_id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table
+                    .filter(user_sessions::user_id.eq(user_id))
+                    .filter(user_sessions::id.ne(keep_session_id))
+                    .filter(user_sessions::is_revoked.eq(false)))
+                .set((user_sessions::is_revoked.eq(true), user_sessions::revoked_at.eq(diesel::dsl::now.nullable())))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::update_device_name", skip_all)]
+    async fn update_device_name(&self, id: Uuid, device_name: &str) -> Result<UserSession, AppError> {
+        let device_name = device_name.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::update(user_sessions::table.find(id))
+                .set(user_sessions::device_name.eq(device_name))
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|e| {
-            tracing::error!("Failed to get DB connection: {}", e);
-            AppError::InternalError
-        })?;
-        
-        diesel::update(user_sessions::table.find(id))
-            .set((
-                user_sessions::refresh_token_hash.eq(new_hash),
-                user_sessions::expires_at.eq(new_expires_at),
-                user_sessions::last_used_at.eq(diesel::dsl::now)
+    #[instrument(name = "SessionRepository::delete_stale", skip_all)]
+    async fn delete_stale(&self, cutoff: chrono::NaiveDateTime, idle_timeout: chrono::Duration) -> Result<usize, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::delete(user_sessions::table.filter(
+                user_sessions::expires_at.lt(cutoff)
+                    .or(user_sessions::revoked_at.lt(cutoff))
+                    .or(user_sessions::last_used_at.lt(cutoff - idle_timeout))
             ))
-            .execute(&mut conn)
-            .map(|_| ())
+            .execute(conn)
             .map_err(AppError::from)
+        }).await
     }
 
-    fn revoke(&self, id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(user_sessions::table.find(id))
-            .set(user_sessions::is_revoked.eq(true))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "SessionRepository::find_active_by_user", skip_all)]
+    async fn find_active_by_user(&self, user_id: Uuid, idle_since: chrono::NaiveDateTime) -> Result<Vec<UserSession>, AppError> {
+        database::run(&self.db, move |conn| {
+            user_sessions::table
+                .filter(user_sessions::user_id.eq(user_id))
+                .filter(user_sessions::is_revoked.eq(false))
+                .filter(user_sessions::expires_at.gt(diesel::dsl::now))
+                .filter(user_sessions::last_used_at.gt(idle_since))
+                .order(user_sessions::created_at.desc())
+                .load::<UserSession>(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(user_sessions::table.filter(user_sessions::user_id.eq(user_id)))
-            .set(user_sessions::is_revoked.eq(true))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "SessionRepository::count_active", skip_all)]
+    async fn count_active(&self, idle_since: chrono::NaiveDateTime) -> Result<i64, AppError> {
+        database::run(&self.db, move |conn| {
+            user_sessions::table
+                .filter(user_sessions::is_revoked.eq(false))
+                .filter(user_sessions::expires_at.gt(diesel::dsl::now))
+                .filter(user_sessions::last_used_at.gt(idle_since))
+                .count()
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_active_by_user(&self, user_id: Uuid) -> Result<Vec<UserSession>, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        user_sessions::table
-            .filter(user_sessions::user_id.eq(user_id))
-            .filter(user_sessions::is_revoked.eq(false))
-            .filter(user_sessions::expires_at.gt(diesel::dsl::now))
-            .order(user_sessions::created_at.desc())
-            .load::<UserSession>(&mut conn)
+    #[instrument(name = "SessionRepository::find_latest_located", skip_all)]
+    async fn find_latest_located(&self, user_id: Uuid) -> Result<Option<UserSession>, AppError> {
+        database::run(&self.db, move |conn| {
+            user_sessions::table
+                .filter(user_sessions::user_id.eq(user_id))
+                .filter(user_sessions::latitude.is_not_null())
+                .filter(user_sessions::longitude.is_not_null())
+                .order(user_sessions::last_used_at.desc())
+                .first::<UserSession>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SessionRepository::has_recent_session", skip_all)]
+    async fn has_recent_session(&self, user_id: Uuid, device_name: Option<&str>, ip_address: Option<&str>, since: chrono::NaiveDateTime) -> Result<bool, AppError> {
+        let device_name = device_name.map(str::to_string);
+        let ip_address = ip_address.map(str::to_string);
+        database::run(&self.db, move |conn| {
+            diesel::select(diesel::dsl::exists(
+                user_sessions::table
+                    .filter(user_sessions::user_id.eq(user_id))
+                    .filter(user_sessions::device_name.is_not_distinct_from(device_name))
+                    .filter(user_sessions::ip_address.is_not_distinct_from(ip_address))
+                    .filter(user_sessions::last_used_at.gt(since))
+            ))
+            .get_result(conn)
             .map_err(AppError::from)
+        }).await
     }
 }
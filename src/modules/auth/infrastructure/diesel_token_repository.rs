@@ -1,82 +1,105 @@
+use async_trait::async_trait;
 use diesel::prelude::*;
 use uuid::Uuid;
-use crate::common::{database::DbPool, errors::AppError};
+use crate::common::{database::{self, DbHandle}, errors::AppError};
 use crate::modules::auth::domain::{
     entity::token::{EmailVerificationToken, NewEmailVerificationToken, PasswordResetToken, NewPasswordResetToken},
     repository::verification::VerificationTokenRepository,
 };
 use crate::schema::{email_verification_tokens, password_reset_tokens};
+use tracing::instrument;
 
 pub struct DieselVerificationTokenRepository {
-    pool: DbPool,
+    db: DbHandle,
 }
 
 impl DieselVerificationTokenRepository {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
     }
 }
 
+#[async_trait]
 impl VerificationTokenRepository for DieselVerificationTokenRepository {
-    fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::insert_into(email_verification_tokens::table)
-            .values(&token)
-            .get_result(&mut conn)
-            .map_err(AppError::from)
+    #[instrument(name = "VerificationTokenRepository::create_email_verification", skip_all)]
+    async fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(email_verification_tokens::table)
+                .values(&token)
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_email_verification_by_user(&self, user_id_val: Uuid) -> Result<Option<EmailVerificationToken>, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        email_verification_tokens::table
-            .filter(email_verification_tokens::user_id.eq(user_id_val))
-            .filter(email_verification_tokens::used.eq(false)) // Only unused
-            .order(email_verification_tokens::created_at.desc())
-            .first::<EmailVerificationToken>(&mut conn)
-            .optional()
-            .map_err(AppError::from)
+    #[instrument(name = "VerificationTokenRepository::find_email_verification_by_user", skip_all)]
+    async fn find_email_verification_by_user(&self, user_id_val: Uuid) -> Result<Option<EmailVerificationToken>, AppError> {
+        database::run(&self.db, move |conn| {
+            email_verification_tokens::table
+                .filter(email_verification_tokens::user_id.eq(user_id_val))
+                .filter(email_verification_tokens::used.eq(false)) // Only unused
+                .order(email_verification_tokens::created_at.desc())
+                .first::<EmailVerificationToken>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::insert_into(password_reset_tokens::table)
-            .values(&token)
-            .get_result(&mut conn)
-            .map_err(AppError::from)
+    #[instrument(name = "VerificationTokenRepository::create_password_reset", skip_all)]
+    async fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(password_reset_tokens::table)
+                .values(&token)
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_password_reset_by_user(&self, user_id_val: Uuid) -> Result<Option<PasswordResetToken>, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        password_reset_tokens::table
-            .filter(password_reset_tokens::user_id.eq(user_id_val))
-            .filter(password_reset_tokens::used.eq(false))
-            .order(password_reset_tokens::created_at.desc())
-            .first::<PasswordResetToken>(&mut conn)
-            .optional()
-            .map_err(AppError::from)
+    #[instrument(name = "VerificationTokenRepository::find_password_reset_by_user", skip_all)]
+    async fn find_password_reset_by_user(&self, user_id_val: Uuid) -> Result<Option<PasswordResetToken>, AppError> {
+        database::run(&self.db, move |conn| {
+            password_reset_tokens::table
+                .filter(password_reset_tokens::user_id.eq(user_id_val))
+                .filter(password_reset_tokens::used.eq(false))
+                .order(password_reset_tokens::created_at.desc())
+                .first::<PasswordResetToken>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(email_verification_tokens::table.find(token_id))
-            .set(email_verification_tokens::used.eq(true))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "VerificationTokenRepository::mark_email_verification_as_used", skip_all)]
+    async fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(email_verification_tokens::table.find(token_id))
+                .set(email_verification_tokens::used.eq(true))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "VerificationTokenRepository::mark_password_reset_as_used", skip_all)]
+    async fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(password_reset_tokens::table.find(token_id))
+                .set(password_reset_tokens::used.eq(true))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(password_reset_tokens::table.find(token_id))
-            .set(password_reset_tokens::used.eq(true))
-            .execute(&mut conn)
-            .map(|_| ())
+    #[instrument(name = "VerificationTokenRepository::delete_expired", skip_all)]
+    async fn delete_expired(&self, before: chrono::NaiveDateTime) -> Result<usize, AppError> {
+        database::run(&self.db, move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let verifications = diesel::delete(email_verification_tokens::table.filter(email_verification_tokens::expires_at.lt(before)))
+                    .execute(conn)?;
+                let resets = diesel::delete(password_reset_tokens::table.filter(password_reset_tokens::expires_at.lt(before)))
+                    .execute(conn)?;
+                Ok(verifications + resets)
+            })
             .map_err(AppError::from)
+        }).await
     }
 }
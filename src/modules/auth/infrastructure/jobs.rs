@@ -0,0 +1,47 @@
+use std::sync::Arc;
+use crate::common::{clock::Clock, config::AppConfig, database::DbPool, live_config::LiveConfig};
+use crate::modules::auth::application::cleanup::AuthCleanupService;
+use crate::modules::jobs::application::{handler::FnJobHandler, registry::JobRegistry};
+use super::{diesel_repository::DieselSessionRepository, diesel_token_repository::DieselVerificationTokenRepository};
+
+pub const PURGE_STALE_SESSIONS: &str = "auth.purge_stale_sessions";
+pub const PURGE_EXPIRED_TOKENS: &str = "auth.purge_expired_tokens";
+
+fn cleanup_service(pool: &DbPool, config: &AppConfig, clock: &Arc<dyn Clock>) -> AuthCleanupService<DieselSessionRepository, DieselVerificationTokenRepository> {
+    AuthCleanupService::new(
+        DieselSessionRepository::new(pool.clone()),
+        DieselVerificationTokenRepository::new(pool.clone()),
+        clock.clone(),
+        config.clone(),
+    )
+}
+
+pub fn register(registry: &mut JobRegistry, pool: &DbPool, config: &LiveConfig, clock: &Arc<dyn Clock>) {
+    let (sessions_pool, sessions_config, sessions_clock) = (pool.clone(), config.clone(), clock.clone());
+    registry
+        .register(FnJobHandler::new(PURGE_STALE_SESSIONS, move |_| {
+            let service = cleanup_service(&sessions_pool, &sessions_config.get(), &sessions_clock);
+            async move {
+                let deleted = service.purge_stale_sessions().await?;
+                if deleted > 0 {
+                    tracing::info!("Deleted {} stale sessions", deleted);
+                }
+                Ok(())
+            }
+        }))
+        .every("0 0 * * * *", PURGE_STALE_SESSIONS);
+
+    let (tokens_pool, tokens_config, tokens_clock) = (pool.clone(), config.clone(), clock.clone());
+    registry
+        .register(FnJobHandler::new(PURGE_EXPIRED_TOKENS, move |_| {
+            let service = cleanup_service(&tokens_pool, &tokens_config.get(), &tokens_clock);
+            async move {
+                let deleted = service.purge_expired_tokens().await?;
+                if deleted > 0 {
+                    tracing::info!("Deleted {} expired verification and password reset tokens", deleted);
+                }
+                Ok(())
+            }
+        }))
+        .every("0 30 * * * *", PURGE_EXPIRED_TOKENS);
+}
@@ -0,0 +1,169 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::common::{clock::{Clock, SystemClock}, errors::AppError};
+use crate::modules::auth::domain::{entity::{UserSession, NewUserSession}, repository::SessionRepository};
+
+/// Keeps sessions in memory for tests. Clones share the same data.
+///
+/// Timestamps the database would fill in with `now()` come from the clock, so
+/// a test can freeze it together with the service under test.
+#[derive(Clone)]
+pub struct InMemorySessionRepository {
+    sessions: Arc<Mutex<Vec<UserSession>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for InMemorySessionRepository {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl InMemorySessionRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self { sessions: Arc::default(), clock }
+    }
+
+    fn revoke_where(&self, f: impl Fn(&UserSession) -> bool) -> Result<(), AppError> {
+        let now = self.clock.now();
+        for session in self.sessions.lock().unwrap().iter_mut().filter(|s| !s.is_revoked && f(s)) {
+            session.is_revoked = true;
+            session.revoked_at = Some(now);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    async fn create(&self, session: NewUserSession) -> Result<UserSession, AppError> {
+        let now = self.clock.now();
+        let session = UserSession {
+            id: Uuid::new_v4(),
+            user_id: session.user_id,
+            refresh_token_hash: session.refresh_token_hash,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            device_name: session.device_name,
+            is_revoked: false,
+            expires_at: session.expires_at,
+            created_at: now,
+            last_used_at: now,
+            browser: session.browser,
+            browser_version: session.browser_version,
+            os: session.os,
+            os_version: session.os_version,
+            device_class: session.device_class,
+            engine: session.engine,
+            country_code: session.country_code,
+            country: session.country,
+            city: session.city,
+            asn: session.asn,
+            as_org: session.as_org,
+            latitude: session.latitude,
+            longitude: session.longitude,
+            risk_score: session.risk_score,
+            revoked_at: None,
+            reported_at: None,
+        };
+        self.sessions.lock().unwrap().push(session.clone());
+        Ok(session)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, AppError> {
+        Ok(self.sessions.lock().unwrap().iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn update_last_used(&self, id: Uuid) -> Result<(), AppError> {
+        if let Some(session) = self.sessions.lock().unwrap().iter_mut().find(|s| s.id == id) {
+            session.last_used_at = self.clock.now();
+        }
+        Ok(())
+    }
+
+    async fn update_refresh_token(&self, id: Uuid, new_hash: String, new_expires_at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        if let Some(session) = self.sessions.lock().unwrap().iter_mut().find(|s| s.id == id) {
+            session.refresh_token_hash = new_hash;
+            session.expires_at = new_expires_at;
+            session.last_used_at = self.clock.now();
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<(), AppError> {
+        self.revoke_where(|s| s.id == id)
+    }
+
+    async fn mark_reported(&self, id: Uuid) -> Result<bool, AppError> {
+        let now = self.clock.now();
+        Ok(self.sessions.lock().unwrap().iter_mut()
+            .find(|s| s.id == id && s.reported_at.is_none())
+            .map(|session| session.reported_at = Some(now))
+            .is_some())
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<(), AppError> {
+        self.revoke_where(|s| s.user_id == user_id)
+    }
+
+    async fn revoke_all_for_user_except(&self, user_id: Uuid, keep_session_id: Uuid) -> Result<(), AppError> {
+        self.revoke_where(|s| s.user_id == user_id && s.id != keep_session_id)
+    }
+
+    async fn update_device_name(&self, id: Uuid, device_name: &str) -> Result<UserSession, AppError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        // Same error as Diesel's `get_result` on a missing row
+        let session = sessions.iter_mut().find(|s| s.id == id).ok_or(diesel::result::Error::NotFound)?;
+        session.device_name = Some(device_name.to_string());
+        Ok(session.clone())
+    }
+
+    async fn find_active_by_user(&self, user_id: Uuid, idle_since: chrono::NaiveDateTime) -> Result<Vec<UserSession>, AppError> {
+        let now = self.clock.now();
+        let mut sessions: Vec<_> = self.sessions.lock().unwrap().iter()
+            .filter(|s| s.user_id == user_id && !s.is_revoked && s.expires_at > now && s.last_used_at > idle_since)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(sessions)
+    }
+
+    async fn count_active(&self, idle_since: chrono::NaiveDateTime) -> Result<i64, AppError> {
+        let now = self.clock.now();
+        Ok(self.sessions.lock().unwrap().iter()
+            .filter(|s| !s.is_revoked && s.expires_at > now && s.last_used_at > idle_since)
+            .count() as i64)
+    }
+
+    async fn delete_stale(&self, cutoff: chrono::NaiveDateTime, idle_timeout: chrono::Duration) -> Result<usize, AppError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|s| {
+            !(s.expires_at < cutoff
+                || s.revoked_at.is_some_and(|at| at < cutoff)
+                || s.last_used_at < cutoff - idle_timeout)
+        });
+        Ok(before - sessions.len())
+    }
+
+    async fn find_latest_located(&self, user_id: Uuid) -> Result<Option<UserSession>, AppError> {
+        Ok(self.sessions.lock().unwrap().iter()
+            .filter(|s| s.user_id == user_id && s.latitude.is_some() && s.longitude.is_some())
+            .max_by_key(|s| s.last_used_at)
+            .cloned())
+    }
+
+    async fn has_recent_session(&self, user_id: Uuid, device_name: Option<&str>, ip_address: Option<&str>, since: chrono::NaiveDateTime) -> Result<bool, AppError> {
+        Ok(self.sessions.lock().unwrap().iter().any(|s| {
+            s.user_id == user_id
+                && s.device_name.as_deref() == device_name
+                && s.ip_address.as_deref() == ip_address
+                && s.last_used_at > since
+        }))
+    }
+}
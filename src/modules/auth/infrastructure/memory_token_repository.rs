@@ -0,0 +1,90 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::common::errors::AppError;
+use crate::modules::auth::domain::{
+    entity::token::{EmailVerificationToken, NewEmailVerificationToken, PasswordResetToken, NewPasswordResetToken},
+    repository::verification::VerificationTokenRepository,
+};
+
+/// Keeps email verification and password reset tokens in memory for tests.
+/// Clones share the same data.
+#[derive(Clone, Default)]
+pub struct InMemoryVerificationTokenRepository {
+    email_verifications: Arc<Mutex<Vec<EmailVerificationToken>>>,
+    password_resets: Arc<Mutex<Vec<PasswordResetToken>>>,
+}
+
+impl InMemoryVerificationTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl VerificationTokenRepository for InMemoryVerificationTokenRepository {
+    async fn create_email_verification(&self, token: NewEmailVerificationToken) -> Result<EmailVerificationToken, AppError> {
+        let token = EmailVerificationToken {
+            id: Uuid::new_v4(),
+            user_id: token.user_id,
+            token_hash: token.token_hash,
+            expires_at: token.expires_at,
+            used: false,
+            created_at: Utc::now().naive_utc(),
+        };
+        self.email_verifications.lock().unwrap().push(token.clone());
+        Ok(token)
+    }
+
+    async fn find_email_verification_by_user(&self, user_id: Uuid) -> Result<Option<EmailVerificationToken>, AppError> {
+        // `max_by_key` keeps the last of equal keys, so ties go to the newest insert
+        Ok(self.email_verifications.lock().unwrap().iter()
+            .filter(|t| t.user_id == user_id && !t.used)
+            .max_by_key(|t| t.created_at)
+            .cloned())
+    }
+
+    async fn create_password_reset(&self, token: NewPasswordResetToken) -> Result<PasswordResetToken, AppError> {
+        let token = PasswordResetToken {
+            id: Uuid::new_v4(),
+            user_id: token.user_id,
+            token_hash: token.token_hash,
+            expires_at: token.expires_at,
+            used: false,
+            created_at: Utc::now().naive_utc(),
+        };
+        self.password_resets.lock().unwrap().push(token.clone());
+        Ok(token)
+    }
+
+    async fn find_password_reset_by_user(&self, user_id: Uuid) -> Result<Option<PasswordResetToken>, AppError> {
+        Ok(self.password_resets.lock().unwrap().iter()
+            .filter(|t| t.user_id == user_id && !t.used)
+            .max_by_key(|t| t.created_at)
+            .cloned())
+    }
+
+    async fn mark_email_verification_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        if let Some(token) = self.email_verifications.lock().unwrap().iter_mut().find(|t| t.id == token_id) {
+            token.used = true;
+        }
+        Ok(())
+    }
+
+    async fn mark_password_reset_as_used(&self, token_id: Uuid) -> Result<(), AppError> {
+        if let Some(token) = self.password_resets.lock().unwrap().iter_mut().find(|t| t.id == token_id) {
+            token.used = true;
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(&self, before: chrono::NaiveDateTime) -> Result<usize, AppError> {
+        let mut verifications = self.email_verifications.lock().unwrap();
+        let mut resets = self.password_resets.lock().unwrap();
+        let count = verifications.len() + resets.len();
+        verifications.retain(|t| t.expires_at >= before);
+        resets.retain(|t| t.expires_at >= before);
+        Ok(count - verifications.len() - resets.len())
+    }
+}
@@ -1,3 +1,8 @@
 pub mod diesel_repository;
 pub mod password_service;
 pub mod diesel_token_repository;
+pub mod jobs;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory_repository;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory_token_repository;
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use validator::Validate;
+use crate::modules::auth::domain::entity::UserSession;
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct RegisterUserDto {
@@ -7,6 +8,9 @@ pub struct RegisterUserDto {
     pub email: String,
     #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
     pub password: String,
+    /// Preferred language for emails, e.g. `es` or `pt-BR`. Falls back to `Accept-Language`.
+    #[validate(length(min = 2, max = 35, message = "Invalid locale"))]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -21,6 +25,12 @@ pub struct VerifyEmailDto {
     pub token: String,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReportSignInDto {
+    /// Signed token from the "this wasn't me" link of a new sign-in alert.
+    pub token: String,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct ResetPasswordDto {
     pub token: String,
@@ -45,9 +55,59 @@ pub struct UserSessionDto {
     pub user_agent: Option<String>,
     pub ip_address: Option<String>,
     pub device_name: Option<String>,
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub device_class: Option<String>,
+    pub engine: Option<String>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub asn: Option<i64>,
+    pub as_org: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub risk_score: i32,
     pub is_revoked: bool,
     pub created_at: chrono::NaiveDateTime,
     pub last_used_at: chrono::NaiveDateTime,
     pub expires_at: chrono::NaiveDateTime,
     pub is_current: bool,
 }
+
+impl UserSessionDto {
+    pub fn from_session(s: UserSession, current_session_id: uuid::Uuid) -> Self {
+        Self {
+            is_current: s.id == current_session_id,
+            id: s.id,
+            user_agent: s.user_agent,
+            ip_address: s.ip_address,
+            device_name: s.device_name,
+            browser: s.browser,
+            browser_version: s.browser_version,
+            os: s.os,
+            os_version: s.os_version,
+            device_class: s.device_class,
+            engine: s.engine,
+            country_code: s.country_code,
+            country: s.country,
+            city: s.city,
+            asn: s.asn,
+            as_org: s.as_org,
+            latitude: s.latitude,
+            longitude: s.longitude,
+            risk_score: s.risk_score,
+            is_revoked: s.is_revoked,
+            created_at: s.created_at,
+            last_used_at: s.last_used_at,
+            expires_at: s.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RenameSessionDto {
+    #[validate(length(min = 1, max = 100, message = "Device name must be between 1 and 100 characters"))]
+    pub device_name: String,
+}
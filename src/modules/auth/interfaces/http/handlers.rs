@@ -1,60 +1,56 @@
+use std::sync::Arc;
 use actix_web::{web, HttpResponse};
-use crate::common::{database::DbPool, errors::AppError};
+use crate::common::{errors::AppError, unit_of_work::DynUnitOfWork};
 use crate::modules::auth::application::service::AuthService;
-use crate::modules::users::infrastructure::diesel_repository::DieselUserRepository;
-use crate::modules::auth::infrastructure::{
-    diesel_repository::DieselSessionRepository,
-    diesel_token_repository::DieselVerificationTokenRepository,
-};
-use crate::modules::email::infrastructure::resend::ResendEmailService;
-use crate::common::config::AppConfig;
-use super::dto::{RegisterUserDto, LoginDto, VerifyEmailDto};
+use crate::modules::auth::domain::repository::{verification::VerificationTokenRepository, AuthRepositories, SessionRepository};
+use crate::modules::users::domain::repository::UserRepository;
+use crate::modules::email::domain::service::EmailService;
+use crate::app::AppState;
+use crate::common::client_ip::ClientIp;
+use super::dto::{RegisterUserDto, LoginDto, VerifyEmailDto, ReportSignInDto};
 use validator::Validate;
 
-// Type alias with ALL generics
-type AuthServiceImpl = AuthService<
-    DieselUserRepository,
-    DieselSessionRepository,
-    DieselVerificationTokenRepository,
-    ResendEmailService
+pub type AuthRepositoriesImpl = AuthRepositories<
+    Arc<dyn UserRepository>,
+    Arc<dyn SessionRepository>,
+    Arc<dyn VerificationTokenRepository>,
 >;
 
-// Helper to create service
-fn auth_service_factory(pool: &DbPool, config: &AppConfig) -> AuthServiceImpl {
-    let user_repo = DieselUserRepository::new(pool.clone());
-    let session_repo = DieselSessionRepository::new(pool.clone());
-    let token_repo = DieselVerificationTokenRepository::new(pool.clone());
-    let email_service = ResendEmailService::new(config.clone());
-    let token_service = crate::modules::auth::application::token_service::TokenService::new(config.clone());
-    
-    AuthService::new(
-        user_repo,
-        session_repo,
-        token_repo,
-        email_service,
-        token_service,
-        config.clone()
-    )
-}
+// Type alias with ALL generics
+pub type AuthServiceImpl = AuthService<
+    Arc<dyn UserRepository>,
+    Arc<dyn SessionRepository>,
+    Arc<dyn VerificationTokenRepository>,
+    Arc<dyn EmailService>,
+    Arc<dyn DynUnitOfWork<AuthRepositoriesImpl>>
+>;
 
 pub async fn register(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
+    req: actix_web::HttpRequest,
     body: web::Json<RegisterUserDto>,
 ) -> Result<HttpResponse, AppError> {
     body.validate().map_err(AppError::ValidationError)?;
+
+    // Take the first (highest priority) tag of Accept-Language, e.g. "es-MX,es;q=0.9" -> "es-MX"
+    let locale = body.locale.clone().or_else(|| {
+        req.headers().get("Accept-Language")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.split(',').next())
+            .map(|tag| tag.split(';').next().unwrap_or_default().trim().to_string())
+            .filter(|tag| !tag.is_empty() && tag != "*")
+    });
     
-    let service = auth_service_factory(&pool, &config);
-    service.register(body.email.clone(), body.password.clone()).await?;
+    state.auth.register(body.email.clone(), body.password.clone(), locale).await?;
 
     
     Ok(HttpResponse::Created().json(serde_json::json!({"message": "User registered successfully, please verify your email"})))
 }
 
 pub async fn login(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     req: actix_web::HttpRequest,
+    client_ip: Option<ClientIp>,
     body: web::Json<LoginDto>,
 ) -> Result<HttpResponse, AppError> {
     body.validate().map_err(AppError::ValidationError)?;
@@ -62,11 +58,10 @@ pub async fn login(
     let user_agent = req.headers().get("User-Agent")
         .and_then(|h| h.to_str().ok())
         .map(|s| s.to_string());
-        
-    let ip_address = req.peer_addr().map(|a| a.ip().to_string());
+
+    let ip_address = client_ip.map(|ip| ip.to_string());
     
-    let service = auth_service_factory(&pool, &config);
-    let (access_token, refresh_token) = service.login(body.email.clone(), body.password.clone(), user_agent, ip_address).await?;
+    let (access_token, refresh_token) = state.auth.login(body.email.clone(), body.password.clone(), user_agent, ip_address).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "access_token": access_token,
@@ -75,42 +70,45 @@ pub async fn login(
 }
 
 pub async fn verify_email(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     body: web::Json<VerifyEmailDto>,
 ) -> Result<HttpResponse, AppError> {
     
-    let service = auth_service_factory(&pool, &config);
-    service.verify_email(body.token.clone()).await?;
+    state.auth.verify_email(body.token.clone()).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Email verified successfully"})))
 }
 
+pub async fn report_unrecognized_sign_in(
+    state: web::Data<AppState>,
+    body: web::Json<ReportSignInDto>,
+) -> Result<HttpResponse, AppError> {
+    state.auth.report_unrecognized_sign_in(&body.token).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Session revoked, check your email to reset your password"})))
+}
+
 #[derive(serde::Deserialize)]
 pub struct RequestResetDto {
     pub email: String,
 }
 
 pub async fn request_password_reset(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     body: web::Json<RequestResetDto>,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    service.request_password_reset(&body.email).await?;
+    state.auth.request_password_reset(&body.email).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Password reset email sent"})))
 }
 
 pub async fn reset_password(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     body: web::Json<super::dto::ResetPasswordDto>,
 ) -> Result<HttpResponse, AppError> {
     body.validate().map_err(AppError::ValidationError)?;
 
-    let service = auth_service_factory(&pool, &config);
-    service.reset_password(&body.token, &body.new_password).await?;
+    state.auth.reset_password(&body.token, &body.new_password).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Password reset successfully"})))
 }
@@ -118,12 +116,10 @@ pub async fn reset_password(
 use super::dto::RequestEmailVerificationDto;
 
 pub async fn request_email_verification(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     body: web::Json<RequestEmailVerificationDto>,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    service.request_email_verification(&body.email).await?;
+    state.auth.request_email_verification(&body.email).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Verification email sent"})))
 }
@@ -131,23 +127,19 @@ pub async fn request_email_verification(
 use super::middleware::AuthenticatedUser;
 
 pub async fn logout(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     user: AuthenticatedUser,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    service.logout(user.session_id)?;
+    state.auth.logout(user.user_id, user.session_id).await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Logged out successfully"})))
 }
 
 pub async fn revoke_all_sessions(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     user: AuthenticatedUser,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    service.revoke_all_sessions(user.user_id)?;
+    state.auth.revoke_all_sessions(user.user_id, Some(user.user_id)).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "All sessions revoked"})))
 }
@@ -155,12 +147,10 @@ pub async fn revoke_all_sessions(
 use super::dto::RefreshTokenDto;
 
 pub async fn refresh_token(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     body: web::Json<RefreshTokenDto>,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    let (access_token, refresh_token) = service.refresh_token(&body.refresh_token).await?;
+    let (access_token, refresh_token) = state.auth.refresh_token(&body.refresh_token).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "access_token": access_token,
@@ -168,27 +158,49 @@ pub async fn refresh_token(
     })))
 }
 
-use super::dto::UserSessionDto;
+use super::dto::{UserSessionDto, RenameSessionDto};
 
 pub async fn get_active_sessions(
-    pool: web::Data<DbPool>,
-    config: web::Data<AppConfig>,
+    state: web::Data<AppState>,
     user: AuthenticatedUser,
 ) -> Result<HttpResponse, AppError> {
-    let service = auth_service_factory(&pool, &config);
-    let sessions = service.get_active_sessions(user.user_id)?;
+    let sessions = state.auth.get_active_sessions(user.user_id).await?;
     
-    let dtos: Vec<UserSessionDto> = sessions.into_iter().map(|s| UserSessionDto {
-        id: s.id,
-        user_agent: s.user_agent,
-        ip_address: s.ip_address,
-        device_name: s.device_name,
-        is_revoked: s.is_revoked,
-        created_at: s.created_at,
-        last_used_at: s.last_used_at,
-        expires_at: s.expires_at,
-        is_current: s.id == user.session_id,
-    }).collect();
+    let dtos: Vec<UserSessionDto> = sessions.into_iter()
+        .map(|s| UserSessionDto::from_session(s, user.session_id))
+        .collect();
 
     Ok(HttpResponse::Ok().json(dtos))
-}
\ No newline at end of file
+}
+
+pub async fn revoke_session(
+    state: web::Data<AppState>,
+    user: AuthenticatedUser,
+    path: web::Path<uuid::Uuid>,
+) -> Result<HttpResponse, AppError> {
+    state.auth.revoke_session(user.user_id, path.into_inner()).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn rename_session(
+    state: web::Data<AppState>,
+    user: AuthenticatedUser,
+    path: web::Path<uuid::Uuid>,
+    body: web::Json<RenameSessionDto>,
+) -> Result<HttpResponse, AppError> {
+    body.validate().map_err(AppError::ValidationError)?;
+
+    let session = state.auth.rename_session(user.user_id, path.into_inner(), &body.device_name).await?;
+
+    Ok(HttpResponse::Ok().json(UserSessionDto::from_session(session, user.session_id)))
+}
+
+pub async fn revoke_other_sessions(
+    state: web::Data<AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    state.auth.revoke_other_sessions(user.user_id, user.session_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": "All other sessions revoked"})))
+}
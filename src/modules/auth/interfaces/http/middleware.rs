@@ -6,9 +6,7 @@ use crate::common::errors::AppError;
 
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
-use crate::modules::auth::application::token_service::TokenService;
-
-use crate::common::config::AppConfig;
+use crate::app::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthenticatedUser {
@@ -19,18 +17,15 @@ pub struct AuthenticatedUser {
 
 use std::pin::Pin;
 use std::future::Future;
-use crate::common::database::DbPool;
 use crate::modules::auth::domain::repository::SessionRepository;
-use crate::modules::auth::infrastructure::diesel_repository::DieselSessionRepository;
 
 impl FromRequest for AuthenticatedUser {
     type Error = actix_web::Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let auth_header = req.headers().get("Authorization").map(|h| h.clone());
-        let config = req.app_data::<web::Data<AppConfig>>().cloned();
-        let pool = req.app_data::<web::Data<DbPool>>().cloned();
+        let auth_header = req.headers().get("Authorization").cloned();
+        let state = req.app_data::<web::Data<AppState>>().cloned();
 
         Box::pin(async move {
             let auth_str = match auth_header {
@@ -43,40 +38,23 @@ impl FromRequest for AuthenticatedUser {
             }
             let token = auth_str[7..].to_string();
 
-            let config = config.ok_or_else(|| AppError::InternalError)?;
-            let pool = pool.ok_or_else(|| AppError::InternalError)?;
-            
-            let token_service = TokenService::new(config.as_ref().clone());
+            let state = state.ok_or_else(|| AppError::InternalError)?;
 
-            let claims = token_service.verify_access_token(&token)
+            let now = state.clock.now();
+            let claims = state.tokens.verify_access_token(&token, now)
                 .map_err(|_| AppError::Unauthorized("Invalid or expired token".to_string()))?;
                 
             // Validate session in DB
-            let session_repo = DieselSessionRepository::new(pool.as_ref().clone());
+            let session_repo = &state.sessions;
             let session_id = claims.session_id;
 
-            // Blocking call wrapper
-            let session = web::block(move || session_repo.find_by_id(session_id))
-                .await
-                .map_err(|_| AppError::InternalError)?
-                .map_err(|_| AppError::InternalError)?;
+            let session = session_repo.find_by_id(session_id).await?;
 
             match session {
-                Some(s) if !s.is_revoked => {
-                    // Update last used (fire and forget or await)
-                    // We need a new repo instance or clone because `find_by_id` moved it? 
-                    // No, `find_by_id` takes &self. But `web::block` move closure?
-                    // Let's create repo inside block.
-                    
-                    // Actually, let's do update in another block or same if possible.
-                    // But `find_by_id` returns `UserSession` which we need to check.
-                    
-                    // Update last_used
-                     let _ = web::block(move || {
-                        let repo = DieselSessionRepository::new(pool.as_ref().clone());
-                        repo.update_last_used(session_id)
-                     }).await; // Ignore error on update stats
-                     
+                Some(s) if s.is_usable(now, state.config.auth.session_idle_timeout()) => {
+                    // Ignore errors, the request must not fail over usage stats
+                    let _ = session_repo.update_last_used(session_id).await;
+
                     Ok(AuthenticatedUser {
                         user_id: claims.sub,
                         session_id: claims.session_id,
@@ -90,7 +68,8 @@ impl FromRequest for AuthenticatedUser {
 }
 
 
-pub struct RequireAdmin;
+/// Only lets users with the `admin` role through, and tells the handler which admin it is.
+pub struct RequireAdmin(pub AuthenticatedUser);
 
 impl FromRequest for RequireAdmin {
     type Error = actix_web::Error;
@@ -104,7 +83,7 @@ impl FromRequest for RequireAdmin {
             
             let has_role = user.roles.iter().any(|r| r == "admin");
             if has_role {
-                Ok(RequireAdmin)
+                Ok(RequireAdmin(user))
             } else {
                 Err(AppError::Forbidden("Admin role required".to_string()).into())
             }
@@ -1,6 +1,6 @@
 use actix_web::web;
-use web::{post, get};
-use super::handlers::{register, login, verify_email, request_email_verification, request_password_reset, reset_password, logout, revoke_all_sessions, refresh_token, get_active_sessions};
+use web::{post, get, patch, delete};
+use super::handlers::{register, login, verify_email, request_email_verification, request_password_reset, reset_password, report_unrecognized_sign_in, logout, revoke_all_sessions, revoke_other_sessions, revoke_session, rename_session, refresh_token, get_active_sessions};
 
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -13,8 +13,12 @@ pub fn config(cfg: &mut web::ServiceConfig) {
              .route("/request-email-verification", post().to(request_email_verification))
              .route("/request-password-reset", post().to(request_password_reset))
              .route("/reset-password", post().to(reset_password))
+             .route("/not-me", post().to(report_unrecognized_sign_in))
              .route("/logout", post().to(logout))
              .route("/sessions", get().to(get_active_sessions))
              .route("/sessions/revoke-all", post().to(revoke_all_sessions))
+             .route("/sessions/revoke-others", post().to(revoke_other_sessions))
+             .route("/sessions/{id}", delete().to(revoke_session))
+             .route("/sessions/{id}", patch().to(rename_session))
     );
 }
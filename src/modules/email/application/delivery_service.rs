@@ -0,0 +1,66 @@
+use crate::common::errors::AppError;
+use crate::modules::email::domain::{
+    entity::{DeliveryEvent, DeliveryEventKind, NewEmailSuppression, SuppressionReason},
+    repository::SuppressionRepository,
+};
+use crate::modules::users::domain::repository::UserRepository;
+use tracing::instrument;
+
+/// Records provider delivery feedback. Hard bounces and complaints put the
+/// address on the suppression list and flag the owning user.
+pub struct DeliveryEventService<R: SuppressionRepository, U: UserRepository> {
+    suppression_repo: R,
+    user_repo: U,
+}
+
+impl<R: SuppressionRepository, U: UserRepository> DeliveryEventService<R, U> {
+    pub fn new(suppression_repo: R, user_repo: U) -> Self {
+        Self { suppression_repo, user_repo }
+    }
+
+    #[instrument(name = "DeliveryEventService::record", skip_all)]
+    pub async fn record(&self, event: DeliveryEvent) -> Result<(), AppError> {
+        let reason = match event.kind {
+            DeliveryEventKind::HardBounce => SuppressionReason::Bounce,
+            DeliveryEventKind::Complaint => SuppressionReason::Complaint,
+            DeliveryEventKind::SoftBounce | DeliveryEventKind::Other(_) => {
+                tracing::debug!("Ignoring delivery event {} ({:?})", event.provider_event_id, event.kind);
+                return Ok(());
+            }
+        };
+
+        for email in event.recipients {
+            let user = self.user_repo.find_by_email(&email).await?;
+
+            let suppression = self.suppression_repo.add(NewEmailSuppression {
+                email: email.to_lowercase(),
+                user_id: user.as_ref().map(|u| u.id),
+                reason: reason.to_string(),
+                description: event.description.clone(),
+                provider_event_id: Some(event.provider_event_id.clone()),
+            }).await?;
+
+            if let Some(suppression) = suppression {
+                tracing::warn!("Suppressed {} after {} (event {})", suppression.email, suppression.reason, event.provider_event_id);
+            }
+
+            if let Some(user) = user.filter(|u| !u.email_undeliverable) {
+                self.user_repo.set_email_undeliverable(user.id, true).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes the address off the suppression list and clears the owner's
+    /// flag, e.g. once the mailbox works again. Returns whether it was suppressed.
+    #[instrument(name = "DeliveryEventService::unsuppress", skip_all)]
+    pub async fn unsuppress(&self, email: &str) -> Result<bool, AppError> {
+        let removed = self.suppression_repo.remove(email).await?;
+
+        if let Some(user) = self.user_repo.find_by_email(email).await?.filter(|u| u.email_undeliverable) {
+            self.user_repo.set_email_undeliverable(user.id, false).await?;
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use diesel::{Queryable, Selectable, Insertable, Identifiable};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+use uuid::Uuid;
+use crate::schema::email_suppressions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum SuppressionReason {
+    /// The receiving server permanently rejected the address.
+    Bounce,
+    /// The recipient marked our mail as spam.
+    Complaint,
+}
+
+/// An address we must no longer send mail to.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = email_suppressions)]
+pub struct EmailSuppression {
+    pub id: Uuid,
+    pub email: String,
+    pub user_id: Option<Uuid>,
+    pub reason: String,
+    pub description: Option<String>,
+    pub provider_event_id: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = email_suppressions)]
+pub struct NewEmailSuppression {
+    pub email: String,
+    pub user_id: Option<Uuid>,
+    pub reason: String,
+    pub description: Option<String>,
+    pub provider_event_id: Option<String>,
+}
+
+/// Provider-agnostic delivery feedback about a message we sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryEventKind {
+    HardBounce,
+    SoftBounce,
+    Complaint,
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DeliveryEvent {
+    pub provider_event_id: String,
+    pub kind: DeliveryEventKind,
+    pub recipients: Vec<String>,
+    pub description: Option<String>,
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use super::entity::{EmailSuppression, NewEmailSuppression};
+use crate::common::errors::AppError;
+
+#[async_trait]
+pub trait SuppressionRepository: Send + Sync {
+    /// Adds the address to the suppression list. Returns `None` if it was already suppressed.
+    async fn add(&self, suppression: NewEmailSuppression) -> Result<Option<EmailSuppression>, AppError>;
+    async fn is_suppressed(&self, email: &str) -> Result<bool, AppError>;
+    /// Takes the address off the suppression list. Returns whether it was on it.
+    async fn remove(&self, email: &str) -> Result<bool, AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn SuppressionRepository>`.
+#[async_trait]
+impl<T: SuppressionRepository + ?Sized> SuppressionRepository for std::sync::Arc<T> {
+    async fn add(&self, suppression: NewEmailSuppression) -> Result<Option<EmailSuppression>, AppError> {
+        (**self).add(suppression).await
+    }
+
+    async fn is_suppressed(&self, email: &str) -> Result<bool, AppError> {
+        (**self).is_suppressed(email).await
+    }
+
+    async fn remove(&self, email: &str) -> Result<bool, AppError> {
+        (**self).remove(email).await
+    }
+}
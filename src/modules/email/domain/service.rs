@@ -1,12 +1,27 @@
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
 use crate::common::errors::AppError;
 
 #[derive(Debug, Clone)]
 pub struct EmailRecipient {
     pub email: String,
     pub name: Option<String>,
+    /// BCP 47 language tag (e.g. `es` or `pt-BR`) used to pick a localized template.
+    pub locale: Option<String>,
+    /// Set when the recipient is a registered user, enabling notification
+    /// preferences and unsubscribe links.
+    pub user_id: Option<Uuid>,
+}
 
-
+/// Details of a sign-in shown in a new sign-in alert.
+#[derive(Debug, Clone)]
+pub struct SignInAlert {
+    pub device: String,
+    pub ip_address: Option<String>,
+    /// e.g. `"London, United Kingdom"`, when the IP address could be located.
+    pub location: Option<String>,
+    pub signed_in_at: NaiveDateTime,
 }
 
 #[async_trait]
@@ -14,4 +29,28 @@ pub trait EmailService: Send + Sync {
 
     async fn send_verification_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError>;
     async fn send_password_reset_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError>;
+    /// `token` is the signed "this wasn't me" token for the new session.
+    async fn send_new_sign_in_alert(&self, recipient: &EmailRecipient, alert: &SignInAlert, token: &str) -> Result<(), AppError>;
+    /// Renders a registered template with `variables` and sends it.
+    async fn send_template(&self, recipient: &EmailRecipient, template: &str, variables: &serde_json::Value) -> Result<(), AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn EmailService>`.
+#[async_trait]
+impl<T: EmailService + ?Sized> EmailService for std::sync::Arc<T> {
+    async fn send_verification_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
+        (**self).send_verification_email(recipient, token).await
+    }
+
+    async fn send_password_reset_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
+        (**self).send_password_reset_email(recipient, token).await
+    }
+
+    async fn send_new_sign_in_alert(&self, recipient: &EmailRecipient, alert: &SignInAlert, token: &str) -> Result<(), AppError> {
+        (**self).send_new_sign_in_alert(recipient, alert, token).await
+    }
+
+    async fn send_template(&self, recipient: &EmailRecipient, template: &str, variables: &serde_json::Value) -> Result<(), AppError> {
+        (**self).send_template(recipient, template, variables).await
+    }
 }
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::common::{database::{self, DbHandle}, errors::AppError};
+use crate::modules::email::domain::{
+    entity::{EmailSuppression, NewEmailSuppression},
+    repository::SuppressionRepository,
+};
+use crate::schema::email_suppressions;
+use tracing::instrument;
+
+pub struct DieselSuppressionRepository {
+    db: DbHandle,
+}
+
+impl DieselSuppressionRepository {
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
+    }
+}
+
+#[async_trait]
+impl SuppressionRepository for DieselSuppressionRepository {
+    #[instrument(name = "SuppressionRepository::add", skip_all)]
+    async fn add(&self, suppression: NewEmailSuppression) -> Result<Option<EmailSuppression>, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(email_suppressions::table)
+                .values(&suppression)
+                .on_conflict(email_suppressions::email)
+                .do_nothing()
+                .get_result(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SuppressionRepository::is_suppressed", skip_all)]
+    async fn is_suppressed(&self, email: &str) -> Result<bool, AppError> {
+        let email = email.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::select(diesel::dsl::exists(
+                email_suppressions::table.filter(email_suppressions::email.eq(email.to_lowercase())),
+            ))
+            .get_result(conn)
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "SuppressionRepository::remove", skip_all)]
+    async fn remove(&self, email: &str) -> Result<bool, AppError> {
+        let email = email.to_lowercase();
+        database::run(&self.db, move |conn| {
+            diesel::delete(email_suppressions::table.filter(email_suppressions::email.eq(email)))
+                .execute(conn)
+                .map(|deleted| deleted > 0)
+                .map_err(AppError::from)
+        }).await
+    }
+}
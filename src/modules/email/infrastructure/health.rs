@@ -0,0 +1,19 @@
+use crate::common::{health::{FnHealthCheck, HealthRegistry}, live_config::LiveConfig};
+
+/// Checks that Resend is configured well enough to send, without calling it.
+pub fn register(registry: &mut HealthRegistry, live_config: &LiveConfig) {
+    let live_config = live_config.clone();
+    registry.register(FnHealthCheck::new("email", move || {
+        let config = live_config.get();
+        async move {
+            let email = &config.email;
+            if !email.resend_api_key.expose().starts_with("re_") {
+                return Err("RESEND_API_KEY is not a Resend API key".to_string());
+            }
+            if !email.from.contains('@') {
+                return Err(format!("EMAIL_FROM {:?} is not an email address", email.from));
+            }
+            Ok(Some(format!("resend, sending as {}", email.from)))
+        }
+    }));
+}
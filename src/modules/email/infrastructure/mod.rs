@@ -1 +1,7 @@
+pub mod diesel_repository;
+pub mod health;
 pub mod resend;
+pub mod resend_webhook;
+pub mod templates;
+#[cfg(any(test, feature = "testing"))]
+pub mod recording;
@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use crate::common::errors::AppError;
+use crate::modules::email::domain::service::{EmailRecipient, EmailService, SignInAlert};
+
+/// An email captured by `RecordingEmailService`.
+#[derive(Debug, Clone)]
+pub struct SentEmail {
+    pub recipient: EmailRecipient,
+    pub template: String,
+    /// The raw token of verification, password reset and new sign-in emails.
+    pub token: Option<String>,
+    pub variables: Value,
+}
+
+/// Records emails instead of sending them, so tests can assert on them and
+/// pull out the tokens they carry. Clones share the same outbox.
+#[derive(Clone, Default)]
+pub struct RecordingEmailService {
+    sent: Arc<Mutex<Vec<SentEmail>>>,
+}
+
+impl RecordingEmailService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sent(&self) -> Vec<SentEmail> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// The most recent email sent to `email` using `template`.
+    pub fn last_sent(&self, email: &str, template: &str) -> Option<SentEmail> {
+        self.sent.lock().unwrap().iter()
+            .rev()
+            .find(|e| e.recipient.email == email && e.template == template)
+            .cloned()
+    }
+
+    /// The token of the most recent email sent to `email` using `template`.
+    pub fn last_token(&self, email: &str, template: &str) -> Option<String> {
+        self.last_sent(email, template).and_then(|e| e.token)
+    }
+
+    fn record(&self, recipient: &EmailRecipient, template: &str, token: Option<&str>, variables: Value) {
+        self.sent.lock().unwrap().push(SentEmail {
+            recipient: recipient.clone(),
+            template: template.to_string(),
+            token: token.map(str::to_string),
+            variables,
+        });
+    }
+}
+
+#[async_trait]
+impl EmailService for RecordingEmailService {
+    async fn send_verification_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
+        self.record(recipient, "verification", Some(token), json!({}));
+        Ok(())
+    }
+
+    async fn send_password_reset_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
+        self.record(recipient, "password_reset", Some(token), json!({}));
+        Ok(())
+    }
+
+    async fn send_new_sign_in_alert(&self, recipient: &EmailRecipient, alert: &SignInAlert, token: &str) -> Result<(), AppError> {
+        self.record(recipient, "new_sign_in", Some(token), json!({
+            "device": alert.device,
+            "ip_address": alert.ip_address,
+            "location": alert.location,
+        }));
+        Ok(())
+    }
+
+    async fn send_template(&self, recipient: &EmailRecipient, template: &str, variables: &Value) -> Result<(), AppError> {
+        self.record(recipient, template, None, variables.clone());
+        Ok(())
+    }
+}
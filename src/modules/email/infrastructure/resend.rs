@@ -1,30 +1,45 @@
+use std::sync::Arc;
 use async_trait::async_trait;
+use chrono::Duration;
 use reqwest::Client;
 
-use serde_json::json;
-use crate::common::{config::AppConfig, errors::AppError};
-use super::super::domain::service::{EmailService, EmailRecipient};
-use std::fs;
+use serde_json::{json, Map, Value};
+use crate::common::{clock::Clock, errors::AppError, live_config::LiveConfig, metrics, signed_token::TokenSigner};
+use crate::modules::users::domain::{
+    entity::notification::{UnsubscribeRequest, UNSUBSCRIBE_TOKEN_DAYS, UNSUBSCRIBE_TOKEN_PURPOSE},
+    repository::notification::NotificationPreferenceRepository,
+};
+use super::super::domain::{repository::SuppressionRepository, service::{EmailService, EmailRecipient, SignInAlert}};
+use super::templates::EmailTemplates;
 
-pub struct ResendEmailService {
+pub struct ResendEmailService<R: SuppressionRepository, P: NotificationPreferenceRepository> {
     client: Client,
-    config: AppConfig,
+    templates: EmailTemplates,
+    suppression_repo: R,
+    preference_repo: P,
+    config: LiveConfig,
+    clock: Arc<dyn Clock>,
 }
 
-impl ResendEmailService {
-    pub fn new(config: AppConfig) -> Self {
+impl<R: SuppressionRepository, P: NotificationPreferenceRepository> ResendEmailService<R, P> {
+    pub fn new(config: LiveConfig, suppression_repo: R, preference_repo: P, clock: Arc<dyn Clock>) -> Self {
         Self {
             client: Client::new(),
+            templates: EmailTemplates::from_config(&config.get()),
+            suppression_repo,
+            preference_repo,
             config,
+            clock,
         }
     }
 
-    async fn send(&self, to: &str, subject: &str, html: String, text: String) -> Result<(), AppError> {
+    async fn send(&self, to: &str, subject: &str, html: String, text: String, headers: Map<String, Value>) -> Result<(), AppError> {
         let url = "https://api.resend.com/emails";
-        let api_key = &self.config.resend_api_key;
+        let config = self.config.get();
+        let api_key = &config.email.resend_api_key;
         
         // Use configured sender
-        let from = &self.config.email_from; 
+        let from = &config.email.from; 
 
 
         let body = json!({
@@ -32,11 +47,12 @@ impl ResendEmailService {
             "to": to,
             "subject": subject,
             "html": html,
-            "text": text
+            "text": text,
+            "headers": headers
         });
 
         let response = self.client.post(url)
-            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Authorization", format!("Bearer {}", api_key.expose()))
             .json(&body)
             .send()
             .await
@@ -55,17 +71,6 @@ impl ResendEmailService {
         }
     }
 
-    fn read_template(&self, name: &str) -> Result<String, AppError> {
-        // In production, templates might be compiled in or cached.
-        // For this template, we read from disk.
-        // Assuming running from project root.
-        let path = format!("src/email_templates/{}", name);
-        fs::read_to_string(&path).map_err(|e| {
-            tracing::error!("Failed to read email template {}: {:?}", path, e);
-            AppError::InternalError
-        })
-    }
-
     fn format_recipient(recipient: &EmailRecipient) -> String {
         match &recipient.name {
             Some(name) => format!("{} <{}>", name, recipient.email),
@@ -75,28 +80,79 @@ impl ResendEmailService {
 }
 
 #[async_trait]
-impl EmailService for ResendEmailService {
+impl<R, P> EmailService for ResendEmailService<R, P>
+where
+    R: SuppressionRepository + Send + Sync,
+    P: NotificationPreferenceRepository + Send + Sync,
+{
     async fn send_verification_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
-        let link = format!("{}/auth/verify-email?token={}", self.config.app_url, token);
-        let html_template = self.read_template("verification.html")?;
-        let text_template = self.read_template("verification.txt")?;
+        let config = self.config.get();
+        let link = format!("{}/auth/verify-email?token={}", config.server.app_url, token);
+        self.send_template(recipient, "verification", &json!({
+            "verification_link": link,
+            "expires_in_hours": config.auth.email_verification_hours,
+        })).await
+    }
 
-        let html = html_template.replace("{{verification_link}}", &link);
-        let text = text_template.replace("{{verification_link}}", &link);
+    async fn send_password_reset_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
+        let config = self.config.get();
+        let link = format!("{}/auth/reset-password?token={}", config.server.app_url, token);
+        self.send_template(recipient, "password_reset", &json!({
+            "reset_link": link,
+            "expires_in_minutes": config.auth.password_reset_minutes,
+        })).await
+    }
 
-        let to = Self::format_recipient(recipient);
-        self.send(&to, "Verify your email", html, text).await
+    async fn send_new_sign_in_alert(&self, recipient: &EmailRecipient, alert: &SignInAlert, token: &str) -> Result<(), AppError> {
+        let link = format!("{}/auth/not-me?token={}", self.config.get().server.app_url, token);
+        self.send_template(recipient, "new_sign_in", &json!({
+            "device": alert.device,
+            "ip_address": alert.ip_address.as_deref().unwrap_or("unknown"),
+            "location": alert.location,
+            "signed_in_at": alert.signed_in_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            "not_me_link": link,
+        })).await
     }
 
-    async fn send_password_reset_email(&self, recipient: &EmailRecipient, token: &str) -> Result<(), AppError> {
-        let link = format!("{}/auth/reset-password?token={}", self.config.app_url, token);
-        let html_template = self.read_template("password_reset.html")?;
-        let text_template = self.read_template("password_reset.txt")?;
+    async fn send_template(&self, recipient: &EmailRecipient, template: &str, variables: &Value) -> Result<(), AppError> {
+        let category = EmailTemplates::definition(template)?.category;
+        let mut variables = variables.clone();
+        let mut headers = Map::new();
+
+        // Non-transactional mail honours the user's preferences and carries
+        // RFC 8058 one-click unsubscribe headers.
+        if let (false, Some(user_id)) = (category.is_transactional(), recipient.user_id) {
+            let stored = self.preference_repo.find(user_id, category).await?;
+            if !category.is_enabled(stored.map(|p| p.enabled)) {
+                tracing::debug!("User {} opted out of {} emails, skipping '{}'", user_id, category, template);
+                return Ok(());
+            }
+
+            let token = TokenSigner::from_config(&self.config.get()).sign(
+                UNSUBSCRIBE_TOKEN_PURPOSE,
+                &UnsubscribeRequest { user_id, category }.to_token_data(),
+                Some(self.clock.now() + Duration::days(UNSUBSCRIBE_TOKEN_DAYS)),
+            );
+            let one_click_url = format!("{}/notifications/unsubscribe?token={}", self.config.get().server.api_url(), token);
+            headers.insert("List-Unsubscribe".to_string(), format!("<{}>", one_click_url).into());
+            headers.insert("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".into());
+
+            if let Some(vars) = variables.as_object_mut() {
+                let link = format!("{}/notifications/unsubscribe?token={}", self.config.get().server.app_url, token);
+                vars.insert("unsubscribe_link".to_string(), link.into());
+            }
+        }
 
-        let html = html_template.replace("{{reset_link}}", &link);
-        let text = text_template.replace("{{reset_link}}", &link);
+        let email = self.templates.render(template, recipient.locale.as_deref(), &variables)?;
+
+        if self.suppression_repo.is_suppressed(&recipient.email).await? {
+            tracing::warn!("Not sending '{}' email to suppressed address {}", template, recipient.email);
+            return Ok(());
+        }
 
         let to = Self::format_recipient(recipient);
-        self.send(&to, "Reset your password", html, text).await
+        let result = self.send(&to, &email.subject, email.html, email.text, headers).await;
+        metrics::record_email(template, result.is_ok());
+        result
     }
 }
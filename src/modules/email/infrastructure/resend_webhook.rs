@@ -0,0 +1,174 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use crate::common::errors::AppError;
+use crate::modules::email::domain::entity::{DeliveryEvent, DeliveryEventKind};
+
+/// How far the `svix-timestamp` header may drift from our clock, in seconds.
+const TIMESTAMP_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// The Svix headers sent along with every webhook delivery.
+pub struct SvixHeaders<'a> {
+    pub id: &'a str,
+    pub timestamp: &'a str,
+    pub signature: &'a str,
+}
+
+/// Verifies Svix webhook signatures as used by Resend.
+///
+/// The signature is an HMAC-SHA256 of `"{id}.{timestamp}.{body}"` keyed with
+/// the base64 part of the `whsec_...` signing secret. The header may carry
+/// several space separated `v1,<base64>` signatures during secret rotation.
+pub struct SvixVerifier {
+    key: Vec<u8>,
+}
+
+impl SvixVerifier {
+    pub fn new(secret: &str) -> Result<Self, AppError> {
+        let encoded = secret.strip_prefix("whsec_").unwrap_or(secret);
+        let key = STANDARD.decode(encoded).map_err(|e| {
+            tracing::error!("Invalid webhook signing secret: {}", e);
+            AppError::InternalError
+        })?;
+
+        Ok(Self { key })
+    }
+
+    pub fn verify(&self, headers: &SvixHeaders, body: &[u8]) -> Result<(), AppError> {
+        self.verify_at(headers, body, Utc::now().timestamp())
+    }
+
+    fn verify_at(&self, headers: &SvixHeaders, body: &[u8], now: i64) -> Result<(), AppError> {
+        let timestamp: i64 = headers.timestamp.parse()
+            .map_err(|_| AppError::Unauthorized("Invalid webhook timestamp".to_string()))?;
+
+        if (now - timestamp).abs() > TIMESTAMP_TOLERANCE_SECS {
+            return Err(AppError::Unauthorized("Webhook timestamp outside of tolerance".to_string()));
+        }
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).map_err(|_| AppError::InternalError)?;
+        mac.update(headers.id.as_bytes());
+        mac.update(b".");
+        mac.update(headers.timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+
+        let matches = headers.signature
+            .split_whitespace()
+            .filter_map(|versioned| versioned.strip_prefix("v1,"))
+            .filter_map(|encoded| STANDARD.decode(encoded).ok())
+            .any(|signature| mac.clone().verify_slice(&signature).is_ok());
+
+        if matches {
+            Ok(())
+        } else {
+            Err(AppError::Unauthorized("Invalid webhook signature".to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResendEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    data: ResendEventData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResendEventData {
+    #[serde(default)]
+    to: Vec<String>,
+    bounce: Option<ResendBounce>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResendBounce {
+    #[serde(rename = "type")]
+    bounce_type: Option<String>,
+    #[serde(rename = "subType")]
+    sub_type: Option<String>,
+    message: Option<String>,
+}
+
+/// Maps a Resend webhook payload to a [`DeliveryEvent`].
+pub fn parse_event(provider_event_id: &str, body: &[u8]) -> Result<DeliveryEvent, AppError> {
+    let event: ResendEvent = serde_json::from_slice(body).map_err(|e| {
+        tracing::warn!("Malformed Resend webhook payload: {}", e);
+        AppError::BadRequest("Malformed webhook payload".to_string())
+    })?;
+
+    let (kind, description) = match event.event_type.as_str() {
+        "email.bounced" => {
+            let bounce = event.data.bounce;
+            let permanent = bounce.as_ref()
+                .and_then(|b| b.bounce_type.as_deref())
+                .is_some_and(|t| t.eq_ignore_ascii_case("permanent"));
+            let description = bounce.map(|b| match (b.sub_type, b.message) {
+                (Some(sub_type), Some(message)) => format!("{}: {}", sub_type, message),
+                (sub_type, message) => sub_type.or(message).unwrap_or_default(),
+            });
+
+            let kind = if permanent { DeliveryEventKind::HardBounce } else { DeliveryEventKind::SoftBounce };
+            (kind, description)
+        }
+        "email.complained" => (DeliveryEventKind::Complaint, None),
+        other => (DeliveryEventKind::Other(other.to_string()), None),
+    };
+
+    Ok(DeliveryEvent {
+        provider_event_id: provider_event_id.to_string(),
+        kind,
+        recipients: event.data.to,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from the Svix documentation
+    const SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+    const ID: &str = "msg_p5jXN8AQM9LWM0D4loKWxJek";
+    const TIMESTAMP: i64 = 1614265330;
+    const BODY: &str = r#"{"test": 2432232314}"#;
+    const SIGNATURE: &str = "v1,g0hM9SsE+OTPJTGt/tmIKtSyZlE3uFJELVlNIOLJ1OE=";
+
+    fn headers(signature: &str) -> SvixHeaders<'_> {
+        SvixHeaders { id: ID, timestamp: "1614265330", signature }
+    }
+
+    #[test]
+    fn test_accepts_valid_signature() {
+        let verifier = SvixVerifier::new(SECRET).unwrap();
+        let rotated = format!("v1,bm90LXRoZS1yaWdodC1vbmU= {}", SIGNATURE);
+
+        assert!(verifier.verify_at(&headers(SIGNATURE), BODY.as_bytes(), TIMESTAMP).is_ok());
+        assert!(verifier.verify_at(&headers(&rotated), BODY.as_bytes(), TIMESTAMP + 60).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_tampered_body_and_stale_timestamp() {
+        let verifier = SvixVerifier::new(SECRET).unwrap();
+
+        assert!(verifier.verify_at(&headers(SIGNATURE), br#"{"test": 1}"#, TIMESTAMP).is_err());
+        assert!(verifier.verify_at(&headers(SIGNATURE), BODY.as_bytes(), TIMESTAMP + 600).is_err());
+    }
+
+    #[test]
+    fn test_parses_bounce_and_complaint() {
+        let bounce = br#"{"type":"email.bounced","data":{"to":["a@example.com"],"bounce":{"type":"Permanent","subType":"General","message":"No such user"}}}"#;
+        let event = parse_event("msg_1", bounce).unwrap();
+        assert_eq!(event.kind, DeliveryEventKind::HardBounce);
+        assert_eq!(event.recipients, ["a@example.com"]);
+        assert_eq!(event.description.as_deref(), Some("General: No such user"));
+
+        let transient = br#"{"type":"email.bounced","data":{"to":["a@example.com"],"bounce":{"type":"Transient"}}}"#;
+        assert_eq!(parse_event("msg_2", transient).unwrap().kind, DeliveryEventKind::SoftBounce);
+
+        let complaint = br#"{"type":"email.complained","data":{"to":["a@example.com"]}}"#;
+        assert_eq!(parse_event("msg_3", complaint).unwrap().kind, DeliveryEventKind::Complaint);
+    }
+}
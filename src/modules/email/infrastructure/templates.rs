@@ -0,0 +1,391 @@
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+use minijinja::{context, escape_formatter, AutoEscape, Environment, Error, Output, State, UndefinedBehavior, Value};
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use thiserror::Error;
+use validator::{ValidationError, ValidationErrors};
+use crate::common::{config::AppConfig, errors::AppError};
+use crate::modules::users::domain::entity::notification::NotificationCategory;
+
+/// Embeds a template file from `src/email_templates` into the binary.
+macro_rules! embed {
+    ($path:literal) => {
+        ($path, include_str!(concat!("../../../email_templates/", $path)))
+    };
+}
+
+/// Templates compiled into the binary. Files in the override directory with
+/// the same relative path take precedence over these.
+static EMBEDDED_TEMPLATES: &[(&str, &str)] = &[
+    embed!("layouts/base.html"),
+    embed!("layouts/base.txt"),
+    embed!("partials/button.html"),
+    embed!("partials/footer.html"),
+    embed!("partials/footer.txt"),
+    embed!("verification.subject.txt"),
+    embed!("verification.html"),
+    embed!("verification.txt"),
+    embed!("password_reset.subject.txt"),
+    embed!("password_reset.html"),
+    embed!("password_reset.txt"),
+    embed!("new_sign_in.subject.txt"),
+    embed!("new_sign_in.html"),
+    embed!("new_sign_in.txt"),
+    embed!("es/verification.subject.txt"),
+    embed!("es/verification.html"),
+    embed!("es/verification.txt"),
+    embed!("es/password_reset.subject.txt"),
+    embed!("es/password_reset.html"),
+    embed!("es/password_reset.txt"),
+    embed!("es/new_sign_in.subject.txt"),
+    embed!("es/new_sign_in.html"),
+    embed!("es/new_sign_in.txt"),
+];
+
+/// A renderable email and the variables it needs.
+#[derive(Debug, Serialize)]
+pub struct TemplateDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Decides whether the email honours notification preferences.
+    pub category: NotificationCategory,
+    /// Required variables, each with a sample value used for previews.
+    pub variables: &'static [(&'static str, &'static str)],
+}
+
+impl TemplateDefinition {
+    pub fn sample_variables(&self) -> JsonValue {
+        self.variables
+            .iter()
+            .map(|(name, sample)| (name.to_string(), JsonValue::from(*sample)))
+            .collect::<Map<_, _>>()
+            .into()
+    }
+}
+
+/// Every email the application can send. A template must be registered here
+/// to be rendered.
+pub static TEMPLATE_DEFINITIONS: &[TemplateDefinition] = &[
+    TemplateDefinition {
+        name: "verification",
+        description: "Sent after registration to confirm the email address",
+        category: NotificationCategory::Account,
+        variables: &[
+            ("verification_link", "https://example.com/auth/verify-email?token=sample"),
+            ("expires_in_hours", "24"),
+        ],
+    },
+    TemplateDefinition {
+        name: "password_reset",
+        description: "Sent when a user requests a password reset",
+        category: NotificationCategory::Account,
+        variables: &[
+            ("reset_link", "https://example.com/auth/reset-password?token=sample"),
+            ("expires_in_minutes", "15"),
+        ],
+    },
+    TemplateDefinition {
+        name: "new_sign_in",
+        description: "Sent when a sign-in comes from an unfamiliar device or an impossible travel distance away",
+        category: NotificationCategory::SecurityAlerts,
+        variables: &[
+            ("device", "Chrome on Windows"),
+            ("ip_address", "203.0.113.7"),
+            ("signed_in_at", "2026-01-01 12:00 UTC"),
+            ("not_me_link", "https://example.com/auth/not-me?token=sample"),
+        ],
+    },
+];
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Email template '{0}' is not registered")]
+    UnknownTemplate(String),
+
+    #[error("Email template '{template}' is missing variables: {}", missing.join(", "))]
+    MissingVariables { template: String, missing: Vec<String> },
+
+    #[error("Failed to render email template {path}: {message}")]
+    Render { path: String, message: String },
+}
+
+impl From<TemplateError> for AppError {
+    fn from(err: TemplateError) -> Self {
+        match err {
+            TemplateError::UnknownTemplate(name) => AppError::NotFound(format!("Email template '{}'", name)),
+            TemplateError::MissingVariables { missing, .. } => {
+                let mut errors = ValidationErrors::new();
+                for name in missing {
+                    let mut error = ValidationError::new("missing_variable");
+                    error.message = Some(format!("Missing template variable '{}'", name).into());
+                    error.add_param("name".into(), &name);
+                    errors.add("variables", error);
+                }
+                AppError::ValidationError(errors)
+            }
+            TemplateError::Render { .. } => {
+                tracing::error!("{}", err);
+                AppError::InternalError
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Renders email templates made of three parts sharing a base name:
+/// `<name>.subject.txt`, `<name>.html` (auto-escaped) and `<name>.txt`.
+///
+/// Localized variants live in a `<locale>/` sub-directory and are picked
+/// from the most to the least specific locale tag, e.g. `pt-BR/`, `pt/`,
+/// then the root.
+pub struct EmailTemplates {
+    env: Environment<'static>,
+    override_dir: Option<PathBuf>,
+    app_url: String,
+}
+
+impl EmailTemplates {
+    pub fn new(override_dir: Option<PathBuf>, app_url: String) -> Self {
+        let loader_dir = override_dir.clone();
+
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_formatter(html_formatter);
+        env.set_loader(move |name| Ok(load_source(loader_dir.as_deref(), name)));
+
+        Self {
+            env,
+            override_dir,
+            app_url,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            config.email.templates_dir.clone().map(Into::into),
+            config.server.app_url.clone(),
+        )
+    }
+
+    pub fn definitions() -> &'static [TemplateDefinition] {
+        TEMPLATE_DEFINITIONS
+    }
+
+    pub fn definition(name: &str) -> Result<&'static TemplateDefinition, TemplateError> {
+        TEMPLATE_DEFINITIONS
+            .iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))
+    }
+
+    /// Renders a registered template. Fails if any declared variable is
+    /// missing or null, or if the template uses an undefined one.
+    pub fn render(&self, name: &str, locale: Option<&str>, vars: &JsonValue) -> Result<RenderedEmail, TemplateError> {
+        let definition = Self::definition(name)?;
+
+        let missing: Vec<String> = definition
+            .variables
+            .iter()
+            .map(|(var, _)| *var)
+            .filter(|var| vars.get(var).is_none_or(JsonValue::is_null))
+            .map(str::to_string)
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVariables { template: name.to_string(), missing });
+        }
+
+        let (prefix, locale) = self.resolve_locale(name, locale);
+        let ctx = context! {
+            locale => locale,
+            app_url => self.app_url.clone(),
+            ..Value::from_serialize(vars)
+        };
+
+        let subject = self.render_file(&format!("{}{}.subject.txt", prefix, name), &ctx)?;
+        let html = self.render_file(&format!("{}{}.html", prefix, name), &ctx)?;
+        let text = self.render_file(&format!("{}{}.txt", prefix, name), &ctx)?;
+
+        Ok(RenderedEmail {
+            subject: subject.trim().to_string(),
+            html,
+            text: text.trim().to_string(),
+        })
+    }
+
+    fn render_file(&self, path: &str, ctx: &Value) -> Result<String, TemplateError> {
+        self.env
+            .get_template(path)
+            .and_then(|template| template.render(ctx))
+            .map_err(|e| TemplateError::Render {
+                path: path.to_string(),
+                message: format!("{:#}", e),
+            })
+    }
+
+    /// Returns the directory prefix of the most specific locale that has a
+    /// variant of `name`, together with the locale that was selected.
+    fn resolve_locale(&self, name: &str, locale: Option<&str>) -> (String, String) {
+        let requested = locale.map(|l| l.trim().replace('_', "-")).unwrap_or_default();
+
+        let mut candidates = Vec::new();
+        if !requested.is_empty() {
+            candidates.push(requested.clone());
+            if let Some((language, _)) = requested.split_once('-') {
+                candidates.push(language.to_string());
+            }
+        }
+
+        for candidate in candidates {
+            let prefix = format!("{}/", candidate);
+            if self.exists(&format!("{}{}.html", prefix, name)) {
+                return (prefix, candidate);
+            }
+        }
+
+        (String::new(), DEFAULT_LOCALE.to_string())
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        load_source(self.override_dir.as_deref(), name).is_some()
+    }
+}
+
+/// Like the default formatter, but leaves `/` alone when escaping HTML so links
+/// stay readable in the markup (the default escapes it as `&#x2f;`).
+fn html_formatter(out: &mut Output, state: &State, value: &Value) -> Result<(), Error> {
+    match value.as_str() {
+        Some(s) if state.auto_escape() == AutoEscape::Html && !value.is_safe() => {
+            for c in s.chars() {
+                let result = match c {
+                    '&' => out.write_str("&amp;"),
+                    '<' => out.write_str("&lt;"),
+                    '>' => out.write_str("&gt;"),
+                    '"' => out.write_str("&quot;"),
+                    '\'' => out.write_str("&#x27;"),
+                    c => out.write_char(c),
+                };
+                result.map_err(Error::from)?;
+            }
+            Ok(())
+        }
+        _ => escape_formatter(out, state, value),
+    }
+}
+
+fn load_source(override_dir: Option<&Path>, name: &str) -> Option<String> {
+    // Template names come from our own code and from `extends`/`include`
+    // tags, but never allow them to escape the override directory.
+    let is_safe = Path::new(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if !is_safe {
+        return None;
+    }
+
+    if let Some(dir) = override_dir {
+        let path = dir.join(name);
+        if path.is_file() {
+            match std::fs::read_to_string(&path) {
+                Ok(source) => return Some(source),
+                Err(e) => tracing::warn!("Failed to read email template override {:?}: {}", path, e),
+            }
+        }
+    }
+
+    EMBEDDED_TEMPLATES
+        .iter()
+        .find(|(path, _)| *path == name)
+        .map(|(_, source)| source.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn templates() -> EmailTemplates {
+        EmailTemplates::new(None, "https://app.example.com".to_string())
+    }
+
+    #[test]
+    fn test_renders_embedded_template_with_layout() {
+        let email = templates()
+            .render("verification", None, &json!({ "verification_link": "https://app.example.com/verify?token=abc", "expires_in_hours": 48 }))
+            .unwrap();
+
+        assert_eq!(email.subject, "Verify your email");
+        assert!(email.html.contains("<html lang=\"en\">"));
+        assert!(email.html.contains("href=\"https://app.example.com/verify?token=abc\""));
+        assert!(email.text.contains("https://app.example.com/verify?token=abc"));
+        assert!(email.text.contains("expire in 48 hours"));
+        assert!(email.text.ends_with("https://app.example.com"));
+    }
+
+    #[test]
+    fn test_html_is_escaped_but_text_is_not() {
+        let email = templates()
+            .render("password_reset", None, &json!({ "reset_link": "https://x.test/?a=1&b=<2>", "expires_in_minutes": 15 }))
+            .unwrap();
+
+        assert!(email.html.contains("https://x.test/?a=1&amp;b=&lt;2&gt;"));
+        assert!(email.text.contains("https://x.test/?a=1&b=<2>"));
+    }
+
+    #[test]
+    fn test_selects_locale_variant_with_fallback() {
+        let vars = json!({ "verification_link": "https://x.test", "expires_in_hours": 24 });
+
+        let es = templates().render("verification", Some("es_MX"), &vars).unwrap();
+        assert_eq!(es.subject, "Verifica tu correo electrónico");
+        assert!(es.html.contains("<html lang=\"es\">"));
+
+        let fr = templates().render("verification", Some("fr"), &vars).unwrap();
+        assert_eq!(fr.subject, "Verify your email");
+    }
+
+    #[test]
+    fn test_missing_variables_fail() {
+        let err = templates().render("verification", None, &json!({ "verification_link": null, "expires_in_hours": 24 })).unwrap_err();
+        assert!(matches!(err, TemplateError::MissingVariables { missing, .. } if missing == ["verification_link"]));
+
+        let err = templates().render("unknown", None, &json!({})).unwrap_err();
+        assert!(matches!(err, TemplateError::UnknownTemplate(_)));
+    }
+
+    #[test]
+    fn test_every_definition_renders_with_samples() {
+        for definition in EmailTemplates::definitions() {
+            for locale in [None, Some("es")] {
+                let result = templates().render(definition.name, locale, &definition.sample_variables());
+                assert!(result.is_ok(), "{} ({:?}): {:?}", definition.name, locale, result.err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_override_directory_takes_precedence() {
+        let dir = std::env::temp_dir().join(format!("email-templates-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("verification.subject.txt"), "Custom {{ verification_link }}").unwrap();
+
+        let email = EmailTemplates::new(Some(dir.clone()), "https://x.test".to_string())
+            .render("verification", None, &json!({ "verification_link": "link", "expires_in_hours": 24 }))
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(email.subject, "Custom link");
+        assert!(email.html.contains("Verify Email"));
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::modules::email::infrastructure::templates::{RenderedEmail, TemplateDefinition};
+
+#[derive(Debug, Serialize)]
+pub struct TemplateVariableDto {
+    pub name: String,
+    pub sample: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmailTemplateDto {
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub variables: Vec<TemplateVariableDto>,
+}
+
+impl From<&TemplateDefinition> for EmailTemplateDto {
+    fn from(definition: &TemplateDefinition) -> Self {
+        Self {
+            name: definition.name.to_string(),
+            description: definition.description.to_string(),
+            category: definition.category.to_string(),
+            variables: definition.variables.iter().map(|(name, sample)| TemplateVariableDto {
+                name: name.to_string(),
+                sample: sample.to_string(),
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PreviewQueryDto {
+    #[validate(length(min = 2, max = 35, message = "Invalid locale"))]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PreviewEmailDto {
+    #[validate(length(min = 2, max = 35, message = "Invalid locale"))]
+    pub locale: Option<String>,
+    /// Falls back to the template's sample values when omitted.
+    pub variables: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TestSendEmailDto {
+    #[validate(length(min = 1))]
+    pub template: String,
+    #[validate(email(message = "Invalid email address"))]
+    pub to: String,
+    #[validate(length(min = 2, max = 35, message = "Invalid locale"))]
+    pub locale: Option<String>,
+    /// Falls back to the template's sample values when omitted.
+    pub variables: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmailPreviewDto {
+    pub template: String,
+    pub variables: serde_json::Value,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl EmailPreviewDto {
+    pub fn new(template: &str, variables: serde_json::Value, email: RenderedEmail) -> Self {
+        Self {
+            template: template.to_string(),
+            variables,
+            subject: email.subject,
+            html: email.html,
+            text: email.text,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use validator::Validate;
+use crate::app::AppState;
+use crate::common::{config::{AppConfig, Secret}, errors::AppError};
+use crate::modules::audit::domain::{entity::{AuditAction, AuditEntry}, service::AuditLog};
+use crate::modules::auth::interfaces::http::middleware::RequireAdmin;
+use crate::modules::email::{
+    application::delivery_service::DeliveryEventService,
+    domain::{repository::SuppressionRepository, service::{EmailRecipient, EmailService}},
+    infrastructure::{
+        resend_webhook::{self, SvixHeaders, SvixVerifier},
+        templates::EmailTemplates,
+    },
+};
+use crate::modules::users::domain::repository::UserRepository;
+use super::dto::{EmailPreviewDto, EmailTemplateDto, PreviewEmailDto, PreviewQueryDto, TestSendEmailDto};
+
+pub type DeliveryEventServiceImpl = DeliveryEventService<Arc<dyn SuppressionRepository>, Arc<dyn UserRepository>>;
+
+fn render_preview(
+    config: &AppConfig,
+    name: &str,
+    locale: Option<&str>,
+    variables: Option<serde_json::Value>,
+) -> Result<EmailPreviewDto, AppError> {
+    let definition = EmailTemplates::definition(name)?;
+    let variables = variables.unwrap_or_else(|| definition.sample_variables());
+
+    let email = EmailTemplates::from_config(config).render(name, locale, &variables)?;
+    Ok(EmailPreviewDto::new(name, variables, email))
+}
+
+pub async fn list_templates(
+    _admin: RequireAdmin,
+) -> Result<HttpResponse, AppError> {
+    let dtos: Vec<EmailTemplateDto> = EmailTemplates::definitions().iter().map(EmailTemplateDto::from).collect();
+    Ok(HttpResponse::Ok().json(dtos))
+}
+
+pub async fn preview_template_with_samples(
+    _admin: RequireAdmin,
+    config: web::Data<AppConfig>,
+    path: web::Path<String>,
+    query: web::Query<PreviewQueryDto>,
+) -> Result<HttpResponse, AppError> {
+    query.validate().map_err(AppError::ValidationError)?;
+
+    let preview = render_preview(&config, &path, query.locale.as_deref(), None)?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
+pub async fn preview_template(
+    _admin: RequireAdmin,
+    config: web::Data<AppConfig>,
+    path: web::Path<String>,
+    body: web::Json<PreviewEmailDto>,
+) -> Result<HttpResponse, AppError> {
+    body.validate().map_err(AppError::ValidationError)?;
+    let body = body.into_inner();
+
+    let preview = render_preview(&config, &path, body.locale.as_deref(), body.variables)?;
+    Ok(HttpResponse::Ok().json(preview))
+}
+
+pub async fn test_send(
+    admin: RequireAdmin,
+    state: web::Data<AppState>,
+    body: web::Json<TestSendEmailDto>,
+) -> Result<HttpResponse, AppError> {
+    body.validate().map_err(AppError::ValidationError)?;
+    let body = body.into_inner();
+
+    let definition = EmailTemplates::definition(&body.template)?;
+    let variables = body.variables.unwrap_or_else(|| definition.sample_variables());
+
+    let recipient = EmailRecipient {
+        email: body.to.clone(),
+        name: None,
+        locale: body.locale,
+        user_id: None,
+    };
+
+    state.email.send_template(&recipient, &body.template, &variables).await?;
+
+    state.audit.record(
+        AuditEntry::new(AuditAction::TestEmailSent)
+            .actor(admin.0.user_id)
+            .with("template", body.template.as_str())
+            .with("to", body.to.as_str()),
+    ).await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": format!("Test email sent to {}", body.to)})))
+}
+
+pub async fn unsuppress(
+    admin: RequireAdmin,
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let email = path.into_inner();
+    if !state.delivery_events.unsuppress(&email).await? {
+        return Err(AppError::NotFound(format!("{} is not suppressed", email)));
+    }
+
+    state.audit.record(AuditEntry::new(AuditAction::EmailUnsuppressed).actor(admin.0.user_id).with("email", email.as_str())).await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": format!("{} can receive emails again", email)})))
+}
+
+pub async fn resend_webhook(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, AppError> {
+    let secret = state.config.email.resend_webhook_secret.as_ref().map(Secret::expose).ok_or_else(|| {
+        tracing::error!("Received a Resend webhook but RESEND_WEBHOOK_SECRET is not set");
+        AppError::InternalError
+    })?;
+
+    let header = |name: &str| {
+        req.headers().get(name)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| AppError::Unauthorized(format!("Missing {} header", name)))
+    };
+    let headers = SvixHeaders {
+        id: header("svix-id")?,
+        timestamp: header("svix-timestamp")?,
+        signature: header("svix-signature")?,
+    };
+
+    SvixVerifier::new(secret)?.verify(&headers, &body)?;
+    let event = resend_webhook::parse_event(headers.id, &body)?;
+
+    state.delivery_events.record(event).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Webhook processed"})))
+}
@@ -0,0 +1,17 @@
+use actix_web::web;
+use super::handlers::{list_templates, preview_template, preview_template_with_samples, resend_webhook, test_send, unsuppress};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin/emails")
+            .route("/templates", web::get().to(list_templates))
+            .route("/templates/{name}/preview", web::get().to(preview_template_with_samples))
+            .route("/templates/{name}/preview", web::post().to(preview_template))
+            .route("/test-send", web::post().to(test_send))
+            .route("/suppressions/{email}", web::delete().to(unsuppress))
+    );
+    cfg.service(
+        web::scope("/webhooks")
+            .route("/resend", web::post().to(resend_webhook))
+    );
+}
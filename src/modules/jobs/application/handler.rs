@@ -0,0 +1,43 @@
+use std::future::Future;
+use async_trait::async_trait;
+use crate::common::errors::AppError;
+
+/// Runs jobs of one kind. Returning an error schedules a retry with backoff
+/// until the job runs out of attempts, so handlers must be safe to re-run.
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// The `kind` of the jobs this handler runs, e.g. `auth.purge_stale_sessions`.
+    fn kind(&self) -> &'static str;
+    async fn run(&self, payload: serde_json::Value) -> Result<(), AppError>;
+}
+
+/// Adapts a closure returning a future, for handlers that only call into a service.
+pub struct FnJobHandler<F> {
+    kind: &'static str,
+    run: F,
+}
+
+impl<F, Fut> FnJobHandler<F>
+where
+    F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    pub fn new(kind: &'static str, run: F) -> Self {
+        Self { kind, run }
+    }
+}
+
+#[async_trait]
+impl<F, Fut> JobHandler for FnJobHandler<F>
+where
+    F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    async fn run(&self, payload: serde_json::Value) -> Result<(), AppError> {
+        (self.run)(payload).await
+    }
+}
@@ -0,0 +1,4 @@
+pub mod handler;
+pub mod registry;
+pub mod service;
+pub mod worker;
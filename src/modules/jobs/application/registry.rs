@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::handler::JobHandler;
+use crate::modules::jobs::domain::schedule::CronSchedule;
+
+/// A job enqueued whenever its cron schedule comes due. The schedule name
+/// doubles as the job's unique key, so a slow run never overlaps the next.
+#[derive(Debug, Clone)]
+pub struct RecurringJob {
+    pub name: String,
+    pub kind: &'static str,
+    pub schedule: CronSchedule,
+}
+
+/// The job kinds a worker can run and the recurring jobs it keeps enqueueing.
+#[derive(Default)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    recurring: Vec<RecurringJob>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, handler: impl JobHandler + 'static) -> &mut Self {
+        let kind = handler.kind();
+        if self.handlers.insert(kind, Arc::new(handler)).is_some() {
+            tracing::warn!("Job handler for {} registered twice, using the last one", kind);
+        }
+        self
+    }
+
+    /// Runs jobs of `kind` on the cron `expression` (with seconds, in UTC).
+    ///
+    /// Panics on an invalid expression, as schedules are fixed at compile time.
+    pub fn every(&mut self, expression: &str, kind: &'static str) -> &mut Self {
+        let schedule = CronSchedule::parse(expression)
+            .unwrap_or_else(|e| panic!("Invalid cron expression {:?} for {}: {}", expression, kind, e));
+        self.recurring.push(RecurringJob { name: kind.to_string(), kind, schedule });
+        self
+    }
+
+    pub fn handler(&self, kind: &str) -> Option<Arc<dyn JobHandler>> {
+        self.handlers.get(kind).cloned()
+    }
+
+    pub fn recurring(&self) -> &[RecurringJob] {
+        &self.recurring
+    }
+
+    pub fn find_recurring(&self, name: &str) -> Option<&RecurringJob> {
+        self.recurring.iter().find(|job| job.name == name)
+    }
+}
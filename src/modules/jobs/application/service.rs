@@ -0,0 +1,43 @@
+use serde::Serialize;
+use crate::common::errors::AppError;
+use crate::modules::jobs::domain::{
+    entity::{Job, NewJob},
+    repository::JobRepository,
+};
+use tracing::instrument;
+
+/// Deferred work for other modules: enqueue a job here and a worker runs the
+/// handler registered for its kind.
+pub struct JobQueue<J: JobRepository> {
+    job_repo: J,
+}
+
+impl<J: JobRepository> JobQueue<J> {
+    pub fn new(job_repo: J) -> Self {
+        Self { job_repo }
+    }
+
+    /// Queues a job of `kind` to run as soon as a worker is free.
+    #[instrument(name = "JobQueue::enqueue", skip_all)]
+    pub async fn enqueue<P: Serialize>(&self, kind: &str, payload: &P) -> Result<Option<Job>, AppError> {
+        self.enqueue_job(NewJob::new(kind, to_value(payload)?)).await
+    }
+
+    /// Queues a fully specified job, e.g. one with a unique key or a later `run_at`.
+    /// Returns `None` if an unfinished job already holds the unique key.
+    #[instrument(name = "JobQueue::enqueue_job", skip_all)]
+    pub async fn enqueue_job(&self, job: NewJob) -> Result<Option<Job>, AppError> {
+        let queued = self.job_repo.enqueue(job).await?;
+        if let Some(job) = &queued {
+            tracing::debug!("Enqueued {} job {} for {}", job.kind, job.id, job.run_at);
+        }
+        Ok(queued)
+    }
+}
+
+fn to_value<P: Serialize>(payload: &P) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(payload).map_err(|e| {
+        tracing::error!("Failed to serialize job payload: {}", e);
+        AppError::InternalError
+    })
+}
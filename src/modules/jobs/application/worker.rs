@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use crate::common::{clock::Clock, errors::AppError};
+use crate::modules::jobs::domain::{
+    entity::{Job, NewJob},
+    repository::JobRepository,
+    schedule::retry_delay,
+};
+use super::registry::JobRegistry;
+
+/// Workers without a heartbeat for this long, or three polls if that's longer,
+/// are assumed dead and lose their running jobs.
+const DEAD_WORKER_TIMEOUT_MINUTES: i64 = 5;
+
+/// Polls the queue and runs up to `concurrency` jobs at a time. Any number of
+/// workers, in-process or in the `worker` binary, can share one database.
+pub struct JobWorker<J: JobRepository> {
+    job_repo: Arc<J>,
+    registry: Arc<JobRegistry>,
+    worker_id: String,
+    concurrency: usize,
+    poll_interval: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<J: JobRepository + Send + Sync + 'static> JobWorker<J> {
+    pub fn new(job_repo: J, registry: JobRegistry, concurrency: usize, poll_interval: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            job_repo: Arc::new(job_repo),
+            registry: Arc::new(registry),
+            worker_id: format!("{}-{}", std::process::id(), &uuid::Uuid::new_v4().simple().to_string()[..8]),
+            concurrency: concurrency.max(1),
+            poll_interval,
+            clock,
+        }
+    }
+
+    /// Runs until the task is dropped.
+    pub async fn run(self) {
+        tracing::info!("Job worker {} started with concurrency {}", self.worker_id, self.concurrency);
+
+        if let Err(e) = self.register_schedules().await {
+            tracing::error!("Failed to register job schedules: {:?}", e);
+        }
+
+        let slots = Arc::new(Semaphore::new(self.concurrency));
+        let mut interval = tokio::time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.job_repo.heartbeat(&self.worker_id, self.clock.now()).await {
+                tracing::error!("Job worker {} failed to record its heartbeat: {:?}", self.worker_id, e);
+            }
+            if let Err(e) = self.poll(&slots).await {
+                tracing::error!("Job worker {} failed to poll: {:?}", self.worker_id, e);
+            }
+        }
+    }
+
+    async fn register_schedules(&self) -> Result<(), AppError> {
+        let now = self.clock.now();
+        for job in self.registry.recurring() {
+            let Some(next_run_at) = job.schedule.next_after(now) else { continue };
+            self.job_repo.register_schedule(&job.name, job.schedule.expression(), next_run_at).await?;
+        }
+        Ok(())
+    }
+
+    async fn poll(&self, slots: &Arc<Semaphore>) -> Result<(), AppError> {
+        self.enqueue_due_schedules().await?;
+
+        let timeout = chrono::Duration::from_std(self.poll_interval * 3)
+            .unwrap_or_default()
+            .max(chrono::Duration::minutes(DEAD_WORKER_TIMEOUT_MINUTES));
+        let (requeued, failed) = self.job_repo.release_stale(self.clock.now() - timeout).await?;
+        if requeued + failed > 0 {
+            tracing::warn!("Took {} jobs from dead workers: {} requeued, {} out of attempts", requeued + failed, requeued, failed);
+        }
+
+        let free = slots.available_permits();
+        if free == 0 {
+            return Ok(());
+        }
+
+        let jobs = self.job_repo.claim(&self.worker_id, free as i64).await?;
+        for job in jobs {
+            let Ok(permit) = slots.clone().acquire_owned().await else { break };
+            let (job_repo, registry, clock) = (self.job_repo.clone(), self.registry.clone(), self.clock.clone());
+            tokio::spawn(async move {
+                execute(job_repo, registry, clock, job).await;
+                drop(permit);
+            });
+        }
+        Ok(())
+    }
+
+    async fn enqueue_due_schedules(&self) -> Result<(), AppError> {
+        let now = self.clock.now();
+        let due = self.job_repo.find_due_schedules(now).await?;
+
+        for schedule in due {
+            // Schedules removed from the code stay in the table until deleted by hand
+            let Some(recurring) = self.registry.find_recurring(&schedule.name) else { continue };
+            // Missed runs (e.g. while no worker was up) collapse into this one
+            let Some(next_run_at) = recurring.schedule.next_after(now) else { continue };
+            let job = NewJob::new(recurring.kind, serde_json::json!({})).unique_key(schedule.name.clone());
+
+            if self.job_repo.advance_schedule(&schedule, next_run_at, job).await? {
+                tracing::debug!("Enqueued recurring job {}", recurring.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn execute<J: JobRepository + Send + Sync + 'static>(job_repo: Arc<J>, registry: Arc<JobRegistry>, clock: Arc<dyn Clock>, job: Job) {
+    let outcome = match registry.handler(&job.kind) {
+        // Spawned so a panicking handler counts as a failed attempt
+        Some(handler) => {
+            let payload = job.payload.clone();
+            match tokio::spawn(async move { handler.run(payload).await }).await {
+                Ok(result) => result.map_err(|e| format!("{:?}", e)),
+                Err(e) => Err(format!("Handler panicked: {}", e)),
+            }
+        }
+        None => Err(format!("No handler registered for job kind {}", job.kind)),
+    };
+
+    let result = match outcome {
+        Ok(()) => {
+            tracing::debug!("Job {} ({}) completed", job.id, job.kind);
+            job_repo.complete(job.id).await
+        }
+        Err(error) if job.has_attempts_left() && registry.handler(&job.kind).is_some() => {
+            let run_at = clock.now() + retry_delay(job.attempts);
+            tracing::warn!("Job {} ({}) failed attempt {}, retrying at {}: {}", job.id, job.kind, job.attempts, run_at, error);
+            job_repo.retry(job.id, &error, run_at).await
+        }
+        Err(error) => {
+            tracing::error!("Job {} ({}) failed permanently after {} attempts: {}", job.id, job.kind, job.attempts, error);
+            job_repo.fail(job.id, &error).await
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Failed to record job outcome: {:?}", e);
+    }
+}
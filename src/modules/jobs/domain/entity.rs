@@ -0,0 +1,95 @@
+use chrono::NaiveDateTime;
+use diesel::{Queryable, Selectable, Insertable, Identifiable};
+use serde::Serialize;
+use strum::{Display, EnumString};
+use uuid::Uuid;
+use crate::schema::{job_schedules, jobs};
+
+/// Failed jobs are retried until they have been attempted this many times.
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for `run_at` or a free worker. Also covers jobs waiting to be retried.
+    Pending,
+    /// Claimed by the worker in `locked_by`.
+    Running,
+    Completed,
+    /// Gave up after `max_attempts`, or no handler knows the job's kind.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = jobs)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub unique_key: Option<String>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: NaiveDateTime,
+    pub locked_at: Option<NaiveDateTime>,
+    pub locked_by: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl Job {
+    pub fn has_attempts_left(&self) -> bool {
+        self.attempts < self.max_attempts
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// While a pending or running job holds this key, enqueueing another one with it is a no-op.
+    pub unique_key: Option<String>,
+    pub max_attempts: i32,
+    pub run_at: NaiveDateTime,
+}
+
+impl NewJob {
+    /// A job of `kind` that may run right away.
+    pub fn new(kind: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self {
+            kind: kind.into(),
+            payload,
+            unique_key: None,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            run_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn unique_key(mut self, key: impl Into<String>) -> Self {
+        self.unique_key = Some(key.into());
+        self
+    }
+
+    pub fn run_at(mut self, run_at: NaiveDateTime) -> Self {
+        self.run_at = run_at;
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+/// When a recurring job is next due. Shared by all workers so each run is enqueued once.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Insertable)]
+#[diesel(table_name = job_schedules)]
+#[diesel(primary_key(name))]
+pub struct JobSchedule {
+    pub name: String,
+    pub cron: String,
+    pub next_run_at: NaiveDateTime,
+    pub last_run_at: Option<NaiveDateTime>,
+}
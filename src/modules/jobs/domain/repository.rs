@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+use super::entity::{Job, JobSchedule, NewJob};
+use crate::common::errors::AppError;
+
+#[async_trait]
+pub trait JobRepository: Send + Sync {
+    /// Queues the job. Returns `None` if an unfinished job already holds its unique key.
+    async fn enqueue(&self, job: NewJob) -> Result<Option<Job>, AppError>;
+    /// Locks up to `limit` due pending jobs for `worker_id`, skipping rows other workers hold,
+    /// and marks them running with one more attempt.
+    async fn claim(&self, worker_id: &str, limit: i64) -> Result<Vec<Job>, AppError>;
+    async fn complete(&self, id: Uuid) -> Result<(), AppError>;
+    /// Puts a failed job back in the queue until `run_at`.
+    async fn retry(&self, id: Uuid, error: &str, run_at: NaiveDateTime) -> Result<(), AppError>;
+    /// Gives up on a job for good.
+    async fn fail(&self, id: Uuid, error: &str) -> Result<(), AppError>;
+    /// Takes running jobs away from workers without a heartbeat since `seen_before`,
+    /// e.g. ones that crashed. The lost run counts as an attempt: jobs with attempts
+    /// left are requeued, the others fail. Returns how many were `(requeued, failed)`.
+    async fn release_stale(&self, seen_before: NaiveDateTime) -> Result<(usize, usize), AppError>;
+    /// Deletes completed and failed jobs that finished before `before`. Returns how many.
+    async fn delete_finished(&self, before: NaiveDateTime) -> Result<usize, AppError>;
+
+    /// Creates the schedule, or resets its next run if the cron expression changed.
+    async fn register_schedule(&self, name: &str, cron: &str, next_run_at: NaiveDateTime) -> Result<(), AppError>;
+    async fn find_due_schedules(&self, now: NaiveDateTime) -> Result<Vec<JobSchedule>, AppError>;
+    /// Moves the schedule on to `next_run_at` and enqueues `job` in one transaction, unless
+    /// another worker already did so for this run. Returns whether this call won.
+    async fn advance_schedule(&self, schedule: &JobSchedule, next_run_at: NaiveDateTime, job: NewJob) -> Result<bool, AppError>;
+
+    /// Records that `worker_id` is alive at `at`.
+    async fn heartbeat(&self, worker_id: &str, at: NaiveDateTime) -> Result<(), AppError>;
+    /// When any worker last recorded a heartbeat.
+    async fn last_heartbeat(&self) -> Result<Option<NaiveDateTime>, AppError>;
+    /// Forgets workers not seen since `before`. Returns how many.
+    async fn delete_stale_workers(&self, before: NaiveDateTime) -> Result<usize, AppError>;
+}
@@ -0,0 +1,65 @@
+use std::str::FromStr;
+use chrono::{Duration, NaiveDateTime};
+
+const BASE_RETRY_DELAY_SECS: i64 = 30;
+const MAX_RETRY_DELAY_SECS: i64 = 60 * 60;
+
+/// How long to wait before retrying a job that failed its `attempt`th run:
+/// 30s, 1m, 2m, 4m, ... capped at one hour.
+pub fn retry_delay(attempt: i32) -> Duration {
+    let exponent = attempt.saturating_sub(1).clamp(0, 20) as u32;
+    Duration::seconds((BASE_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS))
+}
+
+/// A cron expression with a seconds field, e.g. `0 */15 * * * *` for every
+/// quarter hour. Times are UTC.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    expression: String,
+    schedule: cron::Schedule,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, cron::error::Error> {
+        Ok(Self {
+            expression: expression.to_string(),
+            schedule: cron::Schedule::from_str(expression)?,
+        })
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The first time strictly after `after` that matches the expression.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.schedule.after(&after.and_utc()).next().map(|next| next.naive_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(10), Duration::hours(1));
+        assert_eq!(retry_delay(1000), Duration::hours(1));
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let hourly = CronSchedule::parse("0 30 * * * *").unwrap();
+        assert_eq!(hourly.next_after(at("2026-10-19 09:10:00")), Some(at("2026-10-19 09:30:00")));
+        assert_eq!(hourly.next_after(at("2026-10-19 09:30:00")), Some(at("2026-10-19 10:30:00")));
+
+        assert!(CronSchedule::parse("every hour").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::common::{database::{self, DbHandle}, errors::AppError};
+use crate::modules::jobs::domain::{
+    entity::{Job, JobSchedule, JobStatus, NewJob},
+    repository::JobRepository,
+};
+use crate::schema::{job_schedules, job_workers, jobs};
+use tracing::instrument;
+
+pub struct DieselJobRepository {
+    db: DbHandle,
+}
+
+impl DieselJobRepository {
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
+    }
+}
+
+#[async_trait]
+impl JobRepository for DieselJobRepository {
+    #[instrument(name = "JobRepository::enqueue", skip_all)]
+    async fn enqueue(&self, job: NewJob) -> Result<Option<Job>, AppError> {
+        database::run(&self.db, move |conn| {
+            // The only unique index a new job can hit is the partial one on unique_key
+            diesel::insert_into(jobs::table)
+                .values(&job)
+                .on_conflict_do_nothing()
+                .get_result(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::claim", skip_all)]
+    async fn claim(&self, worker_id: &str, limit: i64) -> Result<Vec<Job>, AppError> {
+        let worker_id = worker_id.to_string();
+        database::run(&self.db, move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let ids: Vec<Uuid> = jobs::table
+                    .select(jobs::id)
+                    .filter(jobs::status.eq(JobStatus::Pending.to_string()))
+                    .filter(jobs::run_at.le(diesel::dsl::now))
+                    .order(jobs::run_at.asc())
+                    .limit(limit)
+                    .for_update()
+                    .skip_locked()
+                    .load(conn)?;
+
+                if ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                diesel::update(jobs::table.filter(jobs::id.eq_any(&ids)))
+                    .set((
+                        jobs::status.eq(JobStatus::Running.to_string()),
+                        jobs::attempts.eq(jobs::attempts + 1),
+                        jobs::locked_at.eq(diesel::dsl::now.nullable()),
+                        jobs::locked_by.eq(worker_id),
+                    ))
+                    .get_results(conn)
+            })
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::complete", skip_all)]
+    async fn complete(&self, id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(jobs::table.find(id))
+                .set((
+                    jobs::status.eq(JobStatus::Completed.to_string()),
+                    jobs::locked_at.eq(None::<NaiveDateTime>),
+                    jobs::locked_by.eq(None::<String>),
+                    jobs::finished_at.eq(diesel::dsl::now.nullable()),
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::retry", skip_all)]
+    async fn retry(&self, id: Uuid, error: &str, run_at: NaiveDateTime) -> Result<(), AppError> {
+        let error = error.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::update(jobs::table.find(id))
+                .set((
+                    jobs::status.eq(JobStatus::Pending.to_string()),
+                    jobs::run_at.eq(run_at),
+                    jobs::last_error.eq(error),
+                    jobs::locked_at.eq(None::<NaiveDateTime>),
+                    jobs::locked_by.eq(None::<String>),
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::fail", skip_all)]
+    async fn fail(&self, id: Uuid, error: &str) -> Result<(), AppError> {
+        let error = error.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::update(jobs::table.find(id))
+                .set((
+                    jobs::status.eq(JobStatus::Failed.to_string()),
+                    jobs::last_error.eq(error),
+                    jobs::locked_at.eq(None::<NaiveDateTime>),
+                    jobs::locked_by.eq(None::<String>),
+                    jobs::finished_at.eq(diesel::dsl::now.nullable()),
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::release_stale", skip_all)]
+    async fn release_stale(&self, seen_before: NaiveDateTime) -> Result<(usize, usize), AppError> {
+        database::run(&self.db, move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let alive = job_workers::table
+                    .select(job_workers::id.nullable())
+                    .filter(job_workers::last_seen_at.ge(seen_before));
+                let abandoned = || jobs::table
+                    .filter(jobs::status.eq(JobStatus::Running.to_string()))
+                    .filter(jobs::locked_by.ne_all(alive));
+
+                // `claim` already counted the lost run as an attempt
+                let failed = diesel::update(abandoned().filter(jobs::attempts.ge(jobs::max_attempts)))
+                    .set((
+                        jobs::status.eq(JobStatus::Failed.to_string()),
+                        jobs::last_error.eq("Worker stopped responding"),
+                        jobs::locked_at.eq(None::<NaiveDateTime>),
+                        jobs::locked_by.eq(None::<String>),
+                        jobs::finished_at.eq(diesel::dsl::now.nullable()),
+                    ))
+                    .execute(conn)?;
+                let requeued = diesel::update(abandoned())
+                    .set((
+                        jobs::status.eq(JobStatus::Pending.to_string()),
+                        jobs::last_error.eq("Worker stopped responding"),
+                        jobs::locked_at.eq(None::<NaiveDateTime>),
+                        jobs::locked_by.eq(None::<String>),
+                    ))
+                    .execute(conn)?;
+                Ok((requeued, failed))
+            })
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::delete_finished", skip_all)]
+    async fn delete_finished(&self, before: NaiveDateTime) -> Result<usize, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::delete(
+                jobs::table
+                    .filter(jobs::status.eq_any([JobStatus::Completed.to_string(), JobStatus::Failed.to_string()]))
+                    .filter(jobs::finished_at.lt(before)),
+            )
+            .execute(conn)
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::register_schedule", skip_all)]
+    async fn register_schedule(&self, name: &str, cron: &str, next_run_at: NaiveDateTime) -> Result<(), AppError> {
+        let name = name.to_string();
+        let cron = cron.to_string();
+        database::run(&self.db, move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                diesel::insert_into(job_schedules::table)
+                    .values(JobSchedule {
+                        name: name.clone(),
+                        cron: cron.clone(),
+                        next_run_at,
+                        last_run_at: None,
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+
+                // Keep the stored next run unless the expression changed
+                diesel::update(job_schedules::table.find(&name).filter(job_schedules::cron.ne(&cron)))
+                    .set((job_schedules::cron.eq(&cron), job_schedules::next_run_at.eq(next_run_at)))
+                    .execute(conn)?;
+                Ok(())
+            })
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::find_due_schedules", skip_all)]
+    async fn find_due_schedules(&self, now: NaiveDateTime) -> Result<Vec<JobSchedule>, AppError> {
+        database::run(&self.db, move |conn| {
+            job_schedules::table
+                .filter(job_schedules::next_run_at.le(now))
+                .load::<JobSchedule>(conn)
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::advance_schedule", skip_all)]
+    async fn advance_schedule(&self, schedule: &JobSchedule, next_run_at: NaiveDateTime, job: NewJob) -> Result<bool, AppError> {
+        let schedule = schedule.clone();
+        database::run(&self.db, move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                // Only the worker that still sees the old next_run_at gets to enqueue this run
+                let advanced = diesel::update(
+                    job_schedules::table
+                        .find(&schedule.name)
+                        .filter(job_schedules::next_run_at.eq(schedule.next_run_at)),
+                )
+                .set((
+                    job_schedules::next_run_at.eq(next_run_at),
+                    job_schedules::last_run_at.eq(diesel::dsl::now.nullable()),
+                ))
+                .execute(conn)?;
+
+                if advanced == 0 {
+                    return Ok(false);
+                }
+
+                diesel::insert_into(jobs::table)
+                    .values(&job)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+                Ok(true)
+            })
+            .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::heartbeat", skip_all)]
+    async fn heartbeat(&self, worker_id: &str, at: NaiveDateTime) -> Result<(), AppError> {
+        let worker_id = worker_id.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(job_workers::table)
+                .values((job_workers::id.eq(&worker_id), job_workers::started_at.eq(at), job_workers::last_seen_at.eq(at)))
+                .on_conflict(job_workers::id)
+                .do_update()
+                .set(job_workers::last_seen_at.eq(at))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::last_heartbeat", skip_all)]
+    async fn last_heartbeat(&self) -> Result<Option<NaiveDateTime>, AppError> {
+        database::run(&self.db, move |conn| {
+            job_workers::table
+                .select(diesel::dsl::max(job_workers::last_seen_at))
+                .first(conn)
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "JobRepository::delete_stale_workers", skip_all)]
+    async fn delete_stale_workers(&self, before: NaiveDateTime) -> Result<usize, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::delete(job_workers::table.filter(job_workers::last_seen_at.lt(before)))
+                .execute(conn)
+                .map_err(AppError::from)
+        }).await
+    }
+}
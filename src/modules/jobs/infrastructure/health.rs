@@ -0,0 +1,25 @@
+use std::sync::Arc;
+use crate::common::{clock::Clock, config::AppConfig, database::DbPool, health::{FnHealthCheck, HealthRegistry}};
+use crate::modules::jobs::domain::repository::JobRepository;
+use super::diesel_repository::DieselJobRepository;
+
+/// Passes while some worker, in this process or the `worker` binary, has
+/// polled the queue recently.
+pub fn register(registry: &mut HealthRegistry, pool: &DbPool, config: &AppConfig, clock: Arc<dyn Clock>) {
+    // A few missed polls are tolerated before the queue counts as unattended
+    let max_age = chrono::Duration::seconds((config.jobs.poll_interval_secs as i64 * 3).max(30));
+    let pool = pool.clone();
+    registry.register(FnHealthCheck::new("job_worker", move || {
+        let (job_repo, clock) = (DieselJobRepository::new(pool.clone()), clock.clone());
+        async move {
+            let last_seen = job_repo.last_heartbeat().await
+                .map_err(|e| e.to_string())?
+                .ok_or("no job worker has reported in")?;
+            let age = clock.now() - last_seen;
+            if age > max_age {
+                return Err(format!("last heartbeat {}s ago", age.num_seconds()));
+            }
+            Ok(Some(format!("last heartbeat {}s ago", age.num_seconds())))
+        }
+    }));
+}
@@ -0,0 +1,3 @@
+pub mod diesel_repository;
+pub mod health;
+pub mod worker;
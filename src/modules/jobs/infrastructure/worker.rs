@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::common::{clock::Clock, database::DbPool, live_config::LiveConfig};
+use crate::modules::jobs::application::{handler::FnJobHandler, registry::JobRegistry, worker::JobWorker};
+use crate::modules::jobs::domain::repository::JobRepository;
+use super::diesel_repository::DieselJobRepository;
+
+pub const PURGE_FINISHED_JOBS: &str = "jobs.purge_finished";
+
+/// Every job kind the application knows about. Modules add their handlers and
+/// schedules here. Handlers read `config` on every run, so they see reloads.
+pub fn registry(pool: &DbPool, config: &LiveConfig, clock: &Arc<dyn Clock>) -> JobRegistry {
+    let mut registry = JobRegistry::new();
+    crate::modules::auth::infrastructure::jobs::register(&mut registry, pool, config, clock);
+
+    let (jobs_pool, jobs_config, jobs_clock) = (pool.clone(), config.clone(), clock.clone());
+    registry
+        .register(FnJobHandler::new(PURGE_FINISHED_JOBS, move |_| {
+            let job_repo = DieselJobRepository::new(jobs_pool.clone());
+            let cutoff = jobs_clock.now() - chrono::Duration::days(jobs_config.get().jobs.retention_days);
+            async move {
+                let deleted = job_repo.delete_finished(cutoff).await?;
+                if deleted > 0 {
+                    tracing::info!("Deleted {} finished jobs", deleted);
+                }
+                job_repo.delete_stale_workers(cutoff).await?;
+                Ok(())
+            }
+        }))
+        .every("0 15 3 * * *", PURGE_FINISHED_JOBS);
+
+    registry
+}
+
+/// Concurrency and the poll interval are fixed when the worker starts.
+pub fn worker(pool: &DbPool, config: &LiveConfig, clock: Arc<dyn Clock>) -> JobWorker<DieselJobRepository> {
+    let jobs = config.get().jobs.clone();
+    JobWorker::new(
+        DieselJobRepository::new(pool.clone()),
+        registry(pool, config, &clock),
+        jobs.concurrency,
+        Duration::from_secs(jobs.poll_interval_secs),
+        clock,
+    )
+}
+
+/// Runs a worker on the current runtime alongside the HTTP server.
+pub fn spawn(pool: DbPool, config: LiveConfig, clock: Arc<dyn Clock>) {
+    tokio::spawn(worker(&pool, &config, clock).run());
+}
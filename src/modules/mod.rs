@@ -2,3 +2,5 @@ pub mod auth;
 pub mod users;
 pub mod posts;
 pub mod email;
+pub mod jobs;
+pub mod audit;
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 use crate::modules::posts::domain::{entity::{Post, NewPost}, repository::PostRepository};
 use crate::common::errors::AppError;
+use tracing::instrument;
 
 pub struct PostService<R: PostRepository> {
     repo: R,
@@ -11,43 +12,86 @@ impl<R: PostRepository> PostService<R> {
         Self { repo }
     }
 
-    pub fn create_post(&self, title: String, content: String, author_id: Uuid) -> Result<Post, AppError> {
+    #[instrument(name = "PostService::create_post", skip_all, fields(%author_id))]
+    pub async fn create_post(&self, title: String, content: String, author_id: Uuid) -> Result<Post, AppError> {
         let new_post = NewPost {
             title,
             content,
             author_id,
         };
-        self.repo.create(new_post)
+        self.repo.create(new_post).await
     }
 
-    pub fn get_post(&self, id: Uuid) -> Result<Post, AppError> {
-        self.repo.find_by_id(id)?
+    #[instrument(name = "PostService::get_post", skip_all, fields(%id))]
+    pub async fn get_post(&self, id: Uuid) -> Result<Post, AppError> {
+        self.repo.find_by_id(id).await?
             .ok_or_else(|| AppError::NotFound(format!("Post with id {} not found", id)))
     }
 
-    pub fn list_posts(&self, page: i64, per_page: i64) -> Result<Vec<Post>, AppError> {
+    #[instrument(name = "PostService::list_posts", skip_all)]
+    pub async fn list_posts(&self, page: i64, per_page: i64) -> Result<Vec<Post>, AppError> {
         let limit = if per_page > 0 { per_page } else { 10 };
         let offset = if page > 0 { (page - 1) * limit } else { 0 };
-        self.repo.find_all(limit, offset)
+        self.repo.find_all(limit, offset).await
     }
 
-    pub fn update_post(&self, id: Uuid, title: String, content: String, is_published: bool, user_id: Uuid, is_admin: bool) -> Result<Post, AppError> {
-        let post = self.get_post(id)?;
+    #[instrument(name = "PostService::update_post", skip_all, fields(%id, %user_id))]
+    pub async fn update_post(&self, id: Uuid, title: String, content: String, is_published: bool, user_id: Uuid, is_admin: bool) -> Result<Post, AppError> {
+        let post = self.get_post(id).await?;
         
         if post.author_id != user_id && !is_admin {
             return Err(AppError::Forbidden("You do not have permission to update this post".to_string()));
         }
 
-        self.repo.update(id, title, content, is_published)
+        self.repo.update(id, title, content, is_published).await
     }
 
-    pub fn delete_post(&self, id: Uuid, user_id: Uuid, is_admin: bool) -> Result<(), AppError> {
-        let post = self.get_post(id)?;
+    #[instrument(name = "PostService::delete_post", skip_all, fields(%id, %user_id))]
+    pub async fn delete_post(&self, id: Uuid, user_id: Uuid, is_admin: bool) -> Result<(), AppError> {
+        let post = self.get_post(id).await?;
         
         if post.author_id != user_id && !is_admin {
             return Err(AppError::Forbidden("You do not have permission to delete this post".to_string()));
         }
 
-        self.repo.delete(id)
+        self.repo.delete(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::posts::infrastructure::memory_repository::InMemoryPostRepository;
+
+    #[tokio::test]
+    async fn test_only_author_or_admin_can_change_a_post() {
+        let service = PostService::new(InMemoryPostRepository::new());
+        let (author, stranger, admin) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let post = service.create_post("Title".to_string(), "Body".to_string(), author).await.unwrap();
+
+        let result = service.update_post(post.id, "Hijacked".to_string(), "Body".to_string(), true, stranger, false).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        let result = service.delete_post(post.id, stranger, false).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+
+        let updated = service.update_post(post.id, "Edited".to_string(), "Body".to_string(), true, author, false).await.unwrap();
+        assert_eq!(updated.title, "Edited");
+        assert!(updated.is_published);
+
+        service.delete_post(post.id, admin, true).await.unwrap();
+        assert!(matches!(service.get_post(post.id).await, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_posts_paginates() {
+        let service = PostService::new(InMemoryPostRepository::new());
+        let author = Uuid::new_v4();
+        for i in 0..3 {
+            service.create_post(format!("Post {}", i), "Body".to_string(), author).await.unwrap();
+        }
+
+        assert_eq!(service.list_posts(1, 2).await.unwrap().len(), 2);
+        assert_eq!(service.list_posts(2, 2).await.unwrap().len(), 1);
+        assert_eq!(service.list_posts(0, 0).await.unwrap().len(), 3);
     }
 }
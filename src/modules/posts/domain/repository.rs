@@ -1,12 +1,38 @@
+use async_trait::async_trait;
 use uuid::Uuid;
 use crate::common::errors::AppError;
 use super::entity::{Post, NewPost};
 
-pub trait PostRepository {
-    fn create(&self, new_post: NewPost) -> Result<Post, AppError>;
-    fn find_by_id(&self, id: Uuid) -> Result<Option<Post>, AppError>;
-    fn find_all(&self, limit: i64, offset: i64) -> Result<Vec<Post>, AppError>;
-    fn update(&self, id: Uuid, title: String, content: String, is_published: bool) -> Result<Post, AppError>;
-    fn delete(&self, id: Uuid) -> Result<(), AppError>;
+#[async_trait]
+pub trait PostRepository: Send + Sync {
+    async fn create(&self, new_post: NewPost) -> Result<Post, AppError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Post>, AppError>;
+    async fn find_all(&self, limit: i64, offset: i64) -> Result<Vec<Post>, AppError>;
+    async fn update(&self, id: Uuid, title: String, content: String, is_published: bool) -> Result<Post, AppError>;
+    async fn delete(&self, id: Uuid) -> Result<(), AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn PostRepository>`.
+#[async_trait]
+impl<T: PostRepository + ?Sized> PostRepository for std::sync::Arc<T> {
+    async fn create(&self, new_post: NewPost) -> Result<Post, AppError> {
+        (**self).create(new_post).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Post>, AppError> {
+        (**self).find_by_id(id).await
+    }
+
+    async fn find_all(&self, limit: i64, offset: i64) -> Result<Vec<Post>, AppError> {
+        (**self).find_all(limit, offset).await
+    }
+
+    async fn update(&self, id: Uuid, title: String, content: String, is_published: bool) -> Result<Post, AppError> {
+        (**self).update(id, title, content, is_published).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), AppError> {
+        (**self).delete(id).await
+    }
 }
 
@@ -0,0 +1,62 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::common::errors::AppError;
+use crate::modules::posts::domain::{entity::{Post, NewPost}, repository::PostRepository};
+
+/// Keeps posts in memory for tests. Clones share the same data.
+#[derive(Clone, Default)]
+pub struct InMemoryPostRepository {
+    posts: Arc<Mutex<Vec<Post>>>,
+}
+
+impl InMemoryPostRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PostRepository for InMemoryPostRepository {
+    async fn create(&self, new_post: NewPost) -> Result<Post, AppError> {
+        let now = Utc::now().naive_utc();
+        let post = Post {
+            id: Uuid::new_v4(),
+            title: new_post.title,
+            content: new_post.content,
+            is_published: false,
+            author_id: new_post.author_id,
+            created_at: now,
+            updated_at: now,
+        };
+        self.posts.lock().unwrap().push(post.clone());
+        Ok(post)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Post>, AppError> {
+        Ok(self.posts.lock().unwrap().iter().find(|p| p.id == id).cloned())
+    }
+
+    async fn find_all(&self, limit: i64, offset: i64) -> Result<Vec<Post>, AppError> {
+        let mut posts = self.posts.lock().unwrap().clone();
+        posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+        Ok(posts.into_iter().skip(offset.max(0) as usize).take(limit.max(0) as usize).collect())
+    }
+
+    async fn update(&self, id: Uuid, title: String, content: String, is_published: bool) -> Result<Post, AppError> {
+        let mut posts = self.posts.lock().unwrap();
+        // Same error as Diesel's `get_result` on a missing row
+        let post = posts.iter_mut().find(|p| p.id == id).ok_or(diesel::result::Error::NotFound)?;
+        post.title = title;
+        post.content = content;
+        post.is_published = is_published;
+        post.updated_at = Utc::now().naive_utc();
+        Ok(post.clone())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), AppError> {
+        self.posts.lock().unwrap().retain(|p| p.id != id);
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+use crate::common::{clock::Clock, errors::AppError, signed_token::TokenSigner};
+use crate::modules::users::domain::{
+    entity::notification::{NotificationCategory, NewNotificationPreference, UnsubscribeRequest, UNSUBSCRIBE_TOKEN_PURPOSE},
+    repository::notification::NotificationPreferenceRepository,
+};
+use tracing::instrument;
+
+pub struct NotificationPreferenceService<R: NotificationPreferenceRepository> {
+    repo: R,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: NotificationPreferenceRepository> NotificationPreferenceService<R> {
+    pub fn new(repo: R, clock: Arc<dyn Clock>) -> Self {
+        Self { repo, clock }
+    }
+
+    /// Returns every category users can opt out of with its effective state.
+    #[instrument(name = "NotificationPreferenceService::get_preferences", skip_all, fields(%user_id))]
+    pub async fn get_preferences(&self, user_id: Uuid) -> Result<Vec<(NotificationCategory, bool)>, AppError> {
+        let stored = self.repo.find_by_user(user_id).await?;
+
+        Ok(NotificationCategory::iter()
+            .filter(|category| !category.is_transactional())
+            .map(|category| {
+                let choice = stored.iter()
+                    .find(|p| p.category == category.to_string())
+                    .map(|p| p.enabled);
+                (category, category.is_enabled(choice))
+            })
+            .collect())
+    }
+
+    #[instrument(name = "NotificationPreferenceService::update_preferences", skip_all, fields(%user_id))]
+    pub async fn update_preferences(&self, user_id: Uuid, changes: Vec<(NotificationCategory, bool)>) -> Result<Vec<(NotificationCategory, bool)>, AppError> {
+        if let Some((category, _)) = changes.iter().find(|(c, _)| c.is_transactional()) {
+            return Err(AppError::BadRequest(format!("'{}' notifications cannot be changed", category)));
+        }
+
+        for (category, enabled) in changes {
+            self.repo.upsert(NewNotificationPreference {
+                user_id,
+                category: category.to_string(),
+                enabled,
+            }).await?;
+        }
+
+        self.get_preferences(user_id).await
+    }
+
+    /// Applies a signed one-click unsubscribe link.
+    #[instrument(name = "NotificationPreferenceService::unsubscribe", skip_all)]
+    pub async fn unsubscribe(&self, signer: &TokenSigner, token: &str) -> Result<UnsubscribeRequest, AppError> {
+        let data = signer.verify(UNSUBSCRIBE_TOKEN_PURPOSE, token, self.clock.now())?;
+        let request = UnsubscribeRequest::from_token_data(&data)
+            .filter(|r| !r.category.is_transactional())
+            .ok_or_else(|| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        self.repo.upsert(NewNotificationPreference {
+            user_id: request.user_id,
+            category: request.category.to_string(),
+            enabled: false,
+        }).await?;
+
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::common::clock::FakeClock;
+    use crate::modules::users::{
+        domain::entity::notification::UNSUBSCRIBE_TOKEN_DAYS,
+        infrastructure::memory_notification_repository::InMemoryNotificationPreferenceRepository,
+    };
+
+    #[tokio::test]
+    async fn test_unsubscribe_link_expires() {
+        let clock = FakeClock::frozen();
+        let repo = InMemoryNotificationPreferenceRepository::new();
+        let service = NotificationPreferenceService::new(repo.clone(), Arc::new(clock.clone()));
+        let signer = TokenSigner::new("secret");
+        let user_id = Uuid::new_v4();
+        let request = UnsubscribeRequest { user_id, category: NotificationCategory::SecurityAlerts };
+        let expires_at = clock.now() + Duration::days(UNSUBSCRIBE_TOKEN_DAYS);
+        let token = signer.sign(UNSUBSCRIBE_TOKEN_PURPOSE, &request.to_token_data(), Some(expires_at));
+
+        clock.advance(Duration::days(UNSUBSCRIBE_TOKEN_DAYS) + Duration::seconds(1));
+        assert!(matches!(service.unsubscribe(&signer, &token).await, Err(AppError::Unauthorized(_))));
+        assert!(repo.find(user_id, NotificationCategory::SecurityAlerts).await.unwrap().is_none());
+
+        clock.advance(Duration::seconds(-2));
+        service.unsubscribe(&signer, &token).await.unwrap();
+        assert!(!service.get_preferences(user_id).await.unwrap().contains(&(NotificationCategory::SecurityAlerts, true)));
+    }
+}
@@ -1,50 +1,89 @@
+use std::sync::Arc;
 use uuid::Uuid;
 use crate::modules::users::domain::{entity::User, repository::UserRepository};
+use crate::modules::audit::domain::{entity::{AuditAction, AuditEntry}, service::AuditLog};
 use crate::common::errors::AppError;
+use tracing::instrument;
 
 
 pub struct UserService<R: UserRepository> {
     user_repo: R,
+    audit: Arc<dyn AuditLog>,
 }
 
 impl<R: UserRepository> UserService<R> {
-    pub fn new(user_repo: R) -> Self {
-        Self { user_repo }
+    pub fn new(user_repo: R, audit: Arc<dyn AuditLog>) -> Self {
+        Self { user_repo, audit }
     }
 
-    pub fn find_user_by_id(&self, id: Uuid) -> Result<User, AppError> {
-        self.user_repo.find_by_id(id)?
+    #[instrument(name = "UserService::find_user_by_id", skip_all, fields(%id))]
+    pub async fn find_user_by_id(&self, id: Uuid) -> Result<User, AppError> {
+        self.user_repo.find_by_id(id).await?
             .ok_or_else(|| AppError::NotFound(format!("User with id {} not found", id)))
     }
 
-    #[allow(dead_code)]
-    pub fn find_user_by_email(&self, email: &str) -> Result<User, AppError> {
-        self.user_repo.find_by_email(email)?
+    #[instrument(name = "UserService::find_user_by_email", skip_all)]
+    pub async fn find_user_by_email(&self, email: &str) -> Result<User, AppError> {
+        self.user_repo.find_by_email(email).await?
             .ok_or_else(|| AppError::NotFound(format!("User with email {} not found", email)))
     }
 
-    pub fn assign_role(&self, user_id: Uuid, role: &str) -> Result<(), AppError> {
-        let _ = self.user_repo.find_by_id(user_id)?
+    #[instrument(name = "UserService::list_users", skip_all)]
+    pub async fn list_users(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError> {
+        self.user_repo.list(limit, offset).await
+    }
+
+    #[instrument(name = "UserService::get_roles", skip_all, fields(%user_id))]
+    pub async fn get_roles(&self, user_id: Uuid) -> Result<Vec<String>, AppError> {
+        self.user_repo.get_roles(user_id).await
+    }
+
+    /// Marks the user's email as verified without a verification link.
+    /// `actor_id` is the admin doing it, `None` for the admin CLI.
+    #[instrument(name = "UserService::verify_user", skip_all, fields(%user_id))]
+    pub async fn verify_user(&self, user_id: Uuid, actor_id: Option<Uuid>) -> Result<(), AppError> {
+        let user = self.find_user_by_id(user_id).await?;
+        if user.is_verified {
+            return Err(AppError::Conflict("Email already verified".to_string()));
+        }
+        self.user_repo.verify_user(user.id).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::UserVerified).actor(actor_id).target(user.id)).await;
+        Ok(())
+    }
+
+    /// `actor_id` is the admin granting the role, `None` for the admin CLI.
+    #[instrument(name = "UserService::assign_role", skip_all, fields(%user_id))]
+    pub async fn assign_role(&self, user_id: Uuid, role: &str, actor_id: Option<Uuid>) -> Result<(), AppError> {
+        let _ = self.user_repo.find_by_id(user_id).await?
             .ok_or_else(|| AppError::NotFound(format!("User with id {} not found", user_id)))?;
 
-        let roles = self.user_repo.get_roles(user_id)?;
+        let roles = self.user_repo.get_roles(user_id).await?;
         if roles.contains(&role.to_string()) {
             return Err(AppError::Conflict(format!("User already has role '{}'", role)));
         }
 
-        self.user_repo.add_role(user_id, role)
+        self.user_repo.add_role(user_id, role).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::RoleAssigned).actor(actor_id).target(user_id).with("role", role)).await;
+        Ok(())
     }
 
-    pub fn remove_role(&self, user_id: Uuid, role: &str) -> Result<(), AppError> {
+    /// `actor_id` is the admin revoking the role, `None` for the admin CLI.
+    #[instrument(name = "UserService::remove_role", skip_all, fields(%user_id))]
+    pub async fn remove_role(&self, user_id: Uuid, role: &str, actor_id: Option<Uuid>) -> Result<(), AppError> {
 
-        let _ = self.user_repo.find_by_id(user_id)?
+        let _ = self.user_repo.find_by_id(user_id).await?
             .ok_or_else(|| AppError::NotFound(format!("User with id {} not found", user_id)))?;
 
-        let roles = self.user_repo.get_roles(user_id)?;
+        let roles = self.user_repo.get_roles(user_id).await?;
         if !roles.contains(&role.to_string()) {
             return Err(AppError::Conflict(format!("User does not have role '{}'", role)));
         }
         
-        self.user_repo.remove_role(user_id, role)
+        self.user_repo.remove_role(user_id, role).await?;
+
+        self.audit.record(AuditEntry::new(AuditAction::RoleRemoved).actor(actor_id).target(user_id).with("role", role)).await;
+        Ok(())
     }
 }
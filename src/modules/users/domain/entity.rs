@@ -16,6 +16,8 @@ pub struct User {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub last_login_at: Option<NaiveDateTime>,
+    pub locale: Option<String>,
+    pub email_undeliverable: bool,
 }
 
 #[derive(Debug, Insertable)]
@@ -23,4 +25,6 @@ pub struct User {
 pub struct NewUser {
     pub email: String,
     pub password_hash: String,
+    pub locale: Option<String>,
 }
+pub mod notification;
@@ -0,0 +1,98 @@
+use chrono::NaiveDateTime;
+use diesel::{Queryable, Selectable, Insertable, Identifiable, Associations};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+use uuid::Uuid;
+use crate::schema::notification_preferences;
+use crate::modules::users::domain::entity::User;
+
+/// Purpose bound into signed unsubscribe links.
+pub const UNSUBSCRIBE_TOKEN_PURPOSE: &str = "unsubscribe";
+
+/// How long unsubscribe links work. People unsubscribe from old mail, but a
+/// leaked link, or one signed with a retired key, shouldn't work forever.
+pub const UNSUBSCRIBE_TOKEN_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// Email verification, password resets and other mail about the account
+    /// itself. Always sent.
+    Account,
+    /// Sign-ins from new devices or locations.
+    SecurityAlerts,
+    /// Periodic activity digests.
+    Digest,
+    /// Comments on the user's posts.
+    Comments,
+}
+
+impl NotificationCategory {
+    /// Transactional mail bypasses preferences and carries no unsubscribe link.
+    pub fn is_transactional(self) -> bool {
+        matches!(self, NotificationCategory::Account)
+    }
+
+    /// Whether mail of this category should be sent given the user's stored
+    /// choice, if any. Categories are opt-out.
+    pub fn is_enabled(self, stored: Option<bool>) -> bool {
+        self.is_transactional() || stored.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable, Associations)]
+#[diesel(belongs_to(User))]
+#[diesel(primary_key(user_id, category))]
+#[diesel(table_name = notification_preferences)]
+pub struct NotificationPreference {
+    pub user_id: Uuid,
+    pub category: String,
+    pub enabled: bool,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = notification_preferences)]
+pub struct NewNotificationPreference {
+    pub user_id: Uuid,
+    pub category: String,
+    pub enabled: bool,
+}
+
+/// What an unsubscribe link points at, serialized as `"{user_id}:{category}"`
+/// before being signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsubscribeRequest {
+    pub user_id: Uuid,
+    pub category: NotificationCategory,
+}
+
+impl UnsubscribeRequest {
+    pub fn to_token_data(self) -> String {
+        format!("{}:{}", self.user_id, self.category)
+    }
+
+    pub fn from_token_data(data: &str) -> Option<Self> {
+        let (user_id, category) = data.split_once(':')?;
+        Some(Self {
+            user_id: user_id.parse().ok()?,
+            category: category.parse().ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsubscribe_request_round_trip() {
+        let request = UnsubscribeRequest { user_id: Uuid::new_v4(), category: NotificationCategory::SecurityAlerts };
+
+        assert_eq!(UnsubscribeRequest::from_token_data(&request.to_token_data()), Some(request));
+        assert_eq!(UnsubscribeRequest::from_token_data("not-a-uuid:digest"), None);
+        assert!(NotificationCategory::Account.is_enabled(Some(false)));
+        assert!(!NotificationCategory::Digest.is_enabled(Some(false)));
+    }
+}
@@ -1,18 +1,75 @@
+use async_trait::async_trait;
 use uuid::Uuid;
 use super::entity::{User, NewUser};
 use crate::common::errors::AppError;
 
-pub trait UserRepository {
-    fn create(&self, new_user: NewUser) -> Result<User, AppError>;
-    fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError>;
-    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError>;
-    fn verify_user(&self, id: Uuid) -> Result<(), AppError>;
-    fn get_roles(&self, user_id: Uuid) -> Result<Vec<String>, AppError>;
-    fn add_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError>;
-    fn remove_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError>;
-    fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError>;
-    fn update_last_login(&self, user_id: Uuid) -> Result<(), AppError>;
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, new_user: NewUser) -> Result<User, AppError>;
+    /// Ignores case; the oldest of several matches wins.
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError>;
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError>;
+    async fn verify_user(&self, id: Uuid) -> Result<(), AppError>;
+    async fn set_active(&self, id: Uuid, is_active: bool) -> Result<(), AppError>;
+    /// Users ordered by sign-up date, oldest first.
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError>;
+    async fn get_roles(&self, user_id: Uuid) -> Result<Vec<String>, AppError>;
+    async fn add_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError>;
+    async fn remove_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError>;
+    async fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError>;
+    async fn update_last_login(&self, user_id: Uuid, at: chrono::NaiveDateTime) -> Result<(), AppError>;
+    async fn set_email_undeliverable(&self, user_id: Uuid, undeliverable: bool) -> Result<(), AppError>;
 }
 
+/// Lets services hold a shared, swappable `Arc<dyn UserRepository>`.
+#[async_trait]
+impl<T: UserRepository + ?Sized> UserRepository for std::sync::Arc<T> {
+    async fn create(&self, new_user: NewUser) -> Result<User, AppError> {
+        (**self).create(new_user).await
+    }
 
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        (**self).find_by_email(email).await
+    }
 
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError> {
+        (**self).find_by_id(id).await
+    }
+
+    async fn verify_user(&self, id: Uuid) -> Result<(), AppError> {
+        (**self).verify_user(id).await
+    }
+
+    async fn set_active(&self, id: Uuid, is_active: bool) -> Result<(), AppError> {
+        (**self).set_active(id, is_active).await
+    }
+
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError> {
+        (**self).list(limit, offset).await
+    }
+
+    async fn get_roles(&self, user_id: Uuid) -> Result<Vec<String>, AppError> {
+        (**self).get_roles(user_id).await
+    }
+
+    async fn add_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError> {
+        (**self).add_role(user_id, role_name).await
+    }
+
+    async fn remove_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError> {
+        (**self).remove_role(user_id, role_name).await
+    }
+
+    async fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError> {
+        (**self).update_password(user_id, new_password_hash).await
+    }
+
+    async fn update_last_login(&self, user_id: Uuid, at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        (**self).update_last_login(user_id, at).await
+    }
+
+    async fn set_email_undeliverable(&self, user_id: Uuid, undeliverable: bool) -> Result<(), AppError> {
+        (**self).set_email_undeliverable(user_id, undeliverable).await
+    }
+}
+pub mod notification;
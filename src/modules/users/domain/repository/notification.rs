@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::modules::users::domain::entity::notification::{NotificationCategory, NotificationPreference, NewNotificationPreference};
+use crate::common::errors::AppError;
+
+#[async_trait]
+pub trait NotificationPreferenceRepository: Send + Sync {
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Vec<NotificationPreference>, AppError>;
+    async fn find(&self, user_id: Uuid, category: NotificationCategory) -> Result<Option<NotificationPreference>, AppError>;
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<(), AppError>;
+}
+
+/// Lets services hold a shared, swappable `Arc<dyn NotificationPreferenceRepository>`.
+#[async_trait]
+impl<T: NotificationPreferenceRepository + ?Sized> NotificationPreferenceRepository for std::sync::Arc<T> {
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Vec<NotificationPreference>, AppError> {
+        (**self).find_by_user(user_id).await
+    }
+
+    async fn find(&self, user_id: Uuid, category: NotificationCategory) -> Result<Option<NotificationPreference>, AppError> {
+        (**self).find(user_id, category).await
+    }
+
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<(), AppError> {
+        (**self).upsert(preference).await
+    }
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use uuid::Uuid;
+use crate::common::{database::{self, DbHandle}, errors::AppError};
+use crate::modules::users::domain::{
+    entity::notification::{NotificationCategory, NotificationPreference, NewNotificationPreference},
+    repository::notification::NotificationPreferenceRepository,
+};
+use crate::schema::notification_preferences;
+use tracing::instrument;
+
+pub struct DieselNotificationPreferenceRepository {
+    db: DbHandle,
+}
+
+impl DieselNotificationPreferenceRepository {
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
+    }
+}
+
+#[async_trait]
+impl NotificationPreferenceRepository for DieselNotificationPreferenceRepository {
+    #[instrument(name = "NotificationPreferenceRepository::find_by_user", skip_all)]
+    async fn find_by_user(&self, user_id_val: Uuid) -> Result<Vec<NotificationPreference>, AppError> {
+        database::run(&self.db, move |conn| {
+            notification_preferences::table
+                .filter(notification_preferences::user_id.eq(user_id_val))
+                .load::<NotificationPreference>(conn)
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "NotificationPreferenceRepository::find", skip_all)]
+    async fn find(&self, user_id_val: Uuid, category: NotificationCategory) -> Result<Option<NotificationPreference>, AppError> {
+        database::run(&self.db, move |conn| {
+            notification_preferences::table
+                .find((user_id_val, category.to_string()))
+                .first::<NotificationPreference>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
+    }
+
+    #[instrument(name = "NotificationPreferenceRepository::upsert", skip_all)]
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(notification_preferences::table)
+                .values(&preference)
+                .on_conflict((notification_preferences::user_id, notification_preferences::category))
+                .do_update()
+                .set((
+                    notification_preferences::enabled.eq(excluded(notification_preferences::enabled)),
+                    notification_preferences::updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+}
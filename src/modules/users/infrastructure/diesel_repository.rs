@@ -1,145 +1,189 @@
+use async_trait::async_trait;
 use diesel::prelude::*;
 use uuid::Uuid;
-use crate::common::{database::DbPool, errors::AppError};
+use crate::common::{database::{self, DbHandle}, errors::AppError};
 use crate::modules::users::domain::{entity::{User, NewUser}, repository::UserRepository};
 use crate::schema::users;
+use tracing::instrument;
+
+define_sql_function!(fn lower(text: diesel::sql_types::Text) -> diesel::sql_types::Text);
 
 pub struct DieselUserRepository {
-    pool: DbPool,
+    db: DbHandle,
 }
 
 impl DieselUserRepository {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(db: impl Into<DbHandle>) -> Self {
+        Self { db: db.into() }
     }
 }
 
+#[async_trait]
 impl UserRepository for DieselUserRepository {
-    fn create(&self, new_user: NewUser) -> Result<User, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::insert_into(users::table)
-            .values(&new_user)
-            .get_result(&mut conn)
-            .map_err(AppError::from)
+    #[instrument(name = "UserRepository::create", skip_all)]
+    async fn create(&self, new_user: NewUser) -> Result<User, AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::insert_into(users::table)
+                .values(&new_user)
+                .get_result(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
-        let mut conn = self.pool.get().map_err(|e| {
-            tracing::error!("Failed to get DB connection: {}", e);
-            AppError::InternalError
-        })?;
-        
-        users::table
-            .filter(users::email.eq(email))
-            .first::<User>(&mut conn)
-            .optional()
-            .map_err(AppError::from)
+    #[instrument(name = "UserRepository::find_by_email", skip_all)]
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let email = email.to_string();
+        database::run(&self.db, move |conn| {
+            users::table
+                .filter(lower(users::email).eq(email.to_lowercase()))
+                .order(users::created_at.asc())
+                .first::<User>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        users::table
-            .find(id)
-            .first::<User>(&mut conn)
-            .optional()
-            .map_err(AppError::from)
+    #[instrument(name = "UserRepository::find_by_id", skip_all)]
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError> {
+        database::run(&self.db, move |conn| {
+            users::table
+                .find(id)
+                .first::<User>(conn)
+                .optional()
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn verify_user(&self, id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
+    #[instrument(name = "UserRepository::verify_user", skip_all)]
+    async fn verify_user(&self, id: Uuid) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(users::table.find(id))
+                .set(users::is_verified.eq(true))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
 
-        diesel::update(users::table.find(id))
-            .set(users::is_verified.eq(true))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "UserRepository::set_active", skip_all)]
+    async fn set_active(&self, id: Uuid, is_active: bool) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(users::table.find(id))
+                .set((users::is_active.eq(is_active), users::updated_at.eq(diesel::dsl::now)))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn get_roles(&self, user_id_val: Uuid) -> Result<Vec<String>, AppError> {
-        use crate::schema::{roles, user_roles};
-        
-        let mut conn = self.pool.get().map_err(|e| {
-            tracing::error!("Failed to get DB connection in get_roles: {}", e);
-            AppError::InternalError
-        })?;
-        
-        user_roles::table
-            .filter(user_roles::user_id.eq(user_id_val))
-            .inner_join(roles::table)
-            .select(roles::name)
-            .load::<String>(&mut conn)
-            .map_err(|e| {
-                tracing::error!("Failed to load roles: {}", e);
-                AppError::from(e)
-            })
+    #[instrument(name = "UserRepository::list", skip_all)]
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError> {
+        database::run(&self.db, move |conn| {
+            users::table
+                .order(users::created_at.asc())
+                .limit(limit)
+                .offset(offset)
+                .load::<User>(conn)
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn add_role(&self, user_id_val: Uuid, role_name: &str) -> Result<(), AppError> {
+    #[instrument(name = "UserRepository::get_roles", skip_all)]
+    async fn get_roles(&self, user_id_val: Uuid) -> Result<Vec<String>, AppError> {
         use crate::schema::{roles, user_roles};
-        
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        // 1. Find role id
-        let role_id_val: i32 = roles::table
-            .filter(roles::name.eq(role_name))
-            .select(roles::id)
-            .first(&mut conn)
-            .map_err(|_| AppError::NotFound(format!("Role {} not found", role_name)))?;
-            
-        // 2. Insert into user_roles
-        diesel::insert_into(user_roles::table)
-            .values((
-                user_roles::user_id.eq(user_id_val),
-                user_roles::role_id.eq(role_id_val)
-            ))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+
+        database::run(&self.db, move |conn| {
+            user_roles::table
+                .filter(user_roles::user_id.eq(user_id_val))
+                .inner_join(roles::table)
+                .select(roles::name)
+                .load::<String>(conn)
+                .map_err(|e| {
+                    tracing::error!("Failed to load roles: {}", e);
+                    AppError::from(e)
+                })
+        }).await
     }
 
-    fn remove_role(&self, user_id_val: Uuid, role_name: &str) -> Result<(), AppError> {
+    #[instrument(name = "UserRepository::add_role", skip_all)]
+    async fn add_role(&self, user_id_val: Uuid, role_name: &str) -> Result<(), AppError> {
         use crate::schema::{roles, user_roles};
-        
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        // 1. Find role id
-        let role_id_val: i32 = roles::table
-            .filter(roles::name.eq(role_name))
-            .select(roles::id)
-            .first(&mut conn)
-            .map_err(|_| AppError::NotFound(format!("Role {} not found", role_name)))?;
-            
-        // 2. Delete from user_roles
-        diesel::delete(user_roles::table)
-            .filter(user_roles::user_id.eq(user_id_val))
-            .filter(user_roles::role_id.eq(role_id_val))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
-    }
 
-    fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(users::table.find(user_id))
-            .set(users::password_hash.eq(new_password_hash))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+        let role_name = role_name.to_string();
+        database::run(&self.db, move |conn| {
+            // 1. Find role id
+            let role_id_val: i32 = roles::table
+                .filter(roles::name.eq(&role_name))
+                .select(roles::id)
+                .first(conn)
+                .map_err(|_| AppError::NotFound(format!("Role {} not found", role_name)))?;
+
+            // 2. Insert into user_roles
+            diesel::insert_into(user_roles::table)
+                .values((
+                    user_roles::user_id.eq(user_id_val),
+                    user_roles::role_id.eq(role_id_val)
+                ))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
     }
 
-    fn update_last_login(&self, user_id: Uuid) -> Result<(), AppError> {
-        let mut conn = self.pool.get().map_err(|_| AppError::InternalError)?;
-        
-        diesel::update(users::table.find(user_id))
-            .set(users::last_login_at.eq(chrono::Utc::now().naive_utc()))
-            .execute(&mut conn)
-            .map(|_| ())
-            .map_err(AppError::from)
+    #[instrument(name = "UserRepository::remove_role", skip_all)]
+    async fn remove_role(&self, user_id_val: Uuid, role_name: &str) -> Result<(), AppError> {
+        use crate::schema::{roles, user_roles};
+
+        let role_name = role_name.to_string();
+        database::run(&self.db, move |conn| {
+            // 1. Find role id
+            let role_id_val: i32 = roles::table
+                .filter(roles::name.eq(&role_name))
+                .select(roles::id)
+                .first(conn)
+                .map_err(|_| AppError::NotFound(format!("Role {} not found", role_name)))?;
+
+            // 2. Delete from user_roles
+            diesel::delete(user_roles::table)
+                .filter(user_roles::user_id.eq(user_id_val))
+                .filter(user_roles::role_id.eq(role_id_val))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
     }
-}
 
+    #[instrument(name = "UserRepository::update_password", skip_all)]
+    async fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError> {
+        let new_password_hash = new_password_hash.to_string();
+        database::run(&self.db, move |conn| {
+            diesel::update(users::table.find(user_id))
+                .set(users::password_hash.eq(new_password_hash))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
 
+    #[instrument(name = "UserRepository::update_last_login", skip_all)]
+    async fn update_last_login(&self, user_id: Uuid, at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(users::table.find(user_id))
+                .set(users::last_login_at.eq(at))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
 
+    #[instrument(name = "UserRepository::set_email_undeliverable", skip_all)]
+    async fn set_email_undeliverable(&self, user_id: Uuid, undeliverable: bool) -> Result<(), AppError> {
+        database::run(&self.db, move |conn| {
+            diesel::update(users::table.find(user_id))
+                .set(users::email_undeliverable.eq(undeliverable))
+                .execute(conn)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }).await
+    }
+}
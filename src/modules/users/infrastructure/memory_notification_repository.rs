@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::common::errors::AppError;
+use crate::modules::users::domain::{
+    entity::notification::{NotificationCategory, NotificationPreference, NewNotificationPreference},
+    repository::notification::NotificationPreferenceRepository,
+};
+
+/// Keeps notification preferences in memory for tests. Clones share the same data.
+#[derive(Clone, Default)]
+pub struct InMemoryNotificationPreferenceRepository {
+    preferences: Arc<Mutex<Vec<NotificationPreference>>>,
+}
+
+impl InMemoryNotificationPreferenceRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NotificationPreferenceRepository for InMemoryNotificationPreferenceRepository {
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Vec<NotificationPreference>, AppError> {
+        Ok(self.preferences.lock().unwrap().iter().filter(|p| p.user_id == user_id).cloned().collect())
+    }
+
+    async fn find(&self, user_id: Uuid, category: NotificationCategory) -> Result<Option<NotificationPreference>, AppError> {
+        let category = category.to_string();
+        Ok(self.preferences.lock().unwrap().iter().find(|p| p.user_id == user_id && p.category == category).cloned())
+    }
+
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<(), AppError> {
+        let mut preferences = self.preferences.lock().unwrap();
+        preferences.retain(|p| !(p.user_id == preference.user_id && p.category == preference.category));
+        preferences.push(NotificationPreference {
+            user_id: preference.user_id,
+            category: preference.category,
+            enabled: preference.enabled,
+            updated_at: Utc::now().naive_utc(),
+        });
+        Ok(())
+    }
+}
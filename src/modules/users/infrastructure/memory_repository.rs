@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::common::errors::AppError;
+use crate::modules::users::domain::{entity::{User, NewUser}, repository::UserRepository};
+
+/// Roles seeded by the `create_roles` migration.
+const ROLES: [&str; 2] = ["user", "admin"];
+
+/// Keeps users in memory for tests. Clones share the same data.
+#[derive(Clone, Default)]
+pub struct InMemoryUserRepository {
+    users: Arc<Mutex<Vec<User>>>,
+    roles: Arc<Mutex<Vec<(Uuid, String)>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, id: Uuid, f: impl FnOnce(&mut User)) -> Result<(), AppError> {
+        if let Some(user) = self.users.lock().unwrap().iter_mut().find(|u| u.id == id) {
+            f(user);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create(&self, new_user: NewUser) -> Result<User, AppError> {
+        let mut users = self.users.lock().unwrap();
+        if users.iter().any(|u| u.email == new_user.email) {
+            return Err(AppError::Conflict("Email already exists".to_string()));
+        }
+
+        let now = Utc::now().naive_utc();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: new_user.email,
+            password_hash: new_user.password_hash,
+            is_active: true,
+            is_verified: false,
+            created_at: now,
+            updated_at: now,
+            last_login_at: None,
+            locale: new_user.locale,
+            email_undeliverable: false,
+        };
+        users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let email = email.to_lowercase();
+        Ok(self.users.lock().unwrap().iter().find(|u| u.email.to_lowercase() == email).cloned())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError> {
+        Ok(self.users.lock().unwrap().iter().find(|u| u.id == id).cloned())
+    }
+
+    async fn verify_user(&self, id: Uuid) -> Result<(), AppError> {
+        self.update(id, |u| u.is_verified = true)
+    }
+
+    async fn set_active(&self, id: Uuid, is_active: bool) -> Result<(), AppError> {
+        self.update(id, |u| {
+            u.is_active = is_active;
+            u.updated_at = Utc::now().naive_utc();
+        })
+    }
+
+    async fn list(&self, limit: i64, offset: i64) -> Result<Vec<User>, AppError> {
+        let mut users = self.users.lock().unwrap().clone();
+        users.sort_by_key(|u| u.created_at);
+        Ok(users.into_iter().skip(offset.max(0) as usize).take(limit.max(0) as usize).collect())
+    }
+
+    async fn get_roles(&self, user_id: Uuid) -> Result<Vec<String>, AppError> {
+        Ok(self.roles.lock().unwrap().iter()
+            .filter(|(id, _)| *id == user_id)
+            .map(|(_, role)| role.clone())
+            .collect())
+    }
+
+    async fn add_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError> {
+        if !ROLES.contains(&role_name) {
+            return Err(AppError::NotFound(format!("Role {} not found", role_name)));
+        }
+        let mut roles = self.roles.lock().unwrap();
+        if !roles.iter().any(|(id, role)| *id == user_id && role == role_name) {
+            roles.push((user_id, role_name.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn remove_role(&self, user_id: Uuid, role_name: &str) -> Result<(), AppError> {
+        if !ROLES.contains(&role_name) {
+            return Err(AppError::NotFound(format!("Role {} not found", role_name)));
+        }
+        self.roles.lock().unwrap().retain(|(id, role)| !(*id == user_id && role == role_name));
+        Ok(())
+    }
+
+    async fn update_password(&self, user_id: Uuid, new_password_hash: &str) -> Result<(), AppError> {
+        self.update(user_id, |u| u.password_hash = new_password_hash.to_string())
+    }
+
+    async fn update_last_login(&self, user_id: Uuid, at: chrono::NaiveDateTime) -> Result<(), AppError> {
+        self.update(user_id, |u| u.last_login_at = Some(at))
+    }
+
+    async fn set_email_undeliverable(&self, user_id: Uuid, undeliverable: bool) -> Result<(), AppError> {
+        self.update(user_id, |u| u.email_undeliverable = undeliverable)
+    }
+}
@@ -1 +1,6 @@
 pub mod diesel_repository;
+pub mod diesel_notification_repository;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory_repository;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory_notification_repository;
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use crate::modules::users::domain::entity::User;
+use crate::modules::users::domain::entity::{User, notification::NotificationCategory};
 use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -8,6 +8,8 @@ pub struct UserDto {
     pub id: Uuid,
     pub email: String,
     pub is_verified: bool,
+    /// Set when mail to this address hard-bounced or was marked as spam.
+    pub email_undeliverable: bool,
     pub created_at: String,
 }
 
@@ -17,6 +19,7 @@ impl From<User> for UserDto {
             id: user.id,
             email: user.email,
             is_verified: user.is_verified,
+            email_undeliverable: user.email_undeliverable,
             created_at: user.created_at.to_string(),
         }
     }
@@ -27,3 +30,26 @@ pub struct AssignRoleDto {
     #[validate(length(min = 1))]
     pub role: String,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationPreferenceDto {
+    pub category: NotificationCategory,
+    pub enabled: bool,
+}
+
+impl From<(NotificationCategory, bool)> for NotificationPreferenceDto {
+    fn from((category, enabled): (NotificationCategory, bool)) -> Self {
+        Self { category, enabled }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateNotificationPreferencesDto {
+    #[validate(length(min = 1))]
+    pub preferences: Vec<NotificationPreferenceDto>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQueryDto {
+    pub token: String,
+}
@@ -1,55 +1,87 @@
+use std::sync::Arc;
 use actix_web::{web, HttpResponse};
 use uuid::Uuid;
 use validator::Validate;
-use crate::common::{database::DbPool, errors::AppError};
+use crate::app::AppState;
+use crate::common::{errors::AppError, signed_token::TokenSigner};
 use crate::modules::users::{
-    application::service::UserService,
-    infrastructure::diesel_repository::DieselUserRepository,
+    application::{notification_service::NotificationPreferenceService, service::UserService},
+    domain::repository::{notification::NotificationPreferenceRepository, UserRepository},
 };
 use crate::modules::auth::interfaces::http::middleware::{AuthenticatedUser, RequireAdmin};
-use super::dto::{UserDto, AssignRoleDto};
+use super::dto::{UserDto, AssignRoleDto, NotificationPreferenceDto, UpdateNotificationPreferencesDto, UnsubscribeQueryDto};
 
-// Type alias
-type UserServiceImpl = UserService<DieselUserRepository>;
-
-pub fn user_service_factory(pool: &DbPool) -> UserServiceImpl {
-    let user_repo = DieselUserRepository::new(pool.clone());
-    UserService::new(user_repo)
-}
+// Type aliases
+pub type UserServiceImpl = UserService<Arc<dyn UserRepository>>;
+pub type NotificationPreferenceServiceImpl = NotificationPreferenceService<Arc<dyn NotificationPreferenceRepository>>;
 
 pub async fn get_me(
     user: AuthenticatedUser,
-    pool: web::Data<DbPool>,
+    state: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
-    let service = user_service_factory(&pool);
-    let user_entity = service.find_user_by_id(user.user_id)?;
+    let user_entity = state.users.find_user_by_id(user.user_id).await?;
     Ok(HttpResponse::Ok().json(UserDto::from(user_entity)))
 }
 
 pub async fn assign_role(
-    _admin: RequireAdmin, // Guard
-    pool: web::Data<DbPool>,
+    admin: RequireAdmin,
+    state: web::Data<AppState>,
     path: web::Path<Uuid>,
     body: web::Json<AssignRoleDto>,
 ) -> Result<HttpResponse, AppError> {
     body.validate().map_err(AppError::ValidationError)?;
     let user_id = path.into_inner();
     
-    let service = user_service_factory(&pool);
-    service.assign_role(user_id, &body.role)?;
+    state.users.assign_role(user_id, &body.role, Some(admin.0.user_id)).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Role assigned successfully"})))
 }
 
 pub async fn remove_role(
-    _admin: RequireAdmin,
-    pool: web::Data<DbPool>,
+    admin: RequireAdmin,
+    state: web::Data<AppState>,
     path: web::Path<(Uuid, String)>,
 ) -> Result<HttpResponse, AppError> {
     let (user_id, role) = path.into_inner();
     
-    let service = user_service_factory(&pool);
-    service.remove_role(user_id, &role)?;
+    state.users.remove_role(user_id, &role, Some(admin.0.user_id)).await?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({"message": "Role removed successfully"})))
 }
+
+pub async fn get_notification_preferences(
+    user: AuthenticatedUser,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let preferences = state.notification_preferences.get_preferences(user.user_id).await?;
+
+    let dtos: Vec<NotificationPreferenceDto> = preferences.into_iter().map(NotificationPreferenceDto::from).collect();
+    Ok(HttpResponse::Ok().json(dtos))
+}
+
+pub async fn update_notification_preferences(
+    user: AuthenticatedUser,
+    state: web::Data<AppState>,
+    body: web::Json<UpdateNotificationPreferencesDto>,
+) -> Result<HttpResponse, AppError> {
+    body.validate().map_err(AppError::ValidationError)?;
+    let changes = body.into_inner().preferences.into_iter().map(|p| (p.category, p.enabled)).collect();
+
+    let preferences = state.notification_preferences.update_preferences(user.user_id, changes).await?;
+
+    let dtos: Vec<NotificationPreferenceDto> = preferences.into_iter().map(NotificationPreferenceDto::from).collect();
+    Ok(HttpResponse::Ok().json(dtos))
+}
+
+/// RFC 8058 one-click unsubscribe target of the `List-Unsubscribe` header.
+/// Mail providers POST here without any session, so the token is the only proof.
+pub async fn unsubscribe(
+    state: web::Data<AppState>,
+    query: web::Query<UnsubscribeQueryDto>,
+) -> Result<HttpResponse, AppError> {
+    let request = state.notification_preferences.unsubscribe(&TokenSigner::from_config(&state.config), &query.token).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("Unsubscribed from {} emails", request.category)
+    })))
+}
@@ -1,11 +1,17 @@
 use actix_web::web;
-use super::handlers::{get_me, assign_role, remove_role};
+use super::handlers::{get_me, assign_role, remove_role, get_notification_preferences, update_notification_preferences, unsubscribe};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/users")
             .route("/me", web::get().to(get_me))
+            .route("/me/notifications", web::get().to(get_notification_preferences))
+            .route("/me/notifications", web::put().to(update_notification_preferences))
             .route("/{id}/roles", web::post().to(assign_role))
             .route("/{id}/roles/{role}", web::delete().to(remove_role))
     );
+    cfg.service(
+        web::scope("/notifications")
+            .route("/unsubscribe", web::post().to(unsubscribe))
+    );
 }
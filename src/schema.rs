@@ -1,5 +1,35 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_events (id) {
+        id -> Int8,
+        occurred_at -> Timestamp,
+        action -> Varchar,
+        actor_id -> Nullable<Uuid>,
+        target_id -> Nullable<Uuid>,
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        request_id -> Nullable<Varchar>,
+        metadata -> Jsonb,
+        #[max_length = 64]
+        prev_hash -> Varchar,
+        #[max_length = 64]
+        hash -> Varchar,
+    }
+}
+
+diesel::table! {
+    email_suppressions (id) {
+        id -> Uuid,
+        email -> Varchar,
+        user_id -> Nullable<Uuid>,
+        reason -> Varchar,
+        description -> Nullable<Text>,
+        provider_event_id -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     email_verification_tokens (id) {
         id -> Uuid,
@@ -11,6 +41,50 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    job_schedules (name) {
+        name -> Varchar,
+        cron -> Varchar,
+        next_run_at -> Timestamp,
+        last_run_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    job_workers (id) {
+        id -> Varchar,
+        started_at -> Timestamp,
+        last_seen_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jobs (id) {
+        id -> Uuid,
+        kind -> Varchar,
+        payload -> Jsonb,
+        status -> Varchar,
+        unique_key -> Nullable<Varchar>,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        run_at -> Timestamp,
+        locked_at -> Nullable<Timestamp>,
+        locked_by -> Nullable<Varchar>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    notification_preferences (user_id, category) {
+        user_id -> Uuid,
+        category -> Varchar,
+        enabled -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     password_reset_tokens (id) {
         id -> Uuid,
@@ -63,6 +137,22 @@ diesel::table! {
         expires_at -> Timestamp,
         created_at -> Timestamp,
         last_used_at -> Timestamp,
+        browser -> Nullable<Varchar>,
+        browser_version -> Nullable<Varchar>,
+        os -> Nullable<Varchar>,
+        os_version -> Nullable<Varchar>,
+        device_class -> Nullable<Varchar>,
+        engine -> Nullable<Varchar>,
+        country_code -> Nullable<Varchar>,
+        country -> Nullable<Varchar>,
+        city -> Nullable<Varchar>,
+        asn -> Nullable<Int8>,
+        as_org -> Nullable<Varchar>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+        risk_score -> Int4,
+        revoked_at -> Nullable<Timestamp>,
+        reported_at -> Nullable<Timestamp>,
     }
 }
 
@@ -76,10 +166,14 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         last_login_at -> Nullable<Timestamp>,
+        locale -> Nullable<Varchar>,
+        email_undeliverable -> Bool,
     }
 }
 
+diesel::joinable!(email_suppressions -> users (user_id));
 diesel::joinable!(email_verification_tokens -> users (user_id));
+diesel::joinable!(notification_preferences -> users (user_id));
 diesel::joinable!(password_reset_tokens -> users (user_id));
 diesel::joinable!(posts -> users (author_id));
 diesel::joinable!(user_roles -> roles (role_id));
@@ -87,7 +181,13 @@ diesel::joinable!(user_roles -> users (user_id));
 diesel::joinable!(user_sessions -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    email_suppressions,
     email_verification_tokens,
+    job_schedules,
+    job_workers,
+    jobs,
+    notification_preferences,
     password_reset_tokens,
     posts,
     roles,
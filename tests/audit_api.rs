@@ -0,0 +1,109 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use diesel::RunQueryDsl;
+use serde_json::json;
+use rust_modular_hexagonal_api_template::app::build_app;
+use common::{bearer, call, TestContext, PASSWORD};
+
+#[actix_web::test]
+async fn test_security_actions_are_audited_and_queryable() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let alice = ctx.register_and_login(&app, "alice@example.com").await;
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/login")
+        .insert_header(("User-Agent", "audit-test/1.0"))
+        .peer_addr("127.0.0.1:40000".parse().unwrap())
+        .set_json(json!({ "email": "alice@example.com", "password": "WrongPassword1!" }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    ctx.register_and_login(&app, "admin@example.com").await;
+    ctx.grant_role("admin@example.com", "admin").await;
+    let admin = ctx.login(&app, "admin@example.com", PASSWORD).await;
+    let (_, admin_user) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&admin))).await;
+    let (_, alice_user) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&alice))).await;
+    let alice_id = alice_user["id"].as_str().unwrap();
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri(&format!("/users/{}/roles", alice_id))
+        .insert_header(bearer(&admin))
+        .set_json(json!({ "role": "admin" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Alice sees what happened to her account, newest first
+    let (status, activity) = call(&app, test::TestRequest::get()
+        .uri("/users/me/security-activity")
+        .insert_header(bearer(&alice))).await;
+    assert_eq!(status, StatusCode::OK);
+    let actions: Vec<&str> = activity.as_array().unwrap().iter().map(|e| e["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, ["role_assigned", "login_failed", "login_succeeded"]);
+    assert_eq!(activity[1]["details"]["reason"], "wrong_password");
+    assert_eq!(activity[1]["ip_address"], "127.0.0.1");
+    assert_eq!(activity[1]["user_agent"], "audit-test/1.0");
+    assert!(activity[0].get("actor_id").is_none());
+
+    // Admins can search every event
+    let (status, _) = call(&app, test::TestRequest::get().uri("/admin/audit/events").insert_header(bearer(&alice))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, events) = call(&app, test::TestRequest::get()
+        .uri(&format!("/admin/audit/events?action=role_assigned&target_id={}", alice_id))
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["actor_id"], admin_user["id"]);
+    assert_eq!(events[0]["metadata"]["role"], "admin");
+    let (status, _) = call(&app, test::TestRequest::get()
+        .uri("/admin/audit/events?action=dance")
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let request = test::TestRequest::get()
+        .uri("/admin/audit/events/export?format=csv&action=login_failed")
+        .insert_header(bearer(&admin));
+    let response = test::call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("content-type").unwrap(), "text/csv; charset=utf-8");
+    let csv = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("id,occurred_at,action,actor_id,target_id"));
+    assert!(lines[1].contains(",login_failed,,") && lines[1].contains(alice_id));
+
+    let (_, exports) = call(&app, test::TestRequest::get()
+        .uri("/admin/audit/events?action=audit_log_exported")
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(exports[0]["metadata"]["format"], "csv");
+    assert_eq!(exports[0]["metadata"]["rows"], 1);
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/admin/emails/test-send")
+        .insert_header(bearer(&admin))
+        .set_json(json!({ "template": "verification", "to": "someone@example.com" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, sends) = call(&app, test::TestRequest::get()
+        .uri("/admin/audit/events?action=test_email_sent")
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(sends[0]["actor_id"], admin_user["id"]);
+    assert_eq!(sends[0]["metadata"]["template"], "verification");
+    assert_eq!(sends[0]["metadata"]["to"], "someone@example.com");
+
+    let (status, verification) = call(&app, test::TestRequest::get().uri("/admin/audit/verify").insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verification["intact"], true);
+    assert!(verification["checked"].as_u64().unwrap() >= 6);
+}
+
+#[actix_web::test]
+async fn test_audit_events_are_append_only() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    ctx.register_and_login(&app, "alice@example.com").await;
+
+    let mut conn = ctx.pool.get().unwrap();
+    for statement in ["UPDATE audit_events SET ip_address = '10.9.8.7'", "DELETE FROM audit_events", "TRUNCATE audit_events"] {
+        let error = diesel::sql_query(statement).execute(&mut conn).unwrap_err();
+        assert!(error.to_string().contains("append-only"), "{}: {}", statement, error);
+    }
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use rust_modular_hexagonal_api_template::app::build_app;
+use common::{bearer, call, TestContext, PASSWORD};
+
+#[actix_web::test]
+async fn test_register_verify_login_refresh_logout() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let session = ctx.register_and_login(&app, "alice@example.com").await;
+
+    let (status, me) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&session))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], "alice@example.com");
+    assert_eq!(me["is_verified"], true);
+
+    let (status, refreshed) = call(&app, test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": session.refresh_token }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let new_access_token = refreshed["access_token"].as_str().unwrap().to_string();
+
+    // The rotated-out refresh token no longer works
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": session.refresh_token }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Replaying it revoked the session, so sign in again and log out
+    let session = ctx.login(&app, "alice@example.com", PASSWORD).await;
+    let (status, _) = call(&app, test::TestRequest::post().uri("/auth/logout").insert_header(bearer(&session))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&session))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call(&app, test::TestRequest::get()
+        .uri("/users/me")
+        .insert_header(("Authorization", format!("Bearer {}", new_access_token)))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_register_rejects_invalid_and_duplicate_input() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, body) = call(&app, test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "email": "not-an-email", "password": PASSWORD }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["details"]["email"].is_array());
+
+    ctx.register_and_login(&app, "bob@example.com").await;
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "email": "bob@example.com", "password": PASSWORD }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn test_reset_password() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    ctx.register_and_login(&app, "carol@example.com").await;
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/request-password-reset")
+        .set_json(json!({ "email": "carol@example.com" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let token = ctx.emails.last_token("carol@example.com", "password_reset").unwrap();
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/reset-password")
+        .set_json(json!({ "token": token, "new_password": "NewPassword456!" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": "carol@example.com", "password": PASSWORD }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    ctx.login(&app, "carol@example.com", "NewPassword456!").await;
+}
+
+#[actix_web::test]
+async fn test_revoke_other_sessions() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let phone = ctx.register_and_login(&app, "dave@example.com").await;
+    let laptop = ctx.login(&app, "dave@example.com", PASSWORD).await;
+
+    let (_, sessions) = call(&app, test::TestRequest::get().uri("/auth/sessions").insert_header(bearer(&laptop))).await;
+    assert_eq!(sessions.as_array().unwrap().len(), 2);
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/sessions/revoke-others")
+        .insert_header(bearer(&laptop))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&phone))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (_, sessions) = call(&app, test::TestRequest::get().uri("/auth/sessions").insert_header(bearer(&laptop))).await;
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["is_current"], true);
+}
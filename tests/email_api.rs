@@ -0,0 +1,39 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use rust_modular_hexagonal_api_template::app::build_app;
+use rust_modular_hexagonal_api_template::modules::email::domain::entity::{DeliveryEvent, DeliveryEventKind};
+use common::{bearer, call, TestContext, PASSWORD};
+
+#[actix_web::test]
+async fn test_bounce_flags_user_until_admin_unsuppresses() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let alice = ctx.register_and_login(&app, "alice@example.com").await;
+    ctx.register_and_login(&app, "admin@example.com").await;
+    ctx.grant_role("admin@example.com", "admin").await;
+    let admin = ctx.login(&app, "admin@example.com", PASSWORD).await;
+
+    // Providers don't keep the case the address was registered with
+    ctx.state.delivery_events.record(DeliveryEvent {
+        provider_event_id: "evt_1".to_string(),
+        kind: DeliveryEventKind::HardBounce,
+        recipients: vec!["Alice@Example.com".to_string()],
+        description: None,
+    }).await.unwrap();
+    let (_, me) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&alice))).await;
+    assert_eq!(me["email_undeliverable"], true);
+
+    let (status, _) = call(&app, test::TestRequest::delete()
+        .uri("/admin/emails/suppressions/alice@example.com")
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, me) = call(&app, test::TestRequest::get().uri("/users/me").insert_header(bearer(&alice))).await;
+    assert_eq!(me["email_undeliverable"], false);
+
+    let (status, _) = call(&app, test::TestRequest::delete()
+        .uri("/admin/emails/suppressions/alice@example.com")
+        .insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
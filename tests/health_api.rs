@@ -0,0 +1,34 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use chrono::Utc;
+use rust_modular_hexagonal_api_template::{
+    app::build_app,
+    modules::jobs::{domain::repository::JobRepository, infrastructure::diesel_repository::DieselJobRepository},
+};
+use common::{call, TestContext};
+
+#[actix_web::test]
+async fn test_readiness_reports_each_component() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, body) = call(&app, test::TestRequest::get().uri("/health/live")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "up");
+
+    // No worker runs in tests, so the queue is unattended
+    let (status, report) = call(&app, test::TestRequest::get().uri("/health/ready")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(report["status"], "down");
+    for component in ["database", "migrations", "email"] {
+        assert_eq!(report["checks"][component]["status"], "up", "{}", report);
+    }
+    assert_eq!(report["checks"]["job_worker"]["status"], "down");
+    assert_eq!(report["checks"]["job_worker"]["detail"], "no job worker has reported in");
+
+    DieselJobRepository::new(ctx.pool.clone()).heartbeat("test-worker", Utc::now().naive_utc()).await.unwrap();
+    let (status, report) = call(&app, test::TestRequest::get().uri("/health/ready")).await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(report["status"], "up");
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use serde_json::json;
+use rust_modular_hexagonal_api_template::{
+    modules::jobs::{domain::{entity::NewJob, repository::JobRepository}, infrastructure::diesel_repository::DieselJobRepository},
+    schema::jobs,
+};
+use common::TestContext;
+
+#[actix_web::test]
+async fn test_only_jobs_of_dead_workers_are_released() {
+    let Some(ctx) = TestContext::new() else { return };
+    let job_repo = DieselJobRepository::new(ctx.pool.clone());
+    let now = Utc::now().naive_utc();
+
+    job_repo.heartbeat("alive", now).await.unwrap();
+    job_repo.heartbeat("dead", now - Duration::hours(1)).await.unwrap();
+
+    let long_running = job_repo.enqueue(NewJob::new("test.slow", json!({})).run_at(now - Duration::minutes(3))).await.unwrap().unwrap();
+    let retried = job_repo.enqueue(NewJob::new("test.crash", json!({})).run_at(now - Duration::minutes(2))).await.unwrap().unwrap();
+    let exhausted = job_repo.enqueue(NewJob::new("test.crash", json!({})).run_at(now - Duration::minutes(1)).max_attempts(1)).await.unwrap().unwrap();
+    assert_eq!(job_repo.claim("alive", 1).await.unwrap()[0].id, long_running.id);
+    assert_eq!(job_repo.claim("dead", 2).await.unwrap().len(), 2);
+
+    let released = job_repo.release_stale(now - Duration::minutes(5)).await.unwrap();
+    assert_eq!(released, (1, 1));
+
+    let mut conn = ctx.pool.get().unwrap();
+    let status = |id| jobs::table.find(id).select(jobs::status).first::<String>(&mut conn).unwrap();
+    let statuses = [long_running.id, retried.id, exhausted.id].map(status);
+    assert_eq!(statuses, ["running", "pending", "failed"]);
+}
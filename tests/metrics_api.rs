@@ -0,0 +1,51 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use rust_modular_hexagonal_api_template::{app::build_app, common::config::Secret};
+use common::{call, TestContext};
+
+#[actix_web::test]
+async fn test_metrics_require_the_token() {
+    let Some(ctx) = TestContext::with_config(|config| config.metrics.token = Some(Secret::new("scrape-me"))) else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    ctx.register_and_login(&app, "metrics@example.com").await;
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "email": "metrics@example.com", "password": "WrongPassword1!" }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call(&app, test::TestRequest::get().uri("/metrics")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call(&app, test::TestRequest::get().uri("/metrics").insert_header(("Authorization", "Bearer nope"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let request = test::TestRequest::get()
+        .uri("/metrics")
+        .insert_header(("Authorization", "Bearer scrape-me"));
+    let response = test::call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+
+    for expected in [
+        r#"http_requests_total{method="POST",route="/auth/login",status="401"}"#,
+        r#"http_request_duration_seconds_bucket{method="POST",route="/auth/register",status="201""#,
+        r#"logins_total{outcome="failure",reason="wrong_password"}"#,
+        r#"logins_total{outcome="success",reason=""}"#,
+        r#"db_pool_connections{state="idle"}"#,
+        "db_pool_wait_seconds_count",
+        "active_sessions",
+    ] {
+        assert!(body.contains(expected), "missing {} in:\n{}", expected, body);
+    }
+}
+
+#[actix_web::test]
+async fn test_metrics_are_not_served_without_a_token() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, _) = call(&app, test::TestRequest::get().uri("/metrics")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use rust_modular_hexagonal_api_template::app::build_app;
+use common::{bearer, call, TestContext, PASSWORD};
+
+#[actix_web::test]
+async fn test_post_permissions() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, _) = call(&app, test::TestRequest::post()
+        .uri("/posts")
+        .set_json(json!({ "title": "Anonymous", "content": "Nope" }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let author = ctx.register_and_login(&app, "author@example.com").await;
+    let (status, post) = call(&app, test::TestRequest::post()
+        .uri("/posts")
+        .insert_header(bearer(&author))
+        .set_json(json!({ "title": "Hello", "content": "World" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/posts/{}", post["id"].as_str().unwrap());
+
+    let (status, posts) = call(&app, test::TestRequest::get().uri("/posts")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(posts.as_array().unwrap().len(), 1);
+
+    let stranger = ctx.register_and_login(&app, "stranger@example.com").await;
+    let (status, _) = call(&app, test::TestRequest::put()
+        .uri(&uri)
+        .insert_header(bearer(&stranger))
+        .set_json(json!({ "title": "Mine now", "content": "World", "is_published": true }))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Roles are read at sign-in, so the new admin signs in again
+    ctx.grant_role("stranger@example.com", "admin").await;
+    let admin = ctx.login(&app, "stranger@example.com", PASSWORD).await;
+    let (status, _) = call(&app, test::TestRequest::delete().uri(&uri).insert_header(bearer(&admin))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = call(&app, test::TestRequest::get().uri(&uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
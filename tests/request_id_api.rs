@@ -0,0 +1,28 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use rust_modular_hexagonal_api_template::app::build_app;
+use serde_json::Value;
+use uuid::Uuid;
+use common::TestContext;
+
+#[actix_web::test]
+async fn test_request_id_is_propagated_and_echoed_in_errors() {
+    let Some(ctx) = TestContext::new() else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let missing_post = format!("/posts/{}", Uuid::new_v4());
+
+    let request = test::TestRequest::get().uri(&missing_post).insert_header(("X-Request-Id", "edge-7f3a"));
+    let response = test::call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers().get("x-request-id").unwrap(), "edge-7f3a");
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["request_id"], "edge-7f3a");
+
+    // Without one, the API makes one up and reports the same id in both places
+    let response = test::call_service(&app, test::TestRequest::get().uri(&missing_post).to_request()).await;
+    let generated = response.headers().get("x-request-id").unwrap().to_str().unwrap().to_string();
+    assert!(Uuid::parse_str(&generated).is_ok());
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["request_id"], generated.as_str());
+}